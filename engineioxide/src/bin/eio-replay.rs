@@ -0,0 +1,108 @@
+//! Inspects and replays the engine.io sessions captured by a [`Recorder`](engineioxide::recorder::Recorder).
+//!
+//! ```text
+//! eio-replay dump <recording>
+//! eio-replay replay <recording> <url>
+//! ```
+//!
+//! `dump` prints every record of the recording.
+//! `replay` replays every session against a running engine.io server,
+//! e.g. `eio-replay replay session.eiorec ws://127.0.0.1:3000/engine.io/`,
+//! and fails if the server does not emit the recorded messages.
+use std::{process::ExitCode, time::Duration};
+
+use engineioxide::{
+    recorder::{Direction, Frame, RecordEvent, RecordReader},
+    replay, ProtocolVersion,
+};
+
+const USAGE: &str = "usage:\n  eio-replay dump <recording>\n  eio-replay replay <recording> <url>";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["dump", path] => dump(path),
+        ["replay", path, url] => replay(path, url).await,
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn dump(path: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    for record in RecordReader::open(path)? {
+        let record = record?;
+        let event = match record.event {
+            RecordEvent::Open {
+                protocol,
+                transport,
+            } => format!("open v{} {transport:?}", protocol as u8),
+            RecordEvent::Packet {
+                direction,
+                transport,
+                frame,
+            } => {
+                let arrow = match direction {
+                    Direction::Incoming => "<-",
+                    Direction::Outgoing => "->",
+                };
+                format!("{arrow} {} ({transport:?})", display_frame(&frame))
+            }
+            RecordEvent::Close { reason } => format!("close {reason:?}"),
+        };
+        println!(
+            "[{:>12.6}s] {} {event}",
+            record.time.as_secs_f64(),
+            record.sid
+        );
+    }
+    Ok(true)
+}
+
+async fn replay(path: &str, url: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let sessions = replay::sessions(RecordReader::open(path)?)?;
+    let mut success = true;
+    for session in &sessions {
+        let eio = match session.protocol {
+            ProtocolVersion::V3 => 3,
+            ProtocolVersion::V4 => 4,
+        };
+        let url = format!("{url}?EIO={eio}&transport=websocket");
+        let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+        let report = replay::replay_ws(ws, session, Duration::from_secs(5)).await?;
+        if report.matches() {
+            println!("{}: ok ({} messages)", session.sid, report.received.len());
+            continue;
+        }
+        success = false;
+        println!("{}: mismatch", session.sid);
+        let len = report.expected.len().max(report.received.len());
+        for i in 0..len {
+            let expected = report.expected.get(i);
+            let received = report.received.get(i);
+            if expected != received {
+                let show = |frame: Option<&Frame>| frame.map_or("<none>".into(), display_frame);
+                println!("  #{i} expected {}", show(expected));
+                println!("  #{i} received {}", show(received));
+            }
+        }
+    }
+    Ok(success)
+}
+
+fn display_frame(frame: &Frame) -> String {
+    match frame {
+        Frame::Text(data) => data.clone(),
+        Frame::Binary(data) => format!("<binary {data:?}>"),
+    }
+}
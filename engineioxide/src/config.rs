@@ -30,9 +30,18 @@
 //! let svc = EngineIoService::with_config(MyHandler, config);
 //! ```
 
-use std::{borrow::Cow, time::Duration};
+use std::{borrow::Cow, sync::Arc, time::Duration};
 
-use crate::service::TransportType;
+use bytes::BytesMut;
+use http::{HeaderName, HeaderValue};
+
+#[cfg(feature = "recorder")]
+use crate::recorder::Recorder;
+use crate::{
+    heartbeat::{FixedInterval, HeartbeatStrategy},
+    service::TransportType,
+    sid::Sid,
+};
 
 /// Configuration for the engine.io engine & transports
 #[derive(Debug, Clone)]
@@ -49,6 +58,18 @@ pub struct EngineIoConfig {
     /// Defaults to 20 seconds.
     pub ping_timeout: Duration,
 
+    /// The amount of time the server will wait for a client to complete a transport upgrade
+    /// (e.g. from polling to websocket) before cancelling it.
+    /// The client then keeps using the polling transport.
+    /// Defaults to 10 seconds.
+    pub upgrade_timeout: Duration,
+
+    /// The maximum amount of time the heartbeat timeout of a client can be extended
+    /// while reading from its transport is paused with [`Socket::pause_read`](crate::socket::Socket::pause_read).
+    /// Once it is reached the connection is closed with a heartbeat timeout, even if reading is still paused.
+    /// Defaults to 60 seconds.
+    pub max_read_pause: Duration,
+
     /// The maximum number of packets that can be buffered per connection before being emitted to the client.
     ///
     /// If the buffer if full the `emit()` method will return an error
@@ -63,6 +84,29 @@ pub struct EngineIoConfig {
     /// Allowed transports on this server
     /// It is represented as a bitfield to allow to combine any number of transports easily
     pub transports: u8,
+
+    /// The CORS policy applied to engine.io requests.
+    /// If it is set, preflight `OPTIONS` requests are answered directly
+    /// and requests from a non-allowed origin are rejected, websocket upgrades included.
+    /// Defaults to `None` (no CORS handling).
+    pub cors: Option<CorsConfig>,
+
+    /// A sticky-session cookie set on the handshake response (polling or websocket), with the session id as value.
+    /// It can be used by a load balancer to route every request of a session to the same server.
+    /// Defaults to `None` (no cookie).
+    pub cookie: Option<CookieConfig>,
+
+    /// The strategy used to compute the delay between two pings sent to a client.
+    /// See the [`heartbeat`](crate::heartbeat) module for more details.
+    /// Defaults to [`FixedInterval`] (a ping every `ping_interval`).
+    pub heartbeat: Arc<dyn HeartbeatStrategy>,
+
+    /// A recorder logging every packet of every session.
+    /// See the [`recorder`](crate::recorder) module for more details.
+    /// Defaults to `None` (no recording).
+    #[cfg(feature = "recorder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recorder")))]
+    pub recorder: Option<Arc<Recorder>>,
 }
 
 impl Default for EngineIoConfig {
@@ -71,9 +115,16 @@ impl Default for EngineIoConfig {
             req_path: "/engine.io".into(),
             ping_interval: Duration::from_millis(25000),
             ping_timeout: Duration::from_millis(20000),
+            upgrade_timeout: Duration::from_millis(10000),
+            max_read_pause: Duration::from_millis(60000),
             max_buffer_size: 128,
             max_payload: 1e5 as u64, // 100kb
             transports: TransportType::Polling as u8 | TransportType::Websocket as u8,
+            cors: None,
+            cookie: None,
+            heartbeat: Arc::new(FixedInterval),
+            #[cfg(feature = "recorder")]
+            recorder: None,
         }
     }
 }
@@ -125,6 +176,24 @@ impl EngineIoConfigBuilder {
         self
     }
 
+    /// The amount of time the server will wait for a client to complete a transport upgrade
+    /// (e.g. from polling to websocket) before cancelling it.
+    /// The client then keeps using the polling transport.
+    /// Defaults to 10 seconds.
+    pub fn upgrade_timeout(mut self, upgrade_timeout: Duration) -> Self {
+        self.config.upgrade_timeout = upgrade_timeout;
+        self
+    }
+
+    /// The maximum amount of time the heartbeat timeout of a client can be extended
+    /// while reading from its transport is paused with [`Socket::pause_read`](crate::socket::Socket::pause_read).
+    /// Once it is reached the connection is closed with a heartbeat timeout, even if reading is still paused.
+    /// Defaults to 60 seconds.
+    pub fn max_read_pause(mut self, max_read_pause: Duration) -> Self {
+        self.config.max_read_pause = max_read_pause;
+        self
+    }
+
     /// The maximum number of packets that can be buffered per connection before being emitted to the client.
     ///
     /// If the buffer if full the `emit()` method will return an error
@@ -176,11 +245,17 @@ impl EngineIoConfigBuilder {
     /// Allowed transports on this server
     ///
     /// The `transports` array should have a size of 1 or 2
+    /// (or 3 with the `webtransport` feature)
     ///
     /// Defaults to :
     /// `[TransportType::Polling, TransportType::Websocket]`
+    ///
+    /// The `WebTransport` transport is never enabled by default, it must be explicitly added here.
     pub fn transports<const N: usize>(mut self, transports: [TransportType; N]) -> Self {
+        #[cfg(not(feature = "webtransport"))]
         assert!(N > 0 && N <= 2);
+        #[cfg(feature = "webtransport")]
+        assert!(N > 0 && N <= 3);
         self.config.transports = 0;
         for transport in transports {
             self.config.transports |= transport as u8;
@@ -188,6 +263,84 @@ impl EngineIoConfigBuilder {
         self
     }
 
+    /// The CORS policy applied to engine.io requests.
+    /// If it is set, preflight `OPTIONS` requests are answered directly
+    /// and requests from a non-allowed origin are rejected, websocket upgrades included.
+    /// Defaults to `None` (no CORS handling).
+    ///
+    /// ```
+    /// # use engineioxide::config::{EngineIoConfig, CorsConfig};
+    /// let config = EngineIoConfig::builder()
+    ///     .cors(
+    ///         CorsConfig::new()
+    ///             .allow_origin("https://example.com")
+    ///             .allow_credentials(true),
+    ///     )
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    /// If credentials are allowed without a list of allowed origins:
+    /// any website could then make credentialed requests on behalf of its visitors.
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        assert!(
+            cors.origins.is_some() || !cors.credentials,
+            "CORS credentials cannot be allowed for any origin, use `CorsConfig::allow_origin`"
+        );
+        self.config.cors = Some(cors);
+        self
+    }
+
+    /// A sticky-session cookie set on the handshake response (polling or websocket), with the session id as value.
+    /// It can be used by a load balancer to route every request of a session to the same server.
+    /// Defaults to `None` (no cookie).
+    ///
+    /// ```
+    /// # use engineioxide::config::{EngineIoConfig, CookieConfig};
+    /// // Set a `io=<sid>; Path=/; HttpOnly; SameSite=Lax` cookie
+    /// let config = EngineIoConfig::builder()
+    ///     .cookie(CookieConfig::new())
+    ///     .build();
+    /// ```
+    pub fn cookie(mut self, cookie: CookieConfig) -> Self {
+        self.config.cookie = Some(cookie);
+        self
+    }
+
+    /// The strategy used to compute the delay between two pings sent to a client.
+    /// See the [`heartbeat`](crate::heartbeat) module for more details.
+    /// Defaults to [`FixedInterval`] (a ping every `ping_interval`).
+    ///
+    /// ```
+    /// # use engineioxide::config::EngineIoConfig;
+    /// # use engineioxide::heartbeat::Jitter;
+    /// // Each ping is sent between 90% and 100% of the ping interval
+    /// let config = EngineIoConfig::builder()
+    ///     .heartbeat(Jitter::new(0.1))
+    ///     .build();
+    /// ```
+    pub fn heartbeat(mut self, heartbeat: impl HeartbeatStrategy) -> Self {
+        self.config.heartbeat = Arc::new(heartbeat);
+        self
+    }
+
+    /// Records every packet of every session with the given [`Recorder`].
+    /// See the [`recorder`](crate::recorder) module for more details.
+    ///
+    /// ```no_run
+    /// # use engineioxide::config::EngineIoConfig;
+    /// # use engineioxide::recorder::Recorder;
+    /// let config = EngineIoConfig::builder()
+    ///     .recorder(Recorder::create("sessions.eiorec").unwrap())
+    ///     .build();
+    /// ```
+    #[cfg(feature = "recorder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recorder")))]
+    pub fn recorder(mut self, recorder: Recorder) -> Self {
+        self.config.recorder = Some(Arc::new(recorder));
+        self
+    }
+
     /// Build the config
     pub fn build(self) -> EngineIoConfig {
         self.config
@@ -199,6 +352,196 @@ impl Default for EngineIoConfigBuilder {
     }
 }
 
+/// CORS policy for engine.io requests
+///
+/// By default, any origin is allowed and credentials are not.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// The allowed origins, `None` means any origin
+    pub(crate) origins: Option<Vec<HeaderValue>>,
+    pub(crate) credentials: bool,
+    pub(crate) headers: Vec<HeaderName>,
+    pub(crate) max_age: Option<Duration>,
+}
+
+impl CorsConfig {
+    /// Create a new [`CorsConfig`] allowing any origin
+    pub fn new() -> Self {
+        Self {
+            origins: None,
+            credentials: false,
+            headers: Vec::new(),
+            max_age: None,
+        }
+    }
+
+    /// Add an allowed origin (e.g. `https://example.com`).
+    /// Once an origin is added, requests from any other origin are rejected.
+    ///
+    /// # Panics
+    /// If the origin is not a valid header value
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        let origin = HeaderValue::from_str(origin).expect("invalid CORS origin");
+        self.origins.get_or_insert_with(Vec::new).push(origin);
+        self
+    }
+
+    /// Allow the client to send credentials (cookies, authorization headers) with its requests.
+    /// Defaults to `false`.
+    ///
+    /// Credentials can only be allowed with a list of allowed origins,
+    /// otherwise [`EngineIoConfigBuilder::cors`] panics.
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Allow an extra request header in addition to `Content-Type`.
+    pub fn allow_header(mut self, header: HeaderName) -> Self {
+        self.headers.push(header);
+        self
+    }
+
+    /// How long the result of a preflight request can be cached by the client.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Check if a request coming from the given origin is allowed.
+    /// Requests without an `Origin` header don't come from a browser and are always allowed.
+    pub(crate) fn is_allowed(&self, origin: Option<&HeaderValue>) -> bool {
+        match (origin, &self.origins) {
+            (Some(origin), Some(origins)) => origins.contains(origin),
+            _ => true,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sticky-session cookie set on the handshake response (polling or websocket)
+///
+/// By default, the cookie is named `io`, with the `/` path, `HttpOnly` and `SameSite=Lax` attributes.
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    name: Cow<'static, str>,
+    path: Cow<'static, str>,
+    http_only: bool,
+    same_site: Option<Cow<'static, str>>,
+    /// The attributes of the cookie, formatted and validated once when the config is built
+    attributes: HeaderValue,
+}
+
+impl CookieConfig {
+    /// Create a new [`CookieConfig`] with the default attributes
+    pub fn new() -> Self {
+        Self {
+            name: "io".into(),
+            path: "/".into(),
+            http_only: true,
+            same_site: Some("Lax".into()),
+            attributes: HeaderValue::from_static("; Path=/; HttpOnly; SameSite=Lax"),
+        }
+    }
+
+    /// The name of the cookie.
+    /// Defaults to `io`.
+    ///
+    /// # Panics
+    /// If the name is empty or contains a character that is not allowed in a cookie name
+    /// (control characters, spaces and separators such as `=` or `;`).
+    pub fn name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        let name = name.into();
+        assert!(
+            !name.is_empty() && name.bytes().all(is_cookie_token),
+            "invalid cookie name: {name:?}"
+        );
+        self.name = name;
+        self
+    }
+
+    /// The path of the cookie.
+    /// Defaults to `/`.
+    ///
+    /// # Panics
+    /// If the path contains a control character or a `;`.
+    pub fn path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        let path = path.into();
+        assert!(
+            path.bytes().all(|b| (0x20..0x7f).contains(&b) && b != b';'),
+            "invalid cookie path: {path:?}"
+        );
+        self.path = path;
+        self.format_attributes()
+    }
+
+    /// Set the `HttpOnly` attribute of the cookie.
+    /// Defaults to `true`.
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self.format_attributes()
+    }
+
+    /// The `SameSite` attribute of the cookie (`Strict`, `Lax` or `None`), it is omitted if `None`.
+    /// Defaults to `Lax`.
+    ///
+    /// # Panics
+    /// If the value is not `Strict`, `Lax` or `None`.
+    pub fn same_site(mut self, same_site: Option<impl Into<Cow<'static, str>>>) -> Self {
+        let same_site = same_site.map(Into::into);
+        if let Some(same_site) = &same_site {
+            assert!(
+                ["Strict", "Lax", "None"].contains(&same_site.as_ref()),
+                "invalid cookie SameSite attribute: {same_site:?}"
+            );
+        }
+        self.same_site = same_site;
+        self.format_attributes()
+    }
+
+    /// Formats the attributes of the cookie once they have been validated
+    fn format_attributes(mut self) -> Self {
+        let mut attributes = format!("; Path={}", self.path);
+        if self.http_only {
+            attributes.push_str("; HttpOnly");
+        }
+        if let Some(same_site) = &self.same_site {
+            attributes.push_str("; SameSite=");
+            attributes.push_str(same_site);
+        }
+        self.attributes =
+            HeaderValue::try_from(attributes).expect("cookie attributes are validated");
+        self
+    }
+
+    /// The `Set-Cookie` header value for the given session id
+    pub(crate) fn to_header(&self, sid: Sid) -> HeaderValue {
+        let mut cookie = BytesMut::with_capacity(self.name.len() + 17 + self.attributes.len());
+        cookie.extend_from_slice(self.name.as_bytes());
+        cookie.extend_from_slice(b"=");
+        cookie.extend_from_slice(sid.as_str().as_bytes());
+        cookie.extend_from_slice(self.attributes.as_bytes());
+        // The name and the attributes are validated and a sid only contains base64 characters
+        HeaderValue::from_maybe_shared(cookie.freeze()).expect("cookie is a valid header value")
+    }
+}
+
+/// Returns true if the byte is allowed in a cookie name (an http token, see RFC 6265)
+fn is_cookie_token(b: u8) -> bool {
+    b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b)
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,4 +565,58 @@ mod tests {
         assert!(conf.allowed_transport(TransportType::Polling));
         assert!(conf.allowed_transport(TransportType::Websocket));
     }
+
+    #[test]
+    pub fn cookie_header() {
+        let sid = Sid::new();
+        let cookie = CookieConfig::new();
+        assert_eq!(
+            cookie.to_header(sid),
+            format!("io={sid}; Path=/; HttpOnly; SameSite=Lax").as_str()
+        );
+        let cookie = CookieConfig::new()
+            .name("session")
+            .path("/app")
+            .http_only(false)
+            .same_site(None::<&str>);
+        assert_eq!(
+            cookie.to_header(sid),
+            format!("session={sid}; Path=/app").as_str()
+        );
+        let cookie = CookieConfig::new().same_site(Some("Strict"));
+        assert_eq!(
+            cookie.to_header(sid),
+            format!("io={sid}; Path=/; HttpOnly; SameSite=Strict").as_str()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid cookie name")]
+    pub fn cookie_invalid_name() {
+        CookieConfig::new().name("io; Domain=evil.com");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid cookie path")]
+    pub fn cookie_invalid_path() {
+        CookieConfig::new().path("/\r\nx-injected: true");
+    }
+
+    #[test]
+    #[cfg(feature = "webtransport")]
+    pub fn config_webtransport() {
+        let conf = EngineIoConfig::default();
+        assert!(!conf.allowed_transport(TransportType::WebTransport));
+
+        let conf = EngineIoConfig::builder()
+            .transports([
+                TransportType::Polling,
+                TransportType::Websocket,
+                TransportType::WebTransport,
+            ])
+            .build();
+        assert!(conf.allowed_transport(TransportType::Polling));
+        assert!(conf.allowed_transport(TransportType::Websocket));
+        assert!(conf.allowed_transport(TransportType::WebTransport));
+    }
 }
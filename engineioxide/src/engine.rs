@@ -8,9 +8,15 @@ use http::request::Parts;
 use crate::{
     config::EngineIoConfig,
     handler::EngineIoHandler,
+    heartbeat::HeartbeatScheduler,
     service::TransportType,
     socket::{DisconnectReason, Socket},
 };
+#[cfg(feature = "recorder")]
+use crate::{
+    packet::{OpenPacket, Packet},
+    recorder::Direction,
+};
 use crate::{service::ProtocolVersion, sid::Sid};
 
 type SocketMap<T> = RwLock<HashMap<Sid, Arc<T>>>;
@@ -25,6 +31,9 @@ pub struct EngineIo<H: EngineIoHandler> {
 
     /// The config for the engine.io server
     pub config: EngineIoConfig,
+
+    /// Drives the heartbeat of all the sockets
+    pub(crate) heartbeat: HeartbeatScheduler<H::Data>,
 }
 
 impl<H: EngineIoHandler> EngineIo<H> {
@@ -32,6 +41,7 @@ impl<H: EngineIoHandler> EngineIo<H> {
     pub fn new(handler: H, config: EngineIoConfig) -> Self {
         Self {
             sockets: RwLock::new(HashMap::new()),
+            heartbeat: HeartbeatScheduler::new(&config),
             config,
             handler,
         }
@@ -42,6 +52,7 @@ impl<H: EngineIoHandler> EngineIo<H> {
     /// Create a new engine.io session and a new socket and add it to the socket map
     pub(crate) fn create_session(
         self: &Arc<Self>,
+        sid: Sid,
         protocol: ProtocolVersion,
         transport: TransportType,
         req: Parts,
@@ -51,6 +62,7 @@ impl<H: EngineIoHandler> EngineIo<H> {
         let close_fn = Box::new(move |sid, reason| engine.close_session(sid, reason));
 
         let socket = Socket::new(
+            sid,
             protocol,
             transport,
             &self.config,
@@ -60,6 +72,18 @@ impl<H: EngineIoHandler> EngineIo<H> {
             supports_binary,
         );
         let socket = Arc::new(socket);
+        // The open packet is recorded here because it is sent before anything emitted by the handler
+        #[cfg(feature = "recorder")]
+        if let Some(recorder) = &self.config.recorder {
+            let open = OpenPacket::new(transport, socket.id, &self.config);
+            recorder.record_open(socket.id, protocol, transport);
+            recorder.record_packet(
+                socket.id,
+                Direction::Outgoing,
+                transport,
+                &Packet::Open(open),
+            );
+        }
         self.sockets
             .write()
             .unwrap()
@@ -82,7 +106,10 @@ impl<H: EngineIoHandler> EngineIo<H> {
             // Try to close the internal channel if it is available
             // E.g. with polling transport the channel is not always locked so it is necessary to close it here
             socket.internal_rx.try_lock().map(|mut rx| rx.close()).ok();
-            socket.abort_heartbeat();
+            #[cfg(feature = "recorder")]
+            if let Some(recorder) = &self.config.recorder {
+                recorder.record_close(sid, &reason);
+            }
             self.handler.on_disconnect(socket, reason);
             #[cfg(feature = "tracing")]
             tracing::debug!(
@@ -131,6 +158,7 @@ mod tests {
         let config = EngineIoConfig::default();
         let engine = Arc::new(EngineIo::new(MockHandler, config));
         let socket = engine.create_session(
+            Sid::new(),
             ProtocolVersion::V4,
             TransportType::Polling,
             Request::<()>::default().into_parts().0,
@@ -147,6 +175,7 @@ mod tests {
         let config = EngineIoConfig::default();
         let engine = Arc::new(EngineIo::new(MockHandler, config));
         let socket = engine.create_session(
+            Sid::new(),
             ProtocolVersion::V4,
             TransportType::Polling,
             Request::<()>::default().into_parts().0,
@@ -163,6 +192,7 @@ mod tests {
         let config = EngineIoConfig::default();
         let engine = Arc::new(EngineIo::new(MockHandler, config));
         let socket = engine.create_session(
+            Sid::new(),
             ProtocolVersion::V4,
             TransportType::Polling,
             Request::<()>::default().into_parts().0,
@@ -30,6 +30,8 @@ pub enum Error {
     HeartbeatTimeout,
     #[error("upgrade error")]
     Upgrade,
+    #[error("upgrade timeout")]
+    UpgradeTimeout,
     #[error("aborted connection")]
     Aborted,
 
@@ -42,8 +42,10 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
+use http::{request::Parts, HeaderMap};
 
-use crate::socket::{DisconnectReason, Socket};
+use crate::service::TransportType;
+use crate::socket::{DisconnectReason, Socket, UpgradeError};
 use crate::str::Str;
 
 /// The [`EngineIoHandler`] trait can be implemented on any struct to handle socket events
@@ -64,6 +66,38 @@ pub trait EngineIoHandler: std::fmt::Debug + Send + Sync + 'static {
 
     /// Called when a binary message is received from the client.
     fn on_binary(&self, data: Bytes, socket: Arc<Socket<Self::Data>>);
+
+    /// Called when a client tried to upgrade its polling transport to the given [`TransportType`].
+    ///
+    /// If the upgrade failed with an [`UpgradeError`], the socket keeps using the polling transport.
+    ///
+    /// It does nothing by default.
+    fn on_upgrade(
+        &self,
+        socket: Arc<Socket<Self::Data>>,
+        transport: TransportType,
+        res: Result<(), UpgradeError>,
+    ) {
+        let _ = (socket, transport, res);
+    }
+
+    /// Called before answering a handshake request (polling or websocket).
+    /// The `headers` will be added to the handshake response,
+    /// it can be used to set custom headers or cookies.
+    ///
+    /// It is called before [`EngineIoHandler::on_headers`] and does nothing by default.
+    fn on_initial_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        let _ = (headers, req);
+    }
+
+    /// Called before answering any engine.io http request
+    /// (handshake, polling, post and websocket upgrade).
+    /// The `headers` will be added to the response.
+    ///
+    /// It does nothing by default.
+    fn on_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        let _ = (headers, req);
+    }
 }
 
 impl<T: EngineIoHandler> EngineIoHandler for Arc<T> {
@@ -84,4 +118,21 @@ impl<T: EngineIoHandler> EngineIoHandler for Arc<T> {
     fn on_binary(&self, data: Bytes, socket: Arc<Socket<Self::Data>>) {
         (**self).on_binary(data, socket)
     }
+
+    fn on_upgrade(
+        &self,
+        socket: Arc<Socket<Self::Data>>,
+        transport: TransportType,
+        res: Result<(), UpgradeError>,
+    ) {
+        (**self).on_upgrade(socket, transport, res)
+    }
+
+    fn on_initial_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        (**self).on_initial_headers(headers, req)
+    }
+
+    fn on_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        (**self).on_headers(headers, req)
+    }
 }
@@ -0,0 +1,316 @@
+//! ## Heartbeat strategies
+//!
+//! With the engine.io protocol v4, the server sends a ping packet to each client every `ping_interval`
+//! and closes the connection if the client does not answer within `ping_timeout`.
+//! With the protocol v3, the client sends the ping packets and the server closes the connection
+//! if it does not receive any within `ping_interval + ping_timeout`.
+//! The v3 clients do not answer the ping packets of the server, so the heartbeat timeout always depends
+//! on the pings of the client. A strategy can still make the server send pings to the v3 clients with
+//! [`HeartbeatStrategy::next_v3_ping`], for example to keep idle connections open through a proxy.
+//! If a client answers them, its round trip time is measured like with the protocol v4.
+//!
+//! The delay between two pings can be customized with a [`HeartbeatStrategy`], for example to add some jitter
+//! so that many sockets connected at the same time do not all get pinged at the same time.
+//!
+//! The heartbeats of all the sockets are driven by a single task rather than one task per socket.
+//!
+//! #### Example :
+//! ```rust
+//! # use engineioxide::config::EngineIoConfig;
+//! # use engineioxide::heartbeat::{HeartbeatStrategy, Jitter};
+//! # use engineioxide::TransportType;
+//! # use std::time::Duration;
+//! // Ping polling clients twice as often so that their proxies do not close idle requests
+//! #[derive(Debug)]
+//! struct PollingHeartbeat;
+//! impl HeartbeatStrategy for PollingHeartbeat {
+//!     fn next_ping(&self, interval: Duration, transport: TransportType) -> Duration {
+//!         match transport {
+//!             TransportType::Polling => interval / 2,
+//!             _ => interval,
+//!         }
+//!     }
+//! }
+//!
+//! let config = EngineIoConfig::builder()
+//!     .heartbeat(PollingHeartbeat)
+//!     .build();
+//!
+//! // Or spread the pings over the last 20% of the interval
+//! let config = EngineIoConfig::builder()
+//!     .heartbeat(Jitter::new(0.2))
+//!     .build();
+//!
+//! // Also ping the v3 clients, between their own pings
+//! #[derive(Debug)]
+//! struct V3Keepalive;
+//! impl HeartbeatStrategy for V3Keepalive {
+//!     fn next_ping(&self, interval: Duration, _: TransportType) -> Duration {
+//!         interval
+//!     }
+//!     fn next_v3_ping(&self, interval: Duration, _: TransportType) -> Option<Duration> {
+//!         Some(interval / 2)
+//!     }
+//! }
+//!
+//! let config = EngineIoConfig::builder()
+//!     .heartbeat(V3Keepalive)
+//!     .build();
+//! ```
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{Arc, Mutex, Weak},
+    task::Poll,
+    time::Duration,
+};
+
+use rand::Rng;
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{config::EngineIoConfig, service::TransportType, socket::Socket};
+
+/// A strategy to compute the delay between two pings sent to a socket.
+///
+/// The `interval` given to the strategy is the configured [`ping_interval`](crate::config::EngineIoConfig::ping_interval),
+/// it is advertised to the client during the handshake.
+/// Because the client closes the connection if it does not receive a ping within `ping_interval + ping_timeout`,
+/// the returned delay should not be longer than `interval`.
+///
+/// With the protocol v3 the client sends the pings, therefore [`next_ping`](HeartbeatStrategy::next_ping) is not used.
+pub trait HeartbeatStrategy: Debug + Send + Sync + 'static {
+    /// Returns the delay before sending the next ping to a socket using the given transport.
+    fn next_ping(&self, interval: Duration, transport: TransportType) -> Duration;
+
+    /// Returns the delay before sending the next ping to a socket using the protocol v3,
+    /// or `None` to only answer the pings of the client. Defaults to `None`.
+    ///
+    /// The v3 clients do not answer these pings, the connection is still closed
+    /// if the client does not send its own pings within `ping_interval + ping_timeout`.
+    fn next_v3_ping(&self, interval: Duration, transport: TransportType) -> Option<Duration> {
+        let _ = (interval, transport);
+        None
+    }
+}
+
+/// A [`HeartbeatStrategy`] that sends a ping every `ping_interval`. It is the default strategy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedInterval;
+
+impl HeartbeatStrategy for FixedInterval {
+    fn next_ping(&self, interval: Duration, _: TransportType) -> Duration {
+        interval
+    }
+}
+
+/// A [`HeartbeatStrategy`] that removes a random delay from the `ping_interval`,
+/// to avoid pinging at the same time sockets that were connected together.
+#[derive(Debug, Clone, Copy)]
+pub struct Jitter {
+    ratio: f64,
+}
+
+impl Jitter {
+    /// Creates a [`Jitter`] strategy where each delay is randomly taken between
+    /// `(1 - ratio) * ping_interval` and `ping_interval`.
+    ///
+    /// # Panics
+    /// If the ratio is not between 0 and 1 (e.g. negative or NaN).
+    pub fn new(ratio: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "the jitter ratio must be between 0 and 1, got {ratio}"
+        );
+        Self { ratio }
+    }
+}
+
+impl HeartbeatStrategy for Jitter {
+    fn next_ping(&self, interval: Duration, _: TransportType) -> Duration {
+        let jitter = rand::thread_rng().gen_range(0.0..=self.ratio);
+        interval.mul_f64(1.0 - jitter)
+    }
+}
+
+/// The heartbeat state of a socket, updated by the scheduler and when heartbeat packets are received
+#[derive(Debug)]
+pub(crate) struct HeartbeatState {
+    /// When the last ping was sent to the client, if no pong has been received since (v4)
+    pub ping_sent_at: Option<Instant>,
+    /// When the next ping should be sent to the client (v4)
+    /// or when the last ping was received from the client (v3)
+    pub next_ping_at: Instant,
+    /// When the next ping should be sent to the client if the strategy pings the v3 clients
+    pub next_v3_ping_at: Option<Instant>,
+    /// The last round trip time measured between a ping and its pong
+    pub rtt: Option<Duration>,
+    /// When the last heartbeat packet was received from the client
+    pub last_pong: Option<Instant>,
+    /// When the heartbeat timeout was first extended because reading from the transport is paused
+    pub extended_at: Option<Instant>,
+}
+
+impl HeartbeatState {
+    pub fn new() -> Self {
+        Self {
+            ping_sent_at: None,
+            next_ping_at: Instant::now(),
+            next_v3_ping_at: None,
+            rtt: None,
+            last_pong: None,
+            extended_at: None,
+        }
+    }
+}
+
+/// The settings used to compute the heartbeat deadlines of the sockets
+#[derive(Debug, Clone)]
+pub(crate) struct HeartbeatConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub max_pause: Duration,
+    pub strategy: Arc<dyn HeartbeatStrategy>,
+}
+
+/// Drives the heartbeat of all the registered sockets from a single task.
+///
+/// The sockets are stored in a timer map ordered by their next deadline.
+/// The task is spawned when the first socket is registered and stops when the scheduler is dropped.
+#[derive(Debug)]
+pub(crate) struct HeartbeatScheduler<D: Default + Send + Sync + 'static> {
+    config: HeartbeatConfig,
+    tx: mpsc::UnboundedSender<Weak<Socket<D>>>,
+    rx: Mutex<Option<mpsc::UnboundedReceiver<Weak<Socket<D>>>>>,
+}
+
+impl<D: Default + Send + Sync + 'static> HeartbeatScheduler<D> {
+    pub fn new(config: &EngineIoConfig) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            config: HeartbeatConfig {
+                interval: config.ping_interval,
+                timeout: config.ping_timeout,
+                max_pause: config.max_read_pause,
+                strategy: config.heartbeat.clone(),
+            },
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    }
+
+    /// Starts the heartbeat of a socket
+    pub fn register(&self, socket: &Arc<Socket<D>>) {
+        socket.init_heartbeat(&self.config);
+        if let Some(rx) = self.rx.lock().unwrap().take() {
+            tokio::spawn(run(rx, self.config.clone()));
+        }
+        self.tx.send(Arc::downgrade(socket)).ok();
+    }
+}
+
+enum Event<D: Default + Send + Sync + 'static> {
+    Registered(Option<Weak<Socket<D>>>),
+    Expired,
+}
+
+async fn run<D: Default + Send + Sync + 'static>(
+    mut rx: mpsc::UnboundedReceiver<Weak<Socket<D>>>,
+    config: HeartbeatConfig,
+) {
+    // The sequence number makes the keys unique for sockets with the same deadline
+    let mut timers: BTreeMap<(Instant, u64), Weak<Socket<D>>> = BTreeMap::new();
+    let mut seq: u64 = 0;
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("heartbeat scheduler started");
+
+    loop {
+        let deadline = timers.first_key_value().map(|((deadline, _), _)| *deadline);
+        let mut sleep = pin!(deadline.map(tokio::time::sleep_until));
+        let event = poll_fn(|cx| {
+            if let Poll::Ready(socket) = rx.poll_recv(cx) {
+                return Poll::Ready(Event::Registered(socket));
+            }
+            match sleep.as_mut().as_pin_mut().map(|sleep| sleep.poll(cx)) {
+                Some(Poll::Ready(())) => Poll::Ready(Event::Expired),
+                _ => Poll::Pending,
+            }
+        })
+        .await;
+
+        match event {
+            Event::Registered(None) => break,
+            Event::Registered(Some(weak)) => {
+                if let Some(socket) = weak.upgrade() {
+                    let deadline = socket.heartbeat_tick(Instant::now(), &config);
+                    if let Some(deadline) = deadline {
+                        seq += 1;
+                        timers.insert((deadline, seq), weak);
+                    }
+                }
+            }
+            Event::Expired => {
+                let now = Instant::now();
+                while let Some(entry) = timers.first_entry() {
+                    if entry.key().0 > now {
+                        break;
+                    }
+                    let weak = entry.remove();
+                    let deadline = weak
+                        .upgrade()
+                        .and_then(|socket| socket.heartbeat_tick(now, &config));
+                    if let Some(deadline) = deadline {
+                        seq += 1;
+                        timers.insert((deadline, seq), weak);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("heartbeat scheduler stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_bounds() {
+        let interval = Duration::from_millis(1000);
+        let jitter = Jitter::new(0.2);
+        for _ in 0..100 {
+            let delay = jitter.next_ping(interval, TransportType::Websocket);
+            assert!(delay <= interval);
+            assert!(delay >= Duration::from_millis(800));
+        }
+        assert_eq!(
+            Jitter::new(0.0).next_ping(interval, TransportType::Polling),
+            interval
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn jitter_nan() {
+        Jitter::new(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn jitter_negative() {
+        Jitter::new(-0.1);
+    }
+
+    #[test]
+    fn fixed_interval() {
+        let interval = Duration::from_millis(1000);
+        assert_eq!(
+            FixedInterval.next_ping(interval, TransportType::Polling),
+            interval
+        );
+    }
+}
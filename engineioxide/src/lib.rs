@@ -34,17 +34,25 @@
 
 pub use crate::str::Str;
 pub use service::{ProtocolVersion, TransportType};
-pub use socket::{DisconnectReason, Socket};
+pub use socket::{DisconnectReason, Socket, UpgradeError};
 
 #[cfg(any(test, socketioxide_test))]
 pub use packet::*;
 
 pub mod config;
 pub mod handler;
+pub mod heartbeat;
 pub mod layer;
+#[cfg(feature = "recorder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "recorder")))]
+pub mod recorder;
+#[cfg(feature = "recorder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "recorder")))]
+pub mod replay;
 pub mod service;
 pub mod sid;
 pub mod socket;
+pub mod stats;
 
 mod body;
 mod engine;
@@ -82,7 +82,10 @@ impl Packet {
     /// The base64 max size factor is `ceil(n / 3) * 4`
     pub(crate) fn get_size_hint(&self, b64: bool) -> usize {
         match self {
+            #[cfg(not(feature = "webtransport"))]
             Packet::Open(_) => 156, // max possible size for the open packet serialized
+            #[cfg(feature = "webtransport")]
+            Packet::Open(_) => 171, // same with the additional `webtransport` upgrade
             Packet::Close => 1,
             Packet::Ping => 1,
             Packet::Pong => 1,
@@ -198,9 +201,16 @@ pub struct OpenPacket {
 impl OpenPacket {
     /// Create a new [OpenPacket]
     /// If the current transport is polling, the server will always allow the client to upgrade to websocket
+    /// and to webtransport if it is enabled in the config.
     pub fn new(transport: TransportType, sid: Sid, config: &EngineIoConfig) -> Self {
         let upgrades = if transport == TransportType::Polling {
-            vec!["websocket".to_string()]
+            #[allow(unused_mut)]
+            let mut upgrades = vec!["websocket".to_string()];
+            #[cfg(feature = "webtransport")]
+            if config.allowed_transport(TransportType::WebTransport) {
+                upgrades.push("webtransport".to_string());
+            }
+            upgrades
         } else {
             vec![]
         };
@@ -286,7 +296,12 @@ mod tests {
                 max_payload: u64::MAX,
                 ping_interval: Duration::MAX,
                 ping_timeout: Duration::MAX,
+                #[cfg(not(feature = "webtransport"))]
                 transports: TransportType::Polling as u8 | TransportType::Websocket as u8,
+                #[cfg(feature = "webtransport")]
+                transports: TransportType::Polling as u8
+                    | TransportType::Websocket as u8
+                    | TransportType::WebTransport as u8,
                 ..Default::default()
             },
         );
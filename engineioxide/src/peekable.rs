@@ -1,3 +1,5 @@
+use std::{future::poll_fn, task::Poll};
+
 use tokio::sync::mpsc::{error::TryRecvError, Receiver};
 
 /// Peekable receiver for polling transport
@@ -5,38 +7,140 @@ use tokio::sync::mpsc::{error::TryRecvError, Receiver};
 ///
 /// Its main goal is to be able to peek the next packet without consuming it to calculate the
 /// packet length when using polling transport to check if it fits according to the max_payload setting
+///
+/// It can also drain a priority [`Receiver`] before the main one, so that heartbeat packets
+/// are never delayed by the packets waiting in the main channel.
+///
+/// The items of the control [`Receiver`] are tagged with the number of items sent to the main channel
+/// before them, they are received as soon as these items are received.
+/// It keeps them ordered with the main channel while they are not limited by its capacity.
 #[derive(Debug)]
 pub struct PeekableReceiver<T> {
     rx: Receiver<T>,
+    priority: Option<Receiver<T>>,
+    control: Option<Receiver<(u64, T)>>,
+    /// The next control item, waiting for the items of the main channel sent before it
+    next_control: Option<(u64, T)>,
+    /// The number of items received from the main channel
+    received: u64,
     next: Option<T>,
 }
 impl<T> PeekableReceiver<T> {
+    #[cfg(test)]
     pub fn new(rx: Receiver<T>) -> Self {
-        Self { rx, next: None }
+        Self {
+            rx,
+            priority: None,
+            control: None,
+            next_control: None,
+            received: 0,
+            next: None,
+        }
+    }
+    pub fn with_lanes(rx: Receiver<T>, priority: Receiver<T>, control: Receiver<(u64, T)>) -> Self {
+        Self {
+            rx,
+            priority: Some(priority),
+            control: Some(control),
+            next_control: None,
+            received: 0,
+            next: None,
+        }
     }
     pub fn peek(&mut self) -> Option<&T> {
         if self.next.is_none() {
-            self.next = self.rx.try_recv().ok();
+            self.next = self.try_recv().ok();
         }
         self.next.as_ref()
     }
     pub async fn recv(&mut self) -> Option<T> {
-        if self.next.is_none() {
-            self.rx.recv().await
-        } else {
-            self.next.take()
+        if let Some(next) = self.next.take() {
+            return Some(next);
         }
+        poll_fn(|cx| {
+            if let Some(Poll::Ready(Some(item))) = self.priority.as_mut().map(|rx| rx.poll_recv(cx))
+            {
+                return Poll::Ready(Some(item));
+            }
+            if self.next_control.is_none() {
+                if let Some(Poll::Ready(Some(item))) =
+                    self.control.as_mut().map(|rx| rx.poll_recv(cx))
+                {
+                    self.next_control = Some(item);
+                }
+            }
+            if let Some(item) = self.take_control() {
+                return Poll::Ready(Some(item));
+            }
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(item)) => {
+                    self.received += 1;
+                    Poll::Ready(Some(item))
+                }
+                // The control items left are received once the main channel is closed
+                Poll::Ready(None) => match self.next_control.take() {
+                    Some((_, item)) => Poll::Ready(Some(item)),
+                    None => match self.control.as_mut() {
+                        Some(control) => {
+                            control.poll_recv(cx).map(|item| item.map(|(_, item)| item))
+                        }
+                        None => Poll::Ready(None),
+                    },
+                },
+                Poll::Pending => Poll::Pending,
+            }
+        })
+        .await
     }
     pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
-        if self.next.is_none() {
-            self.rx.try_recv()
-        } else {
-            Ok(self.next.take().unwrap())
+        if let Some(next) = self.next.take() {
+            return Ok(next);
+        }
+        if let Some(Ok(item)) = self.priority.as_mut().map(|rx| rx.try_recv()) {
+            return Ok(item);
+        }
+        if self.next_control.is_none() {
+            if let Some(Ok(item)) = self.control.as_mut().map(|rx| rx.try_recv()) {
+                self.next_control = Some(item);
+            }
+        }
+        if let Some(item) = self.take_control() {
+            return Ok(item);
+        }
+        match self.rx.try_recv() {
+            Ok(item) => {
+                self.received += 1;
+                Ok(item)
+            }
+            Err(TryRecvError::Disconnected) => match self.next_control.take() {
+                Some((_, item)) => Ok(item),
+                None => match self.control.as_mut() {
+                    Some(control) => control.try_recv().map(|(_, item)| item),
+                    None => Err(TryRecvError::Disconnected),
+                },
+            },
+            Err(TryRecvError::Empty) => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Takes the next control item if all the items sent before it to the main channel are received
+    fn take_control(&mut self) -> Option<T> {
+        match self.next_control {
+            Some((sent, _)) if sent <= self.received => {
+                self.next_control.take().map(|(_, item)| item)
+            }
+            _ => None,
         }
     }
 
     pub fn close(&mut self) {
-        self.rx.close()
+        self.rx.close();
+        if let Some(priority) = self.priority.as_mut() {
+            priority.close();
+        }
+        if let Some(control) = self.control.as_mut() {
+            control.close();
+        }
     }
 }
 
@@ -76,4 +180,63 @@ mod tests {
         assert_eq!(rx.recv().await, Some(Packet::Close));
         assert!(rx.peek().is_none());
     }
+
+    #[tokio::test]
+    async fn priority() {
+        use super::PeekableReceiver;
+        use crate::packet::Packet;
+        use tokio::sync::mpsc::channel;
+
+        let (tx, rx) = channel(10);
+        let (priority_tx, priority_rx) = channel(10);
+        let (control_tx, control_rx) = channel(10);
+        let mut rx = PeekableReceiver::with_lanes(rx, priority_rx, control_rx);
+
+        tx.send(Packet::Message("hello".into())).await.unwrap();
+        tx.send(Packet::Message("world".into())).await.unwrap();
+        priority_tx.send(Packet::Ping).await.unwrap();
+        assert_eq!(rx.recv().await, Some(Packet::Ping));
+        assert_eq!(rx.recv().await, Some(Packet::Message("hello".into())));
+
+        priority_tx.send(Packet::Close).await.unwrap();
+        assert_eq!(rx.peek(), Some(&Packet::Close));
+        assert_eq!(rx.try_recv(), Ok(Packet::Close));
+        assert_eq!(rx.try_recv(), Ok(Packet::Message("world".into())));
+
+        // The main channel is still drained once the priority channel is closed
+        drop(priority_tx);
+        tx.send(Packet::Pong).await.unwrap();
+        assert_eq!(rx.recv().await, Some(Packet::Pong));
+        drop(tx);
+        drop(control_tx);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn control() {
+        use super::PeekableReceiver;
+        use crate::packet::Packet;
+        use tokio::sync::mpsc::channel;
+
+        let (tx, rx) = channel(1);
+        let (_priority_tx, priority_rx) = channel(1);
+        let (control_tx, control_rx) = channel(10);
+        let mut rx = PeekableReceiver::with_lanes(rx, priority_rx, control_rx);
+
+        // The control item sent after the first message is received after it, even if the main channel is full
+        tx.send(Packet::Message("hello".into())).await.unwrap();
+        control_tx.send((1, Packet::Noop)).await.unwrap();
+        control_tx.send((1, Packet::Close)).await.unwrap();
+        assert_eq!(rx.peek(), Some(&Packet::Message("hello".into())));
+        assert_eq!(rx.recv().await, Some(Packet::Message("hello".into())));
+        assert_eq!(rx.try_recv(), Ok(Packet::Noop));
+        assert_eq!(rx.recv().await, Some(Packet::Close));
+
+        // A control item waits for the messages sent before it
+        control_tx.send((2, Packet::Close)).await.unwrap();
+        assert!(rx.peek().is_none());
+        tx.send(Packet::Message("world".into())).await.unwrap();
+        assert_eq!(rx.recv().await, Some(Packet::Message("world".into())));
+        assert_eq!(rx.recv().await, Some(Packet::Close));
+    }
 }
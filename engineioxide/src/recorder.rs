@@ -0,0 +1,594 @@
+//! ## Packet capture of engine.io sessions
+//!
+//! A [`Recorder`] set with [`EngineIoConfigBuilder::recorder`](crate::config::EngineIoConfigBuilder::recorder)
+//! logs every packet exchanged with the clients to a compact binary file.
+//! Each [`Record`] has a timestamp, the [`Sid`] of its session and one of the following events:
+//! * the opening of the session with its protocol version and its transport,
+//! * a packet received from or sent to the client, with the transport used at this moment,
+//! * the closing of the session with its [`DisconnectReason`].
+//!
+//! The packets are recorded with their engine.io encoding (e.g. `4hello` for a message), whatever the transport.
+//! The incoming packets are recorded when they are decoded and the outgoing packets when they are queued.
+//! The records are written by a dedicated thread so that the sessions never wait for the writer.
+//!
+//! A recording can be read back with a [`RecordReader`] and replayed with the [`replay`](crate::replay) module.
+//!
+//! ## Example
+//! ```no_run
+//! # use engineioxide::{config::EngineIoConfig, recorder::Recorder, service::EngineIoService};
+//! # use engineioxide::{handler::EngineIoHandler, socket::{Socket, DisconnectReason}};
+//! # use std::sync::Arc;
+//! # #[derive(Debug)]
+//! # struct MyHandler;
+//! # impl EngineIoHandler for MyHandler {
+//! #     type Data = ();
+//! #     fn on_connect(&self, socket: Arc<Socket<()>>) { }
+//! #     fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) { }
+//! #     fn on_message(&self, msg: engineioxide::Str, socket: Arc<Socket<()>>) { }
+//! #     fn on_binary(&self, data: bytes::Bytes, socket: Arc<Socket<()>>) { }
+//! # }
+//! let recorder = Recorder::create("session.eiorec").unwrap();
+//! let config = EngineIoConfig::builder().recorder(recorder).build();
+//! let svc = EngineIoService::with_config(MyHandler, config);
+//! ```
+//!
+//! ## File format
+//! A recording starts with the `EIOREC` magic bytes, a version byte and the start time of the recording
+//! as a little endian `u64` of microseconds since the unix epoch.
+//! It is followed by the records, each made of a kind byte, the time elapsed since the start of the recording
+//! as a LEB128 varint of microseconds, the 16 bytes of the sid and the fields of the kind:
+//! * open: the protocol version byte and the transport byte,
+//! * packet: the transport byte, the length of the data as a varint and the data, of at most 64 MiB,
+//! * close: the disconnect reason byte.
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    str::FromStr,
+    sync::mpsc,
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+use crate::{packet::Packet, sid::Sid, DisconnectReason, ProtocolVersion, TransportType};
+
+const MAGIC: &[u8; 6] = b"EIOREC";
+const VERSION: u8 = 1;
+
+const KIND_OPEN: u8 = 0;
+const KIND_RECV_TEXT: u8 = 1;
+const KIND_RECV_BINARY: u8 = 2;
+const KIND_SEND_TEXT: u8 = 3;
+const KIND_SEND_BINARY: u8 = 4;
+const KIND_CLOSE: u8 = 5;
+
+/// The maximum length of the data of a packet record.
+/// It bounds the allocation made when reading a corrupt or untrusted recording.
+const MAX_DATA_LEN: usize = 64 * 1024 * 1024;
+
+/// The direction of a recorded packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the client
+    Incoming,
+    /// Sent to the client
+    Outgoing,
+}
+
+/// The data of a recorded packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// A packet with its engine.io text encoding
+    Text(String),
+    /// A binary packet with its raw data
+    Binary(Bytes),
+}
+
+impl From<&Packet> for Frame {
+    fn from(packet: &Packet) -> Self {
+        match packet {
+            Packet::Binary(data) | Packet::BinaryV3(data) => Frame::Binary(data.clone()),
+            // Only the binary packets cannot be encoded as text
+            packet => Frame::Text(packet.clone().try_into().unwrap_or_default()),
+        }
+    }
+}
+
+/// An event of a recorded session
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordEvent {
+    /// The session was opened
+    Open {
+        /// The protocol version of the client
+        protocol: ProtocolVersion,
+        /// The transport used to open the session
+        transport: TransportType,
+    },
+    /// A packet was received from or sent to the client
+    Packet {
+        /// The direction of the packet
+        direction: Direction,
+        /// The transport used for the packet
+        transport: TransportType,
+        /// The data of the packet
+        frame: Frame,
+    },
+    /// The session was closed
+    Close {
+        /// The reason of the closing
+        reason: DisconnectReason,
+    },
+}
+
+/// A timestamped event of a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// The time elapsed since the start of the recording
+    pub time: Duration,
+    /// The id of the session
+    pub sid: Sid,
+    /// The recorded event
+    pub event: RecordEvent,
+}
+
+/// A command sent to the writer thread of a [`Recorder`]
+enum Command {
+    /// Writes an encoded record
+    Write(Vec<u8>),
+    /// Flushes the buffered records, and sends the result if a channel is given
+    Flush(Option<mpsc::SyncSender<io::Result<()>>>),
+}
+
+/// Logs every packet of every engine.io session to a writer, see the [module doc](self).
+///
+/// The records are sent to a dedicated thread that writes them, so recording a packet never blocks.
+/// They are buffered and flushed when a session is closed, when [`Recorder::flush`] is called
+/// and when the recorder is dropped.
+pub struct Recorder {
+    start: Instant,
+    tx: Option<mpsc::Sender<Command>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Creates a recorder writing to the given writer from a new thread
+    pub fn new(writer: impl Write + Send + 'static) -> io::Result<Self> {
+        let mut writer = BufWriter::new(Box::new(writer) as Box<dyn Write + Send>);
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&start_time.to_le_bytes())?;
+
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("eio-recorder".into())
+            .spawn(move || run_writer(writer, rx))?;
+        Ok(Self {
+            start: Instant::now(),
+            tx: Some(tx),
+            thread: Some(thread),
+        })
+    }
+
+    /// Creates a recorder writing to a new file at the given path. The file is truncated if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::create(path)?)
+    }
+
+    /// Flushes the buffered records to the writer.
+    ///
+    /// It blocks until the records sent before are written, it should not be called from an async task.
+    pub fn flush(&self) -> io::Result<()> {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.send(Command::Flush(Some(tx)));
+        rx.recv()
+            .unwrap_or_else(|_| Err(io::Error::other("the recorder thread stopped")))
+    }
+
+    pub(crate) fn record_open(
+        &self,
+        sid: Sid,
+        protocol: ProtocolVersion,
+        transport: TransportType,
+    ) {
+        self.write(KIND_OPEN, sid, |buf| {
+            buf.push(protocol as u8);
+            buf.push(transport as u8);
+        });
+    }
+
+    pub(crate) fn record_packet(
+        &self,
+        sid: Sid,
+        direction: Direction,
+        transport: TransportType,
+        packet: &Packet,
+    ) {
+        match Frame::from(packet) {
+            Frame::Text(data) => {
+                self.record_data(sid, direction, transport, false, data.as_bytes())
+            }
+            Frame::Binary(data) => self.record_data(sid, direction, transport, true, &data),
+        }
+    }
+
+    pub(crate) fn record_data(
+        &self,
+        sid: Sid,
+        direction: Direction,
+        transport: TransportType,
+        binary: bool,
+        data: &[u8],
+    ) {
+        let kind = match (direction, binary) {
+            (Direction::Incoming, false) => KIND_RECV_TEXT,
+            (Direction::Incoming, true) => KIND_RECV_BINARY,
+            (Direction::Outgoing, false) => KIND_SEND_TEXT,
+            (Direction::Outgoing, true) => KIND_SEND_BINARY,
+        };
+        if data.len() > MAX_DATA_LEN {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[sid={sid}] packet too large to be recorded");
+            return;
+        }
+        self.write(kind, sid, |buf| {
+            buf.push(transport as u8);
+            write_varint(buf, data.len() as u64);
+            buf.extend_from_slice(data);
+        });
+    }
+
+    pub(crate) fn record_close(&self, sid: Sid, reason: &DisconnectReason) {
+        self.write(KIND_CLOSE, sid, |buf| buf.push(reason_to_u8(reason)));
+        self.send(Command::Flush(None));
+    }
+
+    /// Encodes a record and sends it to the writer thread.
+    /// A write error only loses the record, it is not reported to the session.
+    fn write(&self, kind: u8, sid: Sid, fields: impl FnOnce(&mut Vec<u8>)) {
+        let mut buf = Vec::with_capacity(32);
+        buf.push(kind);
+        write_varint(&mut buf, self.start.elapsed().as_micros() as u64);
+        buf.extend_from_slice(sid.as_str().as_bytes());
+        fields(&mut buf);
+        self.send(Command::Write(buf));
+    }
+
+    fn send(&self, cmd: Command) {
+        if let Some(tx) = &self.tx {
+            tx.send(cmd).ok();
+        }
+    }
+}
+
+/// Writes the records received until the recorder is dropped, then flushes them
+fn run_writer(mut writer: BufWriter<Box<dyn Write + Send>>, rx: mpsc::Receiver<Command>) {
+    for cmd in rx {
+        match cmd {
+            Command::Write(buf) => {
+                if let Err(_e) = writer.write_all(&buf) {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("error writing record: {_e}");
+                }
+            }
+            Command::Flush(tx) => {
+                let res = writer.flush();
+                if let Some(tx) = tx {
+                    tx.send(res).ok();
+                }
+            }
+        }
+    }
+    writer.flush().ok();
+}
+
+impl Drop for Recorder {
+    /// Waits for the writer thread to write and flush the remaining records
+    fn drop(&mut self) {
+        self.tx.take();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+/// Reads the [`Record`]s of a recording made by a [`Recorder`].
+///
+/// It is an iterator that stops at the end of the recording or at the first error.
+pub struct RecordReader<R: Read> {
+    reader: R,
+    start_time: SystemTime,
+    done: bool,
+}
+
+impl RecordReader<BufReader<File>> {
+    /// Opens the recording file at the given path
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Reads the header of a recording from the given reader
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 15];
+        reader.read_exact(&mut header)?;
+        if &header[..6] != MAGIC || header[6] != VERSION {
+            return Err(invalid_data("not a supported engine.io recording"));
+        }
+        let start = u64::from_le_bytes(header[7..].try_into().unwrap());
+        Ok(Self {
+            reader,
+            start_time: UNIX_EPOCH + Duration::from_micros(start),
+            done: false,
+        })
+    }
+
+    /// The time at which the recording was started
+    pub fn start_time(&self) -> SystemTime {
+        self.start_time
+    }
+
+    /// Reads the next record, or returns `None` at the end of the recording
+    fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let mut kind = [0u8];
+        if self.reader.read(&mut kind)? == 0 {
+            return Ok(None);
+        }
+        let time = Duration::from_micros(read_varint(&mut self.reader)?);
+        let mut sid = [0u8; 16];
+        self.reader.read_exact(&mut sid)?;
+        let sid = std::str::from_utf8(&sid)
+            .ok()
+            .and_then(|sid| Sid::from_str(sid).ok())
+            .ok_or_else(|| invalid_data("invalid sid"))?;
+
+        let event = match kind[0] {
+            KIND_OPEN => RecordEvent::Open {
+                protocol: protocol_from_u8(self.read_u8()?)?,
+                transport: transport_from_u8(self.read_u8()?)?,
+            },
+            kind @ KIND_RECV_TEXT..=KIND_SEND_BINARY => {
+                let transport = transport_from_u8(self.read_u8()?)?;
+                let len = read_varint(&mut self.reader)?;
+                if len > MAX_DATA_LEN as u64 {
+                    return Err(invalid_data("packet record too large"));
+                }
+                let mut data = vec![0u8; len as usize];
+                self.reader.read_exact(&mut data)?;
+                let frame = if kind == KIND_RECV_TEXT || kind == KIND_SEND_TEXT {
+                    Frame::Text(String::from_utf8(data).map_err(|_| invalid_data("invalid text"))?)
+                } else {
+                    Frame::Binary(data.into())
+                };
+                let direction = if kind <= KIND_RECV_BINARY {
+                    Direction::Incoming
+                } else {
+                    Direction::Outgoing
+                };
+                RecordEvent::Packet {
+                    direction,
+                    transport,
+                    frame,
+                }
+            }
+            KIND_CLOSE => RecordEvent::Close {
+                reason: reason_from_u8(self.read_u8()?)?,
+            },
+            _ => return Err(invalid_data("unknown record kind")),
+        };
+        Ok(Some(Record { time, sid, event }))
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_record().transpose();
+        self.done = !matches!(res, Some(Ok(_)));
+        res
+    }
+}
+
+impl<R: Read> Debug for RecordReader<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordReader")
+            .field("start_time", &self.start_time)
+            .finish_non_exhaustive()
+    }
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+fn protocol_from_u8(protocol: u8) -> io::Result<ProtocolVersion> {
+    match protocol {
+        3 => Ok(ProtocolVersion::V3),
+        4 => Ok(ProtocolVersion::V4),
+        _ => Err(invalid_data("unknown protocol version")),
+    }
+}
+
+fn transport_from_u8(transport: u8) -> io::Result<TransportType> {
+    match transport {
+        0x01 => Ok(TransportType::Polling),
+        0x02 => Ok(TransportType::Websocket),
+        #[cfg(feature = "webtransport")]
+        0x04 => Ok(TransportType::WebTransport),
+        #[cfg(not(feature = "webtransport"))]
+        0x04 => Err(invalid_data(
+            "webtransport record, the `webtransport` feature is required to read it",
+        )),
+        _ => Err(invalid_data("unknown transport")),
+    }
+}
+
+fn reason_to_u8(reason: &DisconnectReason) -> u8 {
+    match reason {
+        DisconnectReason::TransportClose => 0,
+        DisconnectReason::MultipleHttpPollingError => 1,
+        DisconnectReason::PacketParsingError => 2,
+        DisconnectReason::TransportError => 3,
+        DisconnectReason::HeartbeatTimeout => 4,
+        DisconnectReason::ClosingServer => 5,
+    }
+}
+
+fn reason_from_u8(reason: u8) -> io::Result<DisconnectReason> {
+    match reason {
+        0 => Ok(DisconnectReason::TransportClose),
+        1 => Ok(DisconnectReason::MultipleHttpPollingError),
+        2 => Ok(DisconnectReason::PacketParsingError),
+        3 => Ok(DisconnectReason::TransportError),
+        4 => Ok(DisconnectReason::HeartbeatTimeout),
+        5 => Ok(DisconnectReason::ClosingServer),
+        _ => Err(invalid_data("unknown disconnect reason")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// A writer that can be read after the recorder is dropped
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn record_roundtrip() {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone()).unwrap();
+        let sid = Sid::new();
+        recorder.record_open(sid, ProtocolVersion::V4, TransportType::Polling);
+        recorder.record_packet(
+            sid,
+            Direction::Incoming,
+            TransportType::Polling,
+            &Packet::Message("hello".into()),
+        );
+        recorder.record_packet(
+            sid,
+            Direction::Outgoing,
+            TransportType::Websocket,
+            &Packet::Binary(Bytes::from_static(&[1, 2, 3])),
+        );
+        recorder.record_close(sid, &DisconnectReason::HeartbeatTimeout);
+        drop(recorder);
+
+        let data = buf.0.lock().unwrap().clone();
+        let records: Vec<_> = RecordReader::new(data.as_slice())
+            .unwrap()
+            .map(|r| r.unwrap().event)
+            .collect();
+        assert_eq!(
+            records,
+            [
+                RecordEvent::Open {
+                    protocol: ProtocolVersion::V4,
+                    transport: TransportType::Polling
+                },
+                RecordEvent::Packet {
+                    direction: Direction::Incoming,
+                    transport: TransportType::Polling,
+                    frame: Frame::Text("4hello".into())
+                },
+                RecordEvent::Packet {
+                    direction: Direction::Outgoing,
+                    transport: TransportType::Websocket,
+                    frame: Frame::Binary(Bytes::from_static(&[1, 2, 3]))
+                },
+                RecordEvent::Close {
+                    reason: DisconnectReason::HeartbeatTimeout
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_recording() {
+        let err = RecordReader::new(&b"NOTREC\x01\0\0\0\0\0\0\0\0"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A truncated record ends the iteration with an error
+        let mut data = Vec::from(&b"EIOREC\x01\0\0\0\0\0\0\0\0"[..]);
+        data.extend_from_slice(&[KIND_CLOSE, 0]);
+        let mut reader = RecordReader::new(data.as_slice()).unwrap();
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn oversized_record() {
+        let mut data = Vec::from(&b"EIOREC\x01\0\0\0\0\0\0\0\0"[..]);
+        data.extend_from_slice(&[KIND_RECV_BINARY, 0]);
+        data.extend_from_slice(Sid::new().as_str().as_bytes());
+        data.push(TransportType::Websocket as u8);
+        write_varint(&mut data, u64::MAX);
+        let mut reader = RecordReader::new(data.as_slice()).unwrap();
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
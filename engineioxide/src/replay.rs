@@ -0,0 +1,288 @@
+//! ## Replay of recorded engine.io sessions
+//!
+//! The sessions captured by a [`Recorder`](crate::recorder::Recorder) can be replayed against an engine.io server
+//! to reproduce them in tests:
+//! * in memory with [`EngineIoService::replay_session`](crate::service::EngineIoService::replay_session), without any network,
+//! * against a running server with [`replay_ws`] or with the `eio-replay` binary.
+//!
+//! A [`Session`] is always replayed over the websocket transport, whatever the transports it was recorded with.
+//! Only the messages are replayed: the handshake, the heartbeat and the upgrade packets belong to the transport
+//! and are handled by the replay driver (e.g. the pings of the server are answered right away).
+//!
+//! The replay is deterministic: the recorded timestamps are ignored and each message of the client is sent
+//! once the server has emitted all the messages recorded before it.
+//! The recorded session id is replaced with the new one in the messages.
+//! The messages emitted by the server are collected in a [`ReplayReport`] to be compared with the recorded ones.
+//!
+//! ## Example
+//! ```no_run
+//! # use engineioxide::{handler::EngineIoHandler, service::EngineIoService, socket::{Socket, DisconnectReason}};
+//! # use engineioxide::{recorder::RecordReader, replay};
+//! # use std::{sync::Arc, time::Duration};
+//! # #[derive(Debug)]
+//! # struct MyHandler;
+//! # impl EngineIoHandler for MyHandler {
+//! #     type Data = ();
+//! #     fn on_connect(&self, socket: Arc<Socket<()>>) { }
+//! #     fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) { }
+//! #     fn on_message(&self, msg: engineioxide::Str, socket: Arc<Socket<()>>) { }
+//! #     fn on_binary(&self, data: bytes::Bytes, socket: Arc<Socket<()>>) { }
+//! # }
+//! # async fn doc() -> Result<(), Box<dyn std::error::Error>> {
+//! let svc = EngineIoService::new(MyHandler);
+//! let sessions = replay::sessions(RecordReader::open("session.eiorec")?)?;
+//! for session in &sessions {
+//!     let report = svc.replay_session(session, Duration::from_secs(1)).await?;
+//!     assert!(report.matches(), "{report:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::HashMap, io, str::FromStr, time::Duration};
+
+use futures_util::{SinkExt, StreamExt};
+use http::Request;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::{
+    tungstenite::{self, protocol::Role, Message},
+    WebSocketStream,
+};
+
+use crate::{
+    engine::EngineIo,
+    handler::EngineIoHandler,
+    packet::Packet,
+    recorder::{Direction, Frame, Record, RecordEvent},
+    sid::Sid,
+    transport::ws::{self, WsSession},
+    ProtocolVersion,
+};
+
+/// The records of one engine.io session, in their recorded order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    /// The id of the recorded session
+    pub sid: Sid,
+    /// The protocol version of the recorded client
+    pub protocol: ProtocolVersion,
+    /// All the records of the session, from its opening to its closing
+    pub records: Vec<Record>,
+}
+
+/// Groups the records of a recording by session, in their opening order.
+///
+/// The records of a session that was opened before the start of the recording are ignored.
+pub fn sessions(records: impl IntoIterator<Item = io::Result<Record>>) -> io::Result<Vec<Session>> {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut index = HashMap::new();
+    for record in records {
+        let record = record?;
+        if let RecordEvent::Open { protocol, .. } = record.event {
+            index.insert(record.sid, sessions.len());
+            sessions.push(Session {
+                sid: record.sid,
+                protocol,
+                records: Vec::new(),
+            });
+        }
+        if let Some(&i) = index.get(&record.sid) {
+            sessions[i].records.push(record);
+        }
+    }
+    Ok(sessions)
+}
+
+/// An error that stopped a replay
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    /// The websocket connection failed
+    #[error("websocket error: {0:?}")]
+    Ws(#[from] tungstenite::Error),
+    /// The server did not open the session with a valid open packet
+    #[error("invalid handshake: {0}")]
+    Handshake(String),
+}
+
+/// The messages emitted by the server during a replay, compared with the recorded ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The messages emitted by the server in the recorded session, with the new session id
+    pub expected: Vec<Frame>,
+    /// The messages emitted by the server during the replay
+    pub received: Vec<Frame>,
+}
+
+impl ReplayReport {
+    /// Returns true if the server emitted the recorded messages, in the same order
+    pub fn matches(&self) -> bool {
+        self.expected == self.received
+    }
+}
+
+/// Replays a session in memory against an engine, see [`EngineIoService::replay_session`](crate::service::EngineIoService::replay_session).
+pub(crate) async fn replay_in_memory<H: EngineIoHandler>(
+    engine: std::sync::Arc<EngineIo<H>>,
+    session: &Session,
+    timeout: Duration,
+) -> Result<ReplayReport, ReplayError> {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let protocol = session.protocol;
+    let uri = format!(
+        "{}?EIO={}&transport=websocket",
+        engine.config.req_path, protocol as u8
+    );
+    let (parts, _) = Request::get(uri).body(()).unwrap().into_parts();
+    let ws_session = WsSession::New(Sid::new());
+    tokio::spawn(ws::on_init(engine, server, protocol, ws_session, parts));
+
+    let ws = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+    replay_ws(ws, session, timeout).await
+}
+
+/// Replays a session on a websocket connected to an engine.io server.
+///
+/// The websocket must be opened with the `transport=websocket` query and the `EIO` version of the session.
+/// The server has `timeout` to emit each expected message.
+pub async fn replay_ws<S>(
+    mut ws: WebSocketStream<S>,
+    session: &Session,
+    timeout: Duration,
+) -> Result<ReplayReport, ReplayError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let sid = read_open(&mut ws, timeout).await?;
+    let mut report = ReplayReport {
+        expected: Vec::new(),
+        received: Vec::new(),
+    };
+    let mut closed = false;
+
+    for record in &session.records {
+        let RecordEvent::Packet {
+            direction, frame, ..
+        } = &record.event
+        else {
+            continue;
+        };
+        let Some(frame) = replayed_frame(*direction, frame, session.sid, sid) else {
+            continue;
+        };
+        match direction {
+            Direction::Outgoing => report.expected.push(frame),
+            Direction::Incoming if !closed => {
+                closed = recv_until(&mut ws, &mut report, session.protocol, timeout).await?;
+                if closed {
+                    continue;
+                }
+                let msg = match frame {
+                    Frame::Text(data) => Message::Text(data),
+                    Frame::Binary(data) if session.protocol == ProtocolVersion::V3 => {
+                        // v3 protocol requires packet type as the first byte
+                        let mut bin = vec![0x04];
+                        bin.extend_from_slice(&data);
+                        Message::Binary(bin)
+                    }
+                    Frame::Binary(data) => Message::Binary(data.into()),
+                };
+                ws.send(msg).await?;
+            }
+            Direction::Incoming => (),
+        }
+    }
+    if !closed {
+        recv_until(&mut ws, &mut report, session.protocol, timeout).await?;
+        ws.close(None).await.ok();
+    }
+    Ok(report)
+}
+
+/// Reads the open packet of the server and returns the new session id
+async fn read_open<S>(ws: &mut WebSocketStream<S>, timeout: Duration) -> Result<Sid, ReplayError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    #[derive(serde::Deserialize)]
+    struct OpenPacket {
+        sid: String,
+    }
+    let msg = match tokio::time::timeout(timeout, ws.next()).await {
+        Ok(Some(msg)) => msg?,
+        _ => return Err(ReplayError::Handshake("no open packet".into())),
+    };
+    let open = match &msg {
+        Message::Text(data) if data.starts_with('0') => data[1..].to_string(),
+        msg => {
+            return Err(ReplayError::Handshake(format!(
+                "unexpected message {msg:?}"
+            )))
+        }
+    };
+    serde_json::from_str::<OpenPacket>(&open)
+        .ok()
+        .and_then(|open| Sid::from_str(&open.sid).ok())
+        .ok_or_else(|| ReplayError::Handshake(format!("invalid open packet {open}")))
+}
+
+/// Returns the frame to replay or to expect for a recorded frame, with the new session id,
+/// or `None` if it belongs to the transport (handshake, heartbeat, upgrade...).
+///
+/// The close packet of the client is replayed, the one of the server is a websocket close frame.
+fn replayed_frame(
+    direction: Direction,
+    frame: &Frame,
+    recorded_sid: Sid,
+    sid: Sid,
+) -> Option<Frame> {
+    let data = match frame {
+        Frame::Binary(data) => return Some(Frame::Binary(data.clone())),
+        Frame::Text(data) => data,
+    };
+    match (direction, Packet::try_from(data.clone()).ok()?) {
+        (_, Packet::Message(_)) | (Direction::Incoming, Packet::Close) => Some(Frame::Text(
+            data.replace(recorded_sid.as_str(), sid.as_str()),
+        )),
+        _ => None,
+    }
+}
+
+/// Reads the messages emitted by the server until all the expected messages are received.
+/// It stops earlier if the server does not emit anything for `timeout`.
+///
+/// Returns true if the connection was closed by the server.
+async fn recv_until<S>(
+    ws: &mut WebSocketStream<S>,
+    report: &mut ReplayReport,
+    protocol: ProtocolVersion,
+    timeout: Duration,
+) -> Result<bool, ReplayError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while report.received.len() < report.expected.len() {
+        let msg = match tokio::time::timeout(timeout, ws.next()).await {
+            Ok(Some(msg)) => msg?,
+            Ok(None) => return Ok(true),
+            Err(_) => return Ok(false),
+        };
+        match msg {
+            Message::Text(data) => match Packet::try_from(data.clone()) {
+                // The heartbeat of the server is answered, the v3 clients do not answer the pings of the server
+                Ok(Packet::Ping) if protocol == ProtocolVersion::V4 => {
+                    ws.send(Message::Text("3".into())).await?;
+                }
+                Ok(Packet::Message(_)) => report.received.push(Frame::Text(data)),
+                _ => (),
+            },
+            Message::Binary(mut data) => {
+                if protocol == ProtocolVersion::V3 && !data.is_empty() {
+                    data.remove(0);
+                }
+                report.received.push(Frame::Binary(data.into()));
+            }
+            Message::Close(_) => return Ok(true),
+            _ => (),
+        }
+    }
+    Ok(false)
+}
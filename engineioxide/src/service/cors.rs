@@ -0,0 +1,125 @@
+//! CORS handling for engine.io requests, configured with a [`CorsConfig`]
+
+use http::{
+    header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, VARY,
+    },
+    HeaderMap, HeaderValue, Response, StatusCode,
+};
+
+use crate::{body::ResponseBody, config::CorsConfig};
+
+/// Append the CORS headers for the given request origin
+pub fn apply_headers(cors: &CorsConfig, origin: Option<&HeaderValue>, headers: &mut HeaderMap) {
+    // Credentials are never allowed for any origin, see `EngineIoConfigBuilder::cors`
+    if cors.origins.is_none() {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    } else if let Some(origin) = origin {
+        // The origin was checked against the allowed origins, it is mirrored
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        headers.append(VARY, HeaderValue::from_static("Origin"));
+    }
+    if cors.credentials {
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+}
+
+/// Answer a preflight `OPTIONS` request
+pub fn preflight_response<B>(
+    cors: &CorsConfig,
+    origin: Option<&HeaderValue>,
+) -> Response<ResponseBody<B>> {
+    let mut res = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header(ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, OPTIONS")
+        .body(ResponseBody::empty_response())
+        .unwrap();
+    let headers = res.headers_mut();
+    apply_headers(cors, origin, headers);
+
+    let allowed_headers = std::iter::once("content-type")
+        .chain(cors.headers.iter().map(|h| h.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Header names are always valid header values
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_str(&allowed_headers).unwrap(),
+    );
+    if let Some(max_age) = cors.max_age {
+        headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::HeaderName;
+
+    use super::*;
+    use crate::config::EngineIoConfig;
+
+    #[test]
+    #[should_panic(expected = "CORS credentials cannot be allowed for any origin")]
+    fn any_origin_with_credentials() {
+        EngineIoConfig::builder().cors(CorsConfig::new().allow_credentials(true));
+    }
+
+    #[test]
+    fn any_origin() {
+        let cors = CorsConfig::new();
+        let origin = HeaderValue::from_static("https://example.com");
+        assert!(cors.is_allowed(Some(&origin)));
+        assert!(cors.is_allowed(None));
+
+        let mut headers = HeaderMap::new();
+        apply_headers(&cors, Some(&origin), &mut headers);
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert!(headers.get(ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    }
+
+    #[test]
+    fn allowed_origins_with_credentials() {
+        let cors = CorsConfig::new()
+            .allow_origin("https://example.com")
+            .allow_credentials(true);
+        let origin = HeaderValue::from_static("https://example.com");
+        assert!(cors.is_allowed(Some(&origin)));
+        assert!(!cors.is_allowed(Some(&HeaderValue::from_static("https://evil.com"))));
+        assert!(cors.is_allowed(None));
+
+        let mut headers = HeaderMap::new();
+        apply_headers(&cors, Some(&origin), &mut headers);
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), origin);
+        assert_eq!(headers.get(VARY).unwrap(), "Origin");
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(),
+            "true"
+        );
+    }
+
+    #[test]
+    fn preflight() {
+        let cors = CorsConfig::new()
+            .allow_header(HeaderName::from_static("x-custom"))
+            .max_age(Duration::from_secs(60));
+        let res = preflight_response::<()>(&cors, None);
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let headers = res.headers();
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET, POST, OPTIONS"
+        );
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "content-type, x-custom"
+        );
+        assert_eq!(headers.get(ACCESS_CONTROL_MAX_AGE).unwrap(), "60");
+    }
+}
@@ -1,7 +1,7 @@
 use crate::body::ResponseBody;
 use crate::errors::Error;
 use futures_core::ready;
-use http::Response;
+use http::{HeaderMap, Response};
 use pin_project_lite::pin_project;
 use std::future::Future;
 use std::pin::Pin;
@@ -42,6 +42,36 @@ impl<F, B> ResponseFuture<F, B> {
     pub fn async_response(future: BoxFuture<B>) -> Self {
         ResponseFuture::AsyncResponse { future }
     }
+
+    /// Add the given headers to the engine.io response
+    pub fn with_headers(self, headers: HeaderMap) -> Self
+    where
+        B: Send + 'static,
+    {
+        let extend = move |mut res: Response<ResponseBody<B>>| {
+            res.headers_mut().extend(headers);
+            res
+        };
+        match self {
+            ResponseFuture::EmptyResponse { code } => ResponseFuture::ready(Ok(extend(
+                Response::builder()
+                    .status(code)
+                    .body(ResponseBody::empty_response())
+                    .unwrap(),
+            ))),
+            ResponseFuture::ReadyResponse { res } => {
+                let res = res.unwrap().unwrap_or_else(|e| e.into());
+                ResponseFuture::ready(Ok(extend(res)))
+            }
+            ResponseFuture::AsyncResponse { future } => {
+                ResponseFuture::async_response(Box::pin(async move {
+                    Ok(extend(future.await.unwrap_or_else(|e| e.into())))
+                }))
+            }
+            // Responses from the inner service are left untouched
+            fut @ ResponseFuture::Future { .. } => fut,
+        }
+    }
 }
 
 impl<ResBody, F, E> Future for ResponseFuture<F, ResBody>
@@ -44,6 +44,7 @@ use crate::{
     body::ResponseBody, config::EngineIoConfig, engine::EngineIo, handler::EngineIoHandler,
 };
 
+mod cors;
 mod futures;
 mod parser;
 
@@ -91,6 +92,111 @@ impl<S: Clone, H: EngineIoHandler> EngineIoService<H, S> {
     pub fn into_make_service(self) -> MakeEngineIoService<H, S> {
         MakeEngineIoService::new(self)
     }
+
+    /// Serve the WebTransport sessions of an HTTP/3 connection accepted with [`quinn`].
+    ///
+    /// The QUIC endpoint must negotiate the `h3` ALPN protocol.
+    /// Every `CONNECT` request with the `webtransport` protocol that targets the engine.io path opens a session
+    /// and the first bidirectional stream opened by the client in this session carries the engine.io packets.
+    /// Other requests are answered with a `400 Bad Request`.
+    ///
+    /// The returned future resolves when the connection is closed.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// # use engineioxide::{handler::EngineIoHandler, service::EngineIoService, socket::{Socket, DisconnectReason}};
+    /// # use std::sync::Arc;
+    /// # #[derive(Debug)]
+    /// # struct MyHandler;
+    /// # impl EngineIoHandler for MyHandler {
+    /// #     type Data = ();
+    /// #     fn on_connect(&self, socket: Arc<Socket<()>>) { }
+    /// #     fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) { }
+    /// #     fn on_message(&self, msg: engineioxide::Str, socket: Arc<Socket<()>>) { }
+    /// #     fn on_binary(&self, data: bytes::Bytes, socket: Arc<Socket<()>>) { }
+    /// # }
+    /// # async fn doc(server_config: quinn::ServerConfig) -> std::io::Result<()> {
+    /// // The rustls config of `server_config` must set the `h3` ALPN protocol
+    /// let endpoint = quinn::Endpoint::server(server_config, "0.0.0.0:3000".parse().unwrap())?;
+    /// let svc = EngineIoService::new(MyHandler);
+    /// while let Some(incoming) = endpoint.accept().await {
+    ///     let svc = svc.clone();
+    ///     tokio::spawn(async move {
+    ///         if let Ok(conn) = incoming.await {
+    ///             svc.serve_webtransport(conn).await;
+    ///         }
+    ///     });
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "webtransport")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+    pub fn serve_webtransport(
+        &self,
+        conn: quinn::Connection,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let engine = self.engine.clone();
+        async move {
+            if let Err(_e) = crate::transport::webtransport::serve_h3(engine, conn).await {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("http/3 connection closed with error: {:?}", _e)
+            }
+        }
+    }
+
+    /// Handle a WebTransport session.
+    ///
+    /// With an HTTP/3 stack other than `quinn`, the HTTP/3 connection and the WebTransport session are handled
+    /// by your own stack, see [`EngineIoService::serve_webtransport`] otherwise.
+    /// Once the session is accepted, call this fn with the parts of the `CONNECT` request
+    /// and the first bidirectional stream opened by the client.
+    ///
+    /// The returned future resolves when the stream is closed.
+    /// If the request is not a valid engine.io WebTransport request or if the `WebTransport`
+    /// transport is not enabled in the config, it resolves immediately without touching the stream.
+    #[cfg(feature = "webtransport")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+    pub fn webtransport_session<T>(
+        &self,
+        req: http::request::Parts,
+        stream: T,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static
+    where
+        T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
+    {
+        let engine = self.engine.clone();
+        async move {
+            if !is_valid_webtransport_req(&req, &engine.config) {
+                return;
+            }
+            match crate::transport::webtransport::on_init(engine, stream, req).await {
+                Ok(_) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("webtransport stream closed")
+                }
+                Err(_e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("webtransport stream closed with error: {:?}", _e)
+                }
+            }
+        }
+    }
+
+    /// Replays a recorded [`Session`](crate::replay::Session) in memory against this service,
+    /// over a websocket connection that does not go through the network.
+    ///
+    /// The server has `timeout` to emit each expected message.
+    /// See the [`replay`](crate::replay) module for more details.
+    #[cfg(feature = "recorder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recorder")))]
+    pub async fn replay_session(
+        &self,
+        session: &crate::replay::Session,
+        timeout: std::time::Duration,
+    ) -> Result<crate::replay::ReplayReport, crate::replay::ReplayError> {
+        crate::replay::replay_in_memory(self.engine.clone(), session, timeout).await
+    }
 }
 
 impl<S: Clone, H: EngineIoHandler> Clone for EngineIoService<H, S> {
@@ -107,6 +213,25 @@ impl<H: EngineIoHandler, S> std::fmt::Debug for EngineIoService<H, S> {
     }
 }
 
+#[cfg(feature = "webtransport")]
+/// Returns true if the `CONNECT` request targets the engine.io path with a valid WebTransport query
+pub(crate) fn is_valid_webtransport_req(
+    req: &http::request::Parts,
+    config: &EngineIoConfig,
+) -> bool {
+    if !req.uri.path().starts_with(config.req_path.as_ref()) {
+        return false;
+    }
+    match self::parser::RequestInfo::parse_webtransport(req, config) {
+        Ok(_) => true,
+        Err(_e) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("invalid webtransport request: {:?}", _e);
+            false
+        }
+    }
+}
+
 /// Tower Service implementation.
 impl<H, ReqBody, ResBody, S> TowerSvc<Request<ReqBody>> for EngineIoService<H, S>
 where
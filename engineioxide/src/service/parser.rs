@@ -3,19 +3,20 @@
 use std::{str::FromStr, sync::Arc};
 
 use futures_core::Future;
-use http::{Method, Request, Response};
+use http::{HeaderMap, Method, Request, Response, Uri};
 
 use crate::{
     body::ResponseBody,
     config::EngineIoConfig,
     engine::EngineIo,
     handler::EngineIoHandler,
-    service::futures::ResponseFuture,
+    service::{cors, futures::ResponseFuture},
     sid::Sid,
     transport::{polling, ws},
 };
 
-/// Dispatch a request according to the [`RequestInfo`] to the appropriate [`transport`](crate::transport).
+/// Apply the [`CorsConfig`](crate::config::CorsConfig) if there is one
+/// and dispatch the request to the appropriate [`transport`](crate::transport).
 pub fn dispatch_req<F, H, ReqBody, ResBody>(
     req: Request<ReqBody>,
     engine: Arc<EngineIo<H>>,
@@ -28,7 +29,52 @@ where
     H: EngineIoHandler,
     F: Future,
 {
-    match RequestInfo::parse(&req, &engine.config) {
+    let mut headers = HeaderMap::new();
+    if let Some(cors_config) = &engine.config.cors {
+        let origin = req.headers().get(http::header::ORIGIN);
+        if !cors_config.is_allowed(origin) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("origin not allowed: {:?}", origin);
+            return ResponseFuture::empty_response(403);
+        }
+        if req.method() == Method::OPTIONS {
+            return ResponseFuture::ready(Ok(cors::preflight_response(cors_config, origin)));
+        }
+        cors::apply_headers(cors_config, origin, &mut headers);
+    }
+    dispatch_transport(req, engine, headers)
+}
+
+/// Dispatch a request according to the [`RequestInfo`] to the appropriate [`transport`](crate::transport).
+///
+/// The given headers and the ones set by the [`EngineIoHandler`] header hooks are added to the response.
+fn dispatch_transport<F, H, ReqBody, ResBody>(
+    req: Request<ReqBody>,
+    engine: Arc<EngineIo<H>>,
+    mut headers: HeaderMap,
+) -> ResponseFuture<F, ResBody>
+where
+    ReqBody: http_body::Body + Send + Unpin + 'static,
+    ReqBody::Data: Send,
+    ReqBody::Error: std::fmt::Debug,
+    ResBody: Send + 'static,
+    H: EngineIoHandler,
+    F: Future,
+{
+    let info = RequestInfo::parse(&req, &engine.config);
+    let req = match &info {
+        Ok(info) => {
+            let (parts, body) = req.into_parts();
+            if info.sid.is_none() && info.method == Method::GET {
+                engine.handler.on_initial_headers(&mut headers, &parts);
+            }
+            engine.handler.on_headers(&mut headers, &parts);
+            Request::from_parts(parts, body)
+        }
+        Err(_) => req,
+    };
+
+    let res = match info {
         Ok(RequestInfo {
             protocol,
             sid: None,
@@ -76,6 +122,11 @@ where
             tracing::debug!("invalid request: {:?}", _req);
             ResponseFuture::empty_response(400)
         }
+    };
+    if headers.is_empty() {
+        res
+    } else {
+        res.with_headers(headers)
     }
 }
 
@@ -154,6 +205,10 @@ pub enum TransportType {
     Polling = 0x01,
     /// Websocket transport
     Websocket = 0x02,
+    /// WebTransport transport (over HTTP/3)
+    #[cfg(feature = "webtransport")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+    WebTransport = 0x04,
 }
 
 impl From<u8> for TransportType {
@@ -161,6 +216,8 @@ impl From<u8> for TransportType {
         match t {
             0x01 => TransportType::Polling,
             0x02 => TransportType::Websocket,
+            #[cfg(feature = "webtransport")]
+            0x04 => TransportType::WebTransport,
             _ => panic!("unknown transport type"),
         }
     }
@@ -173,6 +230,8 @@ impl FromStr for TransportType {
         match s {
             "websocket" => Ok(TransportType::Websocket),
             "polling" => Ok(TransportType::Polling),
+            #[cfg(feature = "webtransport")]
+            "webtransport" => Ok(TransportType::WebTransport),
             _ => Err(ParseError::UnknownTransport),
         }
     }
@@ -182,15 +241,14 @@ impl From<TransportType> for &'static str {
         match t {
             TransportType::Polling => "polling",
             TransportType::Websocket => "websocket",
+            #[cfg(feature = "webtransport")]
+            TransportType::WebTransport => "webtransport",
         }
     }
 }
 impl From<TransportType> for String {
     fn from(t: TransportType) -> Self {
-        match t {
-            TransportType::Polling => "polling".into(),
-            TransportType::Websocket => "websocket".into(),
-        }
+        <&'static str>::from(t).into()
     }
 }
 
@@ -213,8 +271,32 @@ pub struct RequestInfo {
 impl RequestInfo {
     /// Parse the request URI to extract the [`TransportType`](crate::service::TransportType) and the socket id.
     fn parse<B>(req: &Request<B>, config: &EngineIoConfig) -> Result<Self, ParseError> {
+        Self::parse_uri(req.uri(), req.method().clone(), config)
+    }
+
+    /// Parse the request of a WebTransport session.
+    ///
+    /// WebTransport sessions are opened with an extended `CONNECT` request
+    /// which plays the same role as the `GET` handshake request of other transports.
+    #[cfg(feature = "webtransport")]
+    pub(crate) fn parse_webtransport(
+        req: &http::request::Parts,
+        config: &EngineIoConfig,
+    ) -> Result<Self, ParseError> {
+        let method = match req.method {
+            Method::CONNECT => Method::GET,
+            _ => return Err(ParseError::BadHandshakeMethod),
+        };
+        let info = Self::parse_uri(&req.uri, method, config)?;
+        if info.transport != TransportType::WebTransport || info.protocol != ProtocolVersion::V4 {
+            return Err(ParseError::TransportMismatch);
+        }
+        Ok(info)
+    }
+
+    fn parse_uri(uri: &Uri, method: Method, config: &EngineIoConfig) -> Result<Self, ParseError> {
         use ParseError::*;
-        let query = req.uri().query().ok_or(UnknownTransport)?;
+        let query = uri.query().ok_or(UnknownTransport)?;
 
         let protocol: ProtocolVersion = query
             .split('&')
@@ -247,7 +329,6 @@ impl RequestInfo {
             .map(|_| true)
             .unwrap_or_default();
 
-        let method = req.method().clone();
         if !matches!(method, Method::GET) && sid.is_none() {
             Err(BadHandshakeMethod)
         } else {
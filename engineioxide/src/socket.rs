@@ -56,7 +56,7 @@
 //! ```
 use std::{
     sync::{
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -67,17 +67,25 @@ use http::request::Parts;
 use smallvec::{smallvec, SmallVec};
 use tokio::{
     sync::{
+        mpsc::error::{SendTimeoutError, TrySendError},
         mpsc::{self},
-        mpsc::{error::TrySendError, Receiver},
-        Mutex,
+        Mutex, Notify,
     },
-    task::JoinHandle,
+    time::Instant,
 };
 use tokio_tungstenite::tungstenite;
 
+#[cfg(feature = "recorder")]
+use crate::recorder::{Direction, Recorder};
 use crate::{
-    config::EngineIoConfig, errors::Error, packet::Packet, peekable::PeekableReceiver,
-    service::ProtocolVersion, Str,
+    config::EngineIoConfig,
+    errors::Error,
+    heartbeat::{HeartbeatConfig, HeartbeatState},
+    packet::Packet,
+    peekable::PeekableReceiver,
+    service::ProtocolVersion,
+    stats::{Counters, SocketStats},
+    Str,
 };
 use crate::{service::TransportType, sid::Sid};
 
@@ -118,36 +126,95 @@ impl From<&Error> for Option<DisconnectReason> {
     }
 }
 
+/// An [`UpgradeError`] represents the reason why a transport upgrade failed.
+///
+/// When an upgrade fails, the [`Socket`] keeps using the polling transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeError {
+    /// The client did not complete the upgrade handshake within the configured
+    /// [`upgrade_timeout`](crate::config::EngineIoConfig::upgrade_timeout)
+    Timeout,
+    /// The client sent an unexpected packet during the upgrade handshake
+    BadHandshake,
+    /// The new transport was closed or an error occured in the transport layer during the upgrade handshake
+    TransportError,
+}
+
+/// Convert an [`Error`] that occured during an upgrade handshake to an [`UpgradeError`]
+impl From<&Error> for UpgradeError {
+    fn from(err: &Error) -> Self {
+        use Error::*;
+        match err {
+            UpgradeTimeout => UpgradeError::Timeout,
+            WsTransport(_) | Io(_) => UpgradeError::TransportError,
+            _ => UpgradeError::BadHandshake,
+        }
+    }
+}
+
 /// A permit to emit a message to the client.
 /// A permit holds a place in the internal channel to send one packet to the client.
 pub struct Permit<'a> {
-    inner: mpsc::Permit<'a, PacketBuf>,
+    inner: Lane<'a>,
+    /// The recorder of the socket, with its id and its transport at the time of the reservation
+    #[cfg(feature = "recorder")]
+    recorder: Option<(&'a Recorder, Sid, TransportType)>,
 }
 impl Permit<'_> {
     /// Consume the permit and emit a message to the client.
     #[inline]
-    pub fn emit(self, msg: String) {
-        self.inner.send(smallvec![Packet::Message(msg.into())]);
+    pub fn emit(self, msg: impl Into<Str>) {
+        self.send(smallvec![Packet::Message(msg.into())]);
     }
     /// Consume the permit and emit a binary message to the client.
     #[inline]
     pub fn emit_binary(self, data: Bytes) {
-        self.inner.send(smallvec![Packet::Binary(data)]);
+        self.send(smallvec![Packet::Binary(data)]);
     }
 
     /// Consume the permit and emit a message with multiple binary data to the client.
     ///
     /// It can be used to ensure atomicity when sending a string packet with adjacent binary packets.
-    pub fn emit_many(self, msg: String, data: Vec<Bytes>) {
+    pub fn emit_many(self, msg: impl Into<Str>, data: Vec<Bytes>) {
         let mut packets = SmallVec::with_capacity(data.len() + 1);
         packets.push(Packet::Message(msg.into()));
         for d in data {
             packets.push(Packet::Binary(d));
         }
-        self.inner.send(packets);
+        self.send(packets);
+    }
+
+    /// Consume the permit and send the packets to the connection, recording them if a recorder is set.
+    #[inline]
+    fn send(self, packets: PacketBuf) {
+        #[cfg(feature = "recorder")]
+        if let Some((recorder, sid, transport)) = self.recorder {
+            for packet in &packets {
+                recorder.record_packet(sid, Direction::Outgoing, transport, packet);
+            }
+        }
+        match self.inner {
+            Lane::Regular(permit, sent) => {
+                // Counted before being sent so that a control packet never overtakes it
+                sent.fetch_add(1, Ordering::AcqRel);
+                permit.send(packets);
+            }
+            Lane::Priority(permit) => permit.send(packets),
+            Lane::Control(permit, sent) => permit.send((sent.load(Ordering::Acquire), packets)),
+        }
     }
 }
 
+/// The lane of the internal channel where a [`Permit`] holds a place
+enum Lane<'a> {
+    /// The regular lane with the counter of the packets sent to it
+    Regular(mpsc::Permit<'a, PacketBuf>, &'a AtomicU64),
+    /// The heartbeat lane
+    Priority(mpsc::Permit<'a, PacketBuf>),
+    /// The control lane, ordered with the regular lane thanks to its counter
+    Control(mpsc::Permit<'a, (u64, PacketBuf)>, &'a AtomicU64),
+}
+
 /// Buffered packets to send to the client
 pub(crate) type PacketBuf = SmallVec<[Packet; 10]>;
 /// A [`Socket`] represents a client connection to the server.
@@ -193,17 +260,38 @@ where
     /// Channel to send [PacketBuf] to the internal connection
     internal_tx: mpsc::Sender<PacketBuf>,
 
-    /// Internal channel to receive Pong [`Packets`](Packet) (v4 protocol) or Ping (v3 protocol) in the heartbeat job
-    /// which is running in a separate task
-    heartbeat_rx: Mutex<Receiver<()>>,
-    /// Channel to send Ping [`Packets`](Packet) (v4 protocol) or Ping (v3 protocol) from the connexion to the heartbeat job
-    /// which is running in a separate task
-    pub(crate) heartbeat_tx: mpsc::Sender<()>,
-    /// Handle to the heartbeat job so that it can be aborted when the socket is closed
-    heartbeat_handle: Mutex<Option<JoinHandle<()>>>,
+    /// The number of [PacketBuf] sent to `internal_tx`, used to order the control packets with them
+    sent: AtomicU64,
+
+    /// Channel to send heartbeat [PacketBuf] (ping and pong) to the internal connection.
+    ///
+    /// It is drained before the `internal_tx` channel so that heartbeats are not delayed by a flood of messages.
+    priority_tx: mpsc::Sender<PacketBuf>,
+
+    /// Channel to send control [PacketBuf] (close, namespace disconnections, acknowledgements) to the internal connection.
+    ///
+    /// Each one is tagged with the number of packets sent to `internal_tx` before it and is sent right after them,
+    /// so that it stays ordered with the messages without being rejected when `internal_tx` is full.
+    control_tx: mpsc::Sender<(u64, PacketBuf)>,
+
+    /// A place reserved in the control lane for the final close packet, so that it is never lost
+    close_permit: std::sync::Mutex<Option<mpsc::OwnedPermit<(u64, PacketBuf)>>>,
+
+    /// Heartbeat state of the socket, driven by the [`HeartbeatScheduler`](crate::heartbeat::HeartbeatScheduler)
+    heartbeat: std::sync::Mutex<HeartbeatState>,
+
+    /// Counters updated by the transports, see [`Socket::stats`]
+    pub(crate) counters: Counters,
 
     /// Function to call when the socket is closed
     close_fn: Box<dyn Fn(Sid, DisconnectReason) + Send + Sync>,
+
+    /// The number of [`Socket::pause_read`] calls not yet matched by a [`Socket::resume_read`].
+    /// Packets are not read from the transport while it is not zero
+    read_paused: AtomicUsize,
+    /// Notified when reading from the transport is resumed with [`Socket::resume_read`]
+    read_resumed: Notify,
+
     /// User data bound to the socket
     pub data: D,
 
@@ -213,6 +301,10 @@ where
     /// If the client supports binary packets (via polling XHR2)
     #[cfg(feature = "v3")]
     pub(crate) supports_binary: bool,
+
+    /// The recorder of the engine, if any
+    #[cfg(feature = "recorder")]
+    recorder: Option<Arc<Recorder>>,
 }
 
 impl<D> Socket<D>
@@ -220,6 +312,7 @@ where
     D: Default + Send + Sync + 'static,
 {
     pub(crate) fn new(
+        sid: Sid,
         protocol: ProtocolVersion,
         transport: TransportType,
         config: &EngineIoConfig,
@@ -228,33 +321,42 @@ where
         #[cfg(feature = "v3")] supports_binary: bool,
     ) -> Self {
         let (internal_tx, internal_rx) = mpsc::channel(config.max_buffer_size);
-        let (heartbeat_tx, heartbeat_rx) = mpsc::channel(1);
+        let (priority_tx, priority_rx) = mpsc::channel(config.max_buffer_size);
+        // One more place is reserved for the close packet
+        let (control_tx, control_rx) = mpsc::channel(config.max_buffer_size + 1);
+        let close_permit = control_tx.clone().try_reserve_owned().ok();
 
         Self {
-            id: Sid::new(),
+            id: sid,
             protocol,
             transport: AtomicU8::new(transport as u8),
 
-            internal_rx: Mutex::new(PeekableReceiver::new(internal_rx)),
+            internal_rx: Mutex::new(PeekableReceiver::with_lanes(
+                internal_rx,
+                priority_rx,
+                control_rx,
+            )),
             internal_tx,
+            sent: AtomicU64::new(0),
+            priority_tx,
+            control_tx,
+            close_permit: std::sync::Mutex::new(close_permit),
 
-            heartbeat_rx: Mutex::new(heartbeat_rx),
-            heartbeat_tx,
-            heartbeat_handle: Mutex::new(None),
+            heartbeat: std::sync::Mutex::new(HeartbeatState::new()),
+            counters: Counters::default(),
             close_fn,
 
+            read_paused: AtomicUsize::new(0),
+            read_resumed: Notify::new(),
+
             data: D::default(),
             req_parts,
 
             #[cfg(feature = "v3")]
             supports_binary,
-        }
-    }
 
-    /// Abort the heartbeat job if it is running
-    pub(crate) fn abort_heartbeat(&self) {
-        if let Ok(Some(handle)) = self.heartbeat_handle.try_lock().map(|mut h| h.take()) {
-            handle.abort();
+            #[cfg(feature = "recorder")]
+            recorder: config.recorder.clone(),
         }
     }
 
@@ -262,113 +364,253 @@ where
     pub(crate) fn send(&self, packet: Packet) -> Result<(), TrySendError<Packet>> {
         #[cfg(feature = "tracing")]
         tracing::debug!("[sid={}] sending packet: {:?}", self.id, packet);
-        self.internal_tx
-            .try_send(smallvec![packet])
-            .map_err(|p| match p {
-                TrySendError::Full(mut p) => TrySendError::Full(p.pop().unwrap()),
-                TrySendError::Closed(mut p) => TrySendError::Closed(p.pop().unwrap()),
-            })?;
-        Ok(())
+        Self::send_with(self.reserve(), packet)
     }
 
-    /// Spawn the heartbeat job
-    ///
-    /// Keep a handle to the job so that it can be aborted when the socket is closed
-    pub(crate) fn spawn_heartbeat(self: Arc<Self>, interval: Duration, timeout: Duration) {
-        let socket = self.clone();
+    /// Sends a heartbeat packet to the connection, before any message waiting in the buffer.
+    pub(crate) fn send_priority(&self, packet: Packet) -> Result<(), TrySendError<Packet>> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("[sid={}] sending priority packet: {:?}", self.id, packet);
+        Self::send_with(self.reserve_priority(), packet)
+    }
 
-        let handle = tokio::spawn(async move {
-            if let Err(_e) = socket.heartbeat_job(interval, timeout).await {
-                socket.close(DisconnectReason::HeartbeatTimeout);
-                #[cfg(feature = "tracing")]
-                tracing::debug!("[sid={}] heartbeat error: {:?}", socket.id, _e);
+    /// Sends a packet with a reserved permit or gives it back with the reservation error.
+    fn send_with(
+        permit: Result<Permit<'_>, TrySendError<()>>,
+        packet: Packet,
+    ) -> Result<(), TrySendError<Packet>> {
+        match permit {
+            Ok(permit) => {
+                permit.send(smallvec![packet]);
+                Ok(())
             }
-        });
-        self.heartbeat_handle
-            .try_lock()
-            .expect("heartbeat handle mutex should not be locked twice")
-            .replace(handle);
+            Err(TrySendError::Full(())) => Err(TrySendError::Full(packet)),
+            Err(TrySendError::Closed(())) => Err(TrySendError::Closed(packet)),
+        }
     }
 
-    /// Heartbeat is sent every `interval` milliseconds by the client and the server is expected to respond within `timeout` milliseconds.
-    ///
-    /// If the client or server does not respond within the timeout, the connection is closed.
-    #[cfg(feature = "v3")]
-    async fn heartbeat_job(&self, interval: Duration, timeout: Duration) -> Result<(), Error> {
-        match self.protocol {
-            ProtocolVersion::V3 => self.heartbeat_job_v3(interval, timeout).await,
-            ProtocolVersion::V4 => self.heartbeat_job_v4(interval, timeout).await,
+    /// Records a packet received from the client, if a [`Recorder`](crate::recorder::Recorder) is set.
+    /// The transport is given by the caller because it may differ from the socket one during an upgrade.
+    #[inline]
+    pub(crate) fn record_recv(&self, _transport: TransportType, _packet: &Packet) {
+        #[cfg(feature = "recorder")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record_packet(self.id, Direction::Incoming, _transport, _packet);
         }
     }
 
-    /// Heartbeat is sent every `interval` milliseconds and the client is expected to respond within `timeout` milliseconds.
-    ///
-    /// If the client does not respond within the timeout, the connection is closed.
-    #[cfg(not(feature = "v3"))]
-    async fn heartbeat_job(&self, interval: Duration, timeout: Duration) -> Result<(), Error> {
-        self.heartbeat_job_v4(interval, timeout).await
+    /// Records a binary packet received from the client, if a [`Recorder`](crate::recorder::Recorder) is set.
+    #[inline]
+    pub(crate) fn record_recv_binary(&self, _transport: TransportType, _data: &[u8]) {
+        #[cfg(feature = "recorder")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record_data(self.id, Direction::Incoming, _transport, true, _data);
+        }
     }
 
-    /// Heartbeat is sent every `interval` milliseconds and the client is expected to respond within `timeout` milliseconds.
-    ///
-    /// If the client does not respond within the timeout, the connection is closed.
-    async fn heartbeat_job_v4(&self, interval: Duration, timeout: Duration) -> Result<(), Error> {
-        let mut heartbeat_rx = self
-            .heartbeat_rx
-            .try_lock()
-            .expect("Pong rx should be locked only once");
-
-        let instant = tokio::time::Instant::now();
-        let mut interval_tick = tokio::time::interval(interval);
-        interval_tick.tick().await;
-        // Sleep for an interval minus the time it took to get here
-        tokio::time::sleep(interval.saturating_sub(Duration::from_millis(
-            15 + instant.elapsed().as_millis() as u64,
-        )))
-        .await;
-
-        #[cfg(feature = "tracing")]
-        tracing::debug!("[sid={}] heartbeat sender routine started", self.id);
+    /// Records a packet written directly to the transport without going through the internal channel
+    /// (e.g. the pong of the upgrade handshake), if a [`Recorder`](crate::recorder::Recorder) is set.
+    #[inline]
+    pub(crate) fn record_send(&self, _transport: TransportType, _packet: &Packet) {
+        #[cfg(feature = "recorder")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record_packet(self.id, Direction::Outgoing, _transport, _packet);
+        }
+    }
 
-        loop {
-            // Some clients send the pong packet in first. If that happens, we should consume it.
-            heartbeat_rx.try_recv().ok();
-
-            self.internal_tx
-                .try_send(smallvec![Packet::Ping])
-                .map_err(|_| Error::HeartbeatTimeout)?;
-            tokio::time::timeout(timeout, heartbeat_rx.recv())
-                .await
-                .map_err(|_| Error::HeartbeatTimeout)?
-                .ok_or(Error::HeartbeatTimeout)?;
-            interval_tick.tick().await;
+    /// Wraps a permit of the internal channel with the recorder of the socket
+    #[inline]
+    fn permit<'a>(&'a self, inner: Lane<'a>) -> Permit<'a> {
+        Permit {
+            inner,
+            #[cfg(feature = "recorder")]
+            recorder: self
+                .recorder
+                .as_deref()
+                .map(|recorder| (recorder, self.id, self.transport_type())),
         }
     }
 
-    #[cfg(feature = "v3")]
-    async fn heartbeat_job_v3(&self, interval: Duration, timeout: Duration) -> Result<(), Error> {
-        let mut heartbeat_rx = self
-            .heartbeat_rx
-            .try_lock()
-            .expect("Pong rx should be locked only once");
+    /// Initializes the heartbeat state when the socket is registered to the [`HeartbeatScheduler`](crate::heartbeat::HeartbeatScheduler)
+    pub(crate) fn init_heartbeat(&self, config: &HeartbeatConfig) {
+        let mut state = self.heartbeat.lock().unwrap();
+        state.next_ping_at = match self.protocol {
+            // The first ping is sent slightly in advance, the client timer starts before the handshake is received
+            ProtocolVersion::V4 => {
+                let delay = config
+                    .strategy
+                    .next_ping(config.interval, self.transport_type());
+                Instant::now() + delay.saturating_sub(Duration::from_millis(15))
+            }
+            // With v3 it is the time of the last ping received from the client
+            ProtocolVersion::V3 => {
+                state.next_v3_ping_at = config
+                    .strategy
+                    .next_v3_ping(config.interval, self.transport_type())
+                    .map(|delay| Instant::now() + delay);
+                Instant::now()
+            }
+        };
+    }
 
-        #[cfg(feature = "tracing")]
-        tracing::debug!("[sid={}] heartbeat receiver routine started", self.id);
+    /// Called by the [`HeartbeatScheduler`](crate::heartbeat::HeartbeatScheduler) when a deadline of this socket is reached.
+    ///
+    /// With v4, the server sends a ping every interval and the client is expected to respond within the timeout.
+    /// With v3, the client sends a ping every interval and the server is expected to respond right away.
+    /// The server may also send pings to v3 clients if the strategy requests it, without expecting any response.
+    ///
+    /// If the client does not respond within the timeout, the connection is closed,
+    /// unless reading from the transport is paused with [`Socket::pause_read`] for less than `max_pause`.
+    /// Returns the next deadline or `None` if the socket is closed.
+    pub(crate) fn heartbeat_tick(
+        self: &Arc<Self>,
+        now: Instant,
+        config: &HeartbeatConfig,
+    ) -> Option<Instant> {
+        if self.is_closed() {
+            return None;
+        }
+        let mut state = self.heartbeat.lock().unwrap();
+        let deadline = match (self.protocol, state.ping_sent_at) {
+            (ProtocolVersion::V3, _) => {
+                let deadline = state.next_ping_at + config.interval + config.timeout;
+                match state.next_v3_ping_at {
+                    Some(ping_at) if now < deadline && ping_at <= now => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("[sid={}] sending v3 ping", self.id);
+                        // The ping is skipped if the priority lane is full, it is only a keepalive
+                        if let Ok(permit) = self.reserve_priority() {
+                            permit.send(smallvec![Packet::Ping]);
+                            state.ping_sent_at = Some(now);
+                        }
+                        state.next_v3_ping_at = config
+                            .strategy
+                            .next_v3_ping(config.interval, self.transport_type())
+                            .map(|delay| now + delay);
+                    }
+                    _ => (),
+                }
+                let wakeup = match state.next_v3_ping_at {
+                    Some(ping_at) => deadline.min(ping_at),
+                    None => deadline,
+                };
+                (now < deadline).then_some(wakeup)
+            }
+            // Wake up at the next ping too, in case the pong is received before the timeout
+            (ProtocolVersion::V4, Some(ping_sent_at)) => {
+                let deadline = ping_sent_at + config.timeout;
+                let wakeup = match state.next_ping_at {
+                    next_ping_at if next_ping_at > now => deadline.min(next_ping_at),
+                    _ => deadline,
+                };
+                (now < deadline).then_some(wakeup)
+            }
+            (ProtocolVersion::V4, None) if now < state.next_ping_at => Some(state.next_ping_at),
+            (ProtocolVersion::V4, None) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[sid={}] sending ping", self.id);
+                self.reserve_priority().ok().map(|permit| {
+                    permit.send(smallvec![Packet::Ping]);
+                    state.ping_sent_at = Some(now);
+                    state.next_ping_at = now
+                        + config
+                            .strategy
+                            .next_ping(config.interval, self.transport_type());
+                    state.next_ping_at.min(now + config.timeout)
+                })
+            }
+        };
+        drop(state);
+
+        // The heartbeat packets of the client cannot be read while reading is paused,
+        // the timeout restarts once it is resumed. It is not extended for more than `max_pause`,
+        // so that a pause that is never released does not keep the connection open forever.
+        if deadline.is_none() && self.is_read_paused() {
+            let mut state = self.heartbeat.lock().unwrap();
+            let max_deadline = *state.extended_at.get_or_insert(now) + config.max_pause;
+            if now < max_deadline {
+                return Some(max_deadline.min(now + config.timeout));
+            }
+        }
+        if deadline.is_none() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[sid={}] heartbeat timeout", self.id);
+            // The disconnect handlers are not run by the scheduler task,
+            // so that a slow handler does not delay the heartbeat of the other sockets
+            let socket = self.clone();
+            tokio::spawn(async move { socket.close(DisconnectReason::HeartbeatTimeout) });
+        }
+        deadline
+    }
 
-        loop {
-            tokio::time::timeout(interval + timeout, heartbeat_rx.recv())
-                .await
-                .map_err(|_| Error::HeartbeatTimeout)?
-                .ok_or(Error::HeartbeatTimeout)?;
+    /// Gives the client a full timeout to send its next heartbeat packet,
+    /// because the ones sent while reading was paused could not be read.
+    fn restart_heartbeat_timeout(&self) {
+        let now = Instant::now();
+        let mut state = self.heartbeat.lock().unwrap();
+        state.extended_at = None;
+        match self.protocol {
+            ProtocolVersion::V3 => state.next_ping_at = now,
+            ProtocolVersion::V4 => {
+                if let Some(ping_sent_at) = state.ping_sent_at.as_mut() {
+                    *ping_sent_at = now;
+                }
+            }
+        }
+    }
 
-            #[cfg(feature = "tracing")]
-            tracing::debug!("[sid={}] ping received, sending pong", self.id);
-            self.internal_tx
-                .try_send(smallvec![Packet::Pong])
-                .map_err(|_| Error::HeartbeatTimeout)?;
+    /// Called when a heartbeat packet is received from the client:
+    /// a pong with the protocol v4 or a ping with the protocol v3.
+    ///
+    /// With v3, a pong can also be received if the client answers the pings of the server.
+    pub(crate) fn recv_heartbeat(&self, packet: &Packet) -> Result<(), TrySendError<Packet>> {
+        let mut state = self.heartbeat.lock().unwrap();
+        state.last_pong = Some(Instant::now());
+        match (self.protocol, packet) {
+            // Some clients send the pong packet in first, it is then ignored.
+            (ProtocolVersion::V4, _) | (ProtocolVersion::V3, Packet::Pong) => {
+                if let Some(ping_sent_at) = state.ping_sent_at.take() {
+                    state.rtt = Some(ping_sent_at.elapsed());
+                }
+                Ok(())
+            }
+            (ProtocolVersion::V3, _) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[sid={}] ping received, sending pong", self.id);
+                state.next_ping_at = Instant::now();
+                drop(state);
+                self.send_priority(Packet::Pong)
+            }
         }
     }
 
+    /// Returns the round trip time measured between the last ping sent to the client and its pong.
+    ///
+    /// It is `None` until the first pong is received. With the protocol v3 the pings are sent by the client,
+    /// it stays `None` unless the server also pings the client with [`HeartbeatStrategy::next_v3_ping`](crate::heartbeat::HeartbeatStrategy::next_v3_ping)
+    /// and the client answers them.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.heartbeat.lock().unwrap().rtt
+    }
+
+    /// Returns a snapshot of the [`SocketStats`] of this socket:
+    /// latency, traffic, buffered packets and transport upgrades.
+    pub fn stats(&self) -> SocketStats {
+        let (rtt, last_pong) = {
+            let state = self.heartbeat.lock().unwrap();
+            (state.rtt, state.last_pong.map(Instant::into_std))
+        };
+        // The place reserved for the close packet is not a buffered packet
+        let reserved = self.close_permit.lock().unwrap().is_some() as usize;
+        let buffered = self.internal_tx.max_capacity() - self.internal_tx.capacity()
+            + self.priority_tx.max_capacity()
+            - self.priority_tx.capacity()
+            + self.control_tx.max_capacity()
+            - self.control_tx.capacity()
+            - reserved;
+        self.counters.snapshot(rtt, last_pong, buffered)
+    }
+
     /// Returns true if the [`Socket`] has a websocket [`TransportType`]
     pub(crate) fn is_ws(&self) -> bool {
         self.transport.load(Ordering::Relaxed) == TransportType::Websocket as u8
@@ -383,6 +625,16 @@ where
     pub(crate) fn upgrade_to_websocket(&self) {
         self.transport
             .store(TransportType::Websocket as u8, Ordering::Relaxed);
+        self.counters.upgraded();
+    }
+
+    /// Sets the [`TransportType`] to WebTransport
+    /// Used when the client upgrade the connection from HTTP to WebTransport
+    #[cfg(feature = "webtransport")]
+    pub(crate) fn upgrade_to_webtransport(&self) {
+        self.transport
+            .store(TransportType::WebTransport as u8, Ordering::Relaxed);
+        self.counters.upgraded();
     }
 
     /// Returns the current [`TransportType`] of the [`Socket`]
@@ -398,7 +650,64 @@ where
     #[inline]
     pub fn reserve(&self) -> Result<Permit<'_>, TrySendError<()>> {
         let permit = self.internal_tx.try_reserve()?;
-        Ok(Permit { inner: permit })
+        Ok(self.permit(Lane::Regular(permit, &self.sent)))
+    }
+
+    /// Reserve a permit on the priority lane of the internal chan.
+    /// The message will be sent before all the messages waiting in the regular lane.
+    ///
+    /// It should be kept for heartbeat packets that must not be delayed by a flood of messages.
+    /// The packets of this lane are not ordered with the regular messages, so it must not be used for packets
+    /// that depend on the ones sent before them (e.g. acknowledgements or a close packet).
+    ///
+    /// If the priority lane is full, the function will return a [`TrySendError::Full`] error.
+    /// If the socket is closed, the function will return a [`TrySendError::Closed`] error.
+    #[inline]
+    pub fn reserve_priority(&self) -> Result<Permit<'_>, TrySendError<()>> {
+        let permit = self.priority_tx.try_reserve()?;
+        Ok(self.permit(Lane::Priority(permit)))
+    }
+
+    /// Reserve a permit on the control lane of the internal chan.
+    /// The message will be sent right after the messages already sent with the regular lane,
+    /// but it does not need a place in the regular lane, which can be full.
+    ///
+    /// It should be kept for the packets that must not be lost when the client is slow to read
+    /// (e.g. acknowledgements or the disconnection of a namespace).
+    /// The control lane has the same capacity as the regular one.
+    ///
+    /// If the control lane is full, the function will return a [`TrySendError::Full`] error.
+    /// If the socket is closed, the function will return a [`TrySendError::Closed`] error.
+    #[inline]
+    pub fn reserve_control(&self) -> Result<Permit<'_>, TrySendError<()>> {
+        let permit = self.control_tx.try_reserve()?;
+        Ok(self.permit(Lane::Control(permit, &self.sent)))
+    }
+
+    /// Waits at most `timeout` for space in the internal chan and reserve a permit to emit a message.
+    ///
+    /// Unlike [`Socket::reserve`], producers can wait for the client to catch up instead of dropping messages.
+    ///
+    /// If the internal chan is still full after the timeout, the function will return a [`SendTimeoutError::Timeout`] error.
+    /// If the socket is closed, the function will return a [`SendTimeoutError::Closed`] error.
+    pub async fn reserve_async(
+        &self,
+        timeout: Duration,
+    ) -> Result<Permit<'_>, SendTimeoutError<()>> {
+        match tokio::time::timeout(timeout, self.internal_tx.reserve()).await {
+            Ok(Ok(permit)) => Ok(self.permit(Lane::Regular(permit, &self.sent))),
+            Ok(Err(_)) => Err(SendTimeoutError::Closed(())),
+            Err(_) => Err(SendTimeoutError::Timeout(())),
+        }
+    }
+
+    /// Returns the number of messages that can still be buffered before the internal chan is full.
+    ///
+    /// Compared to the [`max_buffer_size`](crate::config::EngineIoConfigBuilder::max_buffer_size),
+    /// it can be used as a watermark by producers to adapt their rate to the client.
+    #[inline]
+    pub fn buffer_capacity(&self) -> usize {
+        self.internal_tx.capacity()
     }
 
     /// Emits a message to the client.
@@ -415,11 +724,83 @@ where
         })
     }
 
+    /// Emits a message to the client, waiting at most `timeout` for space in the buffer if it is full.
+    ///
+    /// ⚠️ If the buffer is still full after the timeout or the socket is disconnected,
+    /// an error will be returned with the original data
+    pub async fn emit_async(
+        &self,
+        msg: impl Into<Str>,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<Str>> {
+        let msg = msg.into();
+        match self.reserve_async(timeout).await {
+            Ok(permit) => {
+                permit.emit(msg);
+                Ok(())
+            }
+            Err(SendTimeoutError::Timeout(())) => Err(SendTimeoutError::Timeout(msg)),
+            Err(SendTimeoutError::Closed(())) => Err(SendTimeoutError::Closed(msg)),
+        }
+    }
+
     /// Immediately closes the socket and the underlying connection.
     /// The socket will be removed from the `Engine` and the [`Handler`](crate::handler::EngineIoHandler) will be notified.
     pub fn close(&self, reason: DisconnectReason) {
+        // All the pauses are released so that the transport can observe the closing
+        self.read_paused.store(0, Ordering::Release);
+        self.read_resumed.notify_waiters();
         (self.close_fn)(self.id, reason);
-        self.send(Packet::Close).ok();
+        // The close packet is sent with the place reserved for it, after the messages sent before
+        if let Some(permit) = self.close_permit.lock().unwrap().take() {
+            self.record_send(self.transport_type(), &Packet::Close);
+            permit.send((self.sent.load(Ordering::Acquire), smallvec![Packet::Close]));
+        }
+    }
+
+    /// Stops reading packets from the transport until [`Socket::resume_read`] is called.
+    ///
+    /// It can be used to apply backpressure on the client when the handler cannot keep up with the incoming data:
+    /// * With websocket or webtransport, the next message is not read from the connection.
+    /// * With polling, the response to the current `POST` request is delayed.
+    ///
+    /// Heartbeat packets are not read either while reading is paused,
+    /// therefore the heartbeat timeout of the client restarts when reading is resumed.
+    /// It cannot be extended for more than [`max_read_pause`](crate::config::EngineIoConfig::max_read_pause) though,
+    /// the connection is then closed with [`DisconnectReason::HeartbeatTimeout`].
+    ///
+    /// Pauses can be requested by several independent parts of the application:
+    /// each call must be matched by a call to [`Socket::resume_read`], and reading resumes once all of them are released.
+    pub fn pause_read(&self) {
+        self.read_paused.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Releases a pause requested with [`Socket::pause_read`].
+    /// Reading packets from the transport resumes once all the pauses are released.
+    pub fn resume_read(&self) {
+        let prev = self
+            .read_paused
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+        if prev == Ok(1) {
+            self.restart_heartbeat_timeout();
+            self.read_resumed.notify_waiters();
+        }
+    }
+
+    /// Returns true if reading from the transport is paused
+    pub fn is_read_paused(&self) -> bool {
+        self.read_paused.load(Ordering::Acquire) > 0
+    }
+
+    /// Wait until reading from the transport is allowed
+    pub(crate) async fn read_ready(&self) {
+        loop {
+            let resumed = self.read_resumed.notified();
+            if !self.is_read_paused() {
+                return;
+            }
+            resumed.await;
+        }
     }
 
     /// Returns true if the socket is closed
@@ -461,9 +842,10 @@ impl<D: Default + Send + Sync + 'static> std::fmt::Debug for Socket<D> {
             .field("conn", &self.transport)
             .field("internal_rx", &self.internal_rx)
             .field("internal_tx", &self.internal_tx)
-            .field("heartbeat_rx", &self.heartbeat_rx)
-            .field("heartbeat_tx", &self.heartbeat_tx)
-            .field("heartbeat_handle", &self.heartbeat_handle)
+            .field("control_tx", &self.control_tx)
+            .field("priority_tx", &self.priority_tx)
+            .field("heartbeat", &self.heartbeat)
+            .field("counters", &self.counters)
             .field("req_data", &self.req_parts)
             .finish()
     }
@@ -489,7 +871,7 @@ where
     pub fn new_dummy(
         sid: Sid,
         close_fn: Box<dyn Fn(Sid, DisconnectReason) + Send + Sync>,
-    ) -> Arc<Socket<D>> {
+    ) -> std::sync::Arc<Socket<D>> {
         Socket::new_dummy_piped(sid, close_fn, 1024).0
     }
 
@@ -499,30 +881,48 @@ where
         sid: Sid,
         close_fn: Box<dyn Fn(Sid, DisconnectReason) + Send + Sync>,
         buffer_size: usize,
-    ) -> (Arc<Socket<D>>, tokio::sync::mpsc::Receiver<Packet>) {
+    ) -> (
+        std::sync::Arc<Socket<D>>,
+        tokio::sync::mpsc::Receiver<Packet>,
+    ) {
         let (internal_tx, internal_rx) = mpsc::channel(buffer_size);
-        let (heartbeat_tx, heartbeat_rx) = mpsc::channel(1);
+        let (priority_tx, priority_rx) = mpsc::channel(buffer_size);
+        let (control_tx, control_rx) = mpsc::channel(buffer_size + 1);
+        let close_permit = control_tx.clone().try_reserve_owned().ok();
 
         let sock = Self {
             id: sid,
             protocol: ProtocolVersion::V4,
             transport: AtomicU8::new(TransportType::Websocket as u8),
 
-            internal_rx: Mutex::new(PeekableReceiver::new(internal_rx)),
+            internal_rx: Mutex::new(PeekableReceiver::with_lanes(
+                internal_rx,
+                priority_rx,
+                control_rx,
+            )),
             internal_tx,
+            sent: AtomicU64::new(0),
+            priority_tx,
+            control_tx,
+            close_permit: std::sync::Mutex::new(close_permit),
 
-            heartbeat_rx: Mutex::new(heartbeat_rx),
-            heartbeat_tx,
-            heartbeat_handle: Mutex::new(None),
+            heartbeat: std::sync::Mutex::new(HeartbeatState::new()),
+            counters: Counters::default(),
             close_fn,
 
+            read_paused: AtomicUsize::new(0),
+            read_resumed: Notify::new(),
+
             data: D::default(),
             req_parts: http::Request::<()>::default().into_parts().0,
 
             #[cfg(feature = "v3")]
             supports_binary: true,
+
+            #[cfg(feature = "recorder")]
+            recorder: None,
         };
-        let sock = Arc::new(sock);
+        let sock = std::sync::Arc::new(sock);
 
         let (tx, rx) = mpsc::channel(buffer_size);
         let sock_clone = sock.clone();
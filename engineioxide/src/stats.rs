@@ -0,0 +1,122 @@
+//! ## Statistics about the connection of a [`Socket`](crate::Socket)
+//!
+//! The counters of a socket are updated by the transports as packets are sent and received.
+//! A [`SocketStats`] snapshot of these counters can be retrieved at any time with [`Socket::stats`](crate::Socket::stats).
+//!
+//! #### Example :
+//! ```rust
+//! # use bytes::Bytes;
+//! # use engineioxide::{handler::EngineIoHandler, Socket, DisconnectReason, Str};
+//! # use std::sync::Arc;
+//! #[derive(Debug)]
+//! struct MyHandler;
+//!
+//! impl EngineIoHandler for MyHandler {
+//!     type Data = ();
+//!
+//!     fn on_connect(&self, socket: Arc<Socket<()>>) { }
+//!     fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+//!         let stats = socket.stats();
+//!         println!("{} bytes sent, {} bytes received", stats.bytes_sent, stats.bytes_received);
+//!     }
+//!     fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+//!         // Slow down clients with a high latency
+//!         if socket.stats().rtt.is_some_and(|rtt| rtt.as_millis() > 500) {
+//!             return;
+//!         }
+//!         socket.emit(msg).ok();
+//!     }
+//!     fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) { }
+//! }
+//! ```
+use std::{
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// A snapshot of the statistics of a [`Socket`](crate::Socket).
+///
+/// The bytes are counted at the transport level:
+/// websocket and webtransport frames, or http polling payloads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketStats {
+    /// The last round trip time measured between a ping and its pong.
+    /// It is always `None` with the protocol v3 because the pings are sent by the client.
+    pub rtt: Option<Duration>,
+    /// When the last heartbeat packet was received from the client:
+    /// a pong with the protocol v4 or a ping with the protocol v3
+    pub last_pong: Option<Instant>,
+    /// The number of packets sent to the client
+    pub packets_sent: u64,
+    /// The number of bytes sent to the client
+    pub bytes_sent: u64,
+    /// The number of packets received from the client
+    pub packets_received: u64,
+    /// The number of bytes received from the client
+    pub bytes_received: u64,
+    /// The number of packets waiting in the internal buffer to be sent to the client,
+    /// heartbeat packets included.
+    /// Packets emitted together with [`Permit::emit_many`](crate::socket::Permit::emit_many) count as one.
+    pub buffered_packets: usize,
+    /// When the socket was upgraded from polling to another transport
+    pub upgraded_at: Option<Instant>,
+    /// The number of times the socket switched of transport
+    pub transport_switches: u32,
+}
+
+/// The counters of a socket, updated by the transports
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    packets_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    packets_received: AtomicU64,
+    bytes_received: AtomicU64,
+    transport_switches: AtomicU32,
+    upgraded_at: Mutex<Option<Instant>>,
+}
+
+impl Counters {
+    /// Records packets sent to the client in a frame or payload of `bytes` bytes
+    pub fn sent(&self, packets: usize, bytes: usize) {
+        self.packets_sent
+            .fetch_add(packets as u64, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records packets received from the client in a frame or payload of `bytes` bytes
+    pub fn received(&self, packets: usize, bytes: usize) {
+        self.packets_received
+            .fetch_add(packets as u64, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records a transport upgrade
+    pub fn upgraded(&self) {
+        self.transport_switches.fetch_add(1, Ordering::Relaxed);
+        *self.upgraded_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Creates a [`SocketStats`] snapshot from the counters and the socket state
+    pub fn snapshot(
+        &self,
+        rtt: Option<Duration>,
+        last_pong: Option<Instant>,
+        buffered_packets: usize,
+    ) -> SocketStats {
+        SocketStats {
+            rtt,
+            last_pong,
+            packets_sent: self.packets_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            buffered_packets,
+            upgraded_at: *self.upgraded_at.lock().unwrap(),
+            transport_switches: self.transport_switches.load(Ordering::Relaxed),
+        }
+    }
+}
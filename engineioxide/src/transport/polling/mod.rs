@@ -1,11 +1,11 @@
 //! The polling transport module handles polling, post and init requests
 use std::sync::Arc;
 
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 use futures_util::StreamExt;
-use http::{Request, Response, StatusCode};
+use http::{header::SET_COOKIE, Request, Response, StatusCode};
 use http_body::Body;
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full};
 
 use crate::{
     body::ResponseBody,
@@ -54,6 +54,7 @@ where
     B: Send + 'static,
 {
     let socket = engine.create_session(
+        Sid::new(),
         protocol,
         TransportType::Polling,
         req.into_parts().0,
@@ -61,9 +62,10 @@ where
         supports_binary,
     );
 
-    let packet = OpenPacket::new(TransportType::Polling, socket.id, &engine.config);
+    let sid = socket.id;
+    let packet = OpenPacket::new(TransportType::Polling, sid, &engine.config);
 
-    socket.spawn_heartbeat(engine.config.ping_interval, engine.config.ping_timeout);
+    engine.heartbeat.register(&socket);
 
     let packet: String = Packet::Open(packet).try_into().unwrap();
     let packet = {
@@ -80,7 +82,11 @@ where
         #[cfg(not(feature = "v3"))]
         packet
     };
-    http_response(StatusCode::OK, packet, false).map_err(Error::Http)
+    let mut res = http_response(StatusCode::OK, packet, false).map_err(Error::Http)?;
+    if let Some(cookie) = &engine.config.cookie {
+        res.headers_mut().append(SET_COOKIE, cookie.to_header(sid));
+    }
+    Ok(res)
 }
 
 /// Handle http polling request
@@ -117,10 +123,18 @@ where
     let max_payload = engine.config.max_payload;
 
     #[cfg(feature = "v3")]
-    let Payload { data, has_binary } =
-        payload::encoder(rx, protocol, socket.supports_binary, max_payload).await?;
+    let Payload {
+        data,
+        has_binary,
+        packets,
+    } = payload::encoder(rx, protocol, socket.supports_binary, max_payload).await?;
     #[cfg(not(feature = "v3"))]
-    let Payload { data, has_binary } = payload::encoder(rx, protocol, max_payload).await?;
+    let Payload {
+        data,
+        has_binary,
+        packets,
+    } = payload::encoder(rx, protocol, max_payload).await?;
+    socket.counters.sent(packets, data.len());
 
     #[cfg(feature = "tracing")]
     tracing::debug!("[sid={sid}] sending data: {:?}", data);
@@ -148,10 +162,24 @@ where
         return Err(Error::TransportMismatch);
     }
 
+    // The body is decoded as a stream, its length is counted as its chunks are read
+    let counters = &socket.counters;
+    let body = body.map(|body| {
+        body.map_frame(|frame| {
+            if let Some(data) = frame.data_ref() {
+                counters.received(0, data.remaining());
+            }
+            frame
+        })
+    });
     let packets = payload::decoder(body, protocol, engine.config.max_payload);
     futures_util::pin_mut!(packets);
 
     while let Some(packet) = packets.next().await {
+        if let Ok(packet) = &packet {
+            socket.counters.received(1, 0);
+            socket.record_recv(TransportType::Polling, packet);
+        }
         match packet {
             Ok(Packet::Close) => {
                 #[cfg(feature = "tracing")]
@@ -160,10 +188,7 @@ where
                 engine.close_session(sid, DisconnectReason::TransportClose);
                 break;
             }
-            Ok(Packet::Pong | Packet::Ping) => socket
-                .heartbeat_tx
-                .try_send(())
-                .map_err(|_| Error::HeartbeatTimeout),
+            Ok(p @ (Packet::Pong | Packet::Ping)) => socket.recv_heartbeat(&p).map_err(Error::from),
             Ok(Packet::Message(msg)) => {
                 engine.handler.on_message(msg, socket.clone());
                 Ok(())
@@ -184,6 +209,8 @@ where
                 return Err(e);
             }
         }?;
+        // Wait before reading the next packet if the handler applies backpressure
+        socket.read_ready().await;
     }
     Ok(http_response(StatusCode::OK, "ok", false)?)
 }
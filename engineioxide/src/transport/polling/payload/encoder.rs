@@ -81,12 +81,15 @@ pub async fn v4_encoder(
     #[cfg(feature = "tracing")]
     tracing::debug!("encoding payload with v4 encoder");
     let mut data: String = String::new();
+    // number of packets added to the payload
+    let mut count = 0;
 
     // Send all packets in the buffer
     const PUNCTUATION_LEN: usize = 1;
     while let Some(packets) =
         try_recv_packet(&mut rx, data.len() + PUNCTUATION_LEN, max_payload, true)
     {
+        count += packets.len();
         for packet in packets {
             let packet: String = packet.try_into()?;
 
@@ -100,13 +103,14 @@ pub async fn v4_encoder(
     // If there is no packet in the buffer, wait for the next packet
     if data.is_empty() {
         let packets = recv_packet(&mut rx).await?;
+        count += packets.len();
         for packet in packets {
             let packet: String = packet.try_into()?;
             data.push_str(&packet);
         }
     }
 
-    Ok(Payload::new(data.into(), false))
+    Ok(Payload::new(data.into(), false, count))
 }
 
 /// Encode one packet into a *binary* payload according to the
@@ -176,6 +180,8 @@ pub async fn v3_binary_encoder(
 ) -> Result<Payload, Error> {
     let mut data = bytes::BytesMut::new();
     let mut packet_buffer: Vec<Packet> = Vec::new();
+    // number of packets added to the payload
+    let mut count = 0;
 
     // estimated size of the `packet_buffer` in bytes
     let mut estimated_size: usize = 0;
@@ -188,6 +194,7 @@ pub async fn v3_binary_encoder(
     let mut has_binary = false;
 
     while let Some(packets) = try_recv_packet(&mut rx, estimated_size, max_payload, false) {
+        count += packets.len();
         for packet in packets {
             if packet.is_binary() {
                 has_binary = true;
@@ -213,6 +220,7 @@ pub async fn v3_binary_encoder(
     // If there is no packet in the buffer, wait for the next packet
     if data.is_empty() {
         let packets = recv_packet(&mut rx).await?;
+        count += packets.len();
         for packet in packets {
             match packet {
                 Packet::BinaryV3(_) | Packet::Binary(_) => {
@@ -228,7 +236,7 @@ pub async fn v3_binary_encoder(
 
     #[cfg(feature = "tracing")]
     tracing::debug!("sending packet: {:?}", &data);
-    Ok(Payload::new(data.freeze(), has_binary))
+    Ok(Payload::new(data.freeze(), has_binary, count))
 }
 
 /// Encode multiple packet packet into a *string* payload according to the
@@ -239,6 +247,8 @@ pub async fn v3_string_encoder(
     max_payload: u64,
 ) -> Result<Payload, Error> {
     let mut data = bytes::BytesMut::new();
+    // number of packets added to the payload
+    let mut count = 0;
 
     #[cfg(feature = "tracing")]
     tracing::debug!("encoding payload with v3 string encoder");
@@ -249,6 +259,7 @@ pub async fn v3_string_encoder(
     // Current size of the payload
     let current_size = data.len() + PUNCTUATION_LEN + max_packet_size_len;
     while let Some(packets) = try_recv_packet(&mut rx, current_size, max_payload, true) {
+        count += packets.len();
         for packet in packets {
             v3_string_packet_encoder(packet, &mut data)?;
         }
@@ -257,12 +268,13 @@ pub async fn v3_string_encoder(
     // If there is no packet in the buffer, wait for the next packet
     if data.is_empty() {
         let packets = recv_packet(&mut rx).await?;
+        count += packets.len();
         for packet in packets {
             v3_string_packet_encoder(packet, &mut data)?;
         }
     }
 
-    Ok(Payload::new(data.freeze(), false))
+    Ok(Payload::new(data.freeze(), false, count))
 }
 
 #[cfg(test)]
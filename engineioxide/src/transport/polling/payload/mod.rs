@@ -57,10 +57,16 @@ pub fn decoder(
 pub struct Payload {
     pub data: Bytes,
     pub has_binary: bool,
+    /// The number of packets encoded in the payload
+    pub packets: usize,
 }
 impl Payload {
-    pub fn new(data: Bytes, has_binary: bool) -> Self {
-        Self { data, has_binary }
+    pub fn new(data: Bytes, has_binary: bool, packets: usize) -> Self {
+        Self {
+            data,
+            has_binary,
+            packets,
+        }
     }
 }
 
@@ -0,0 +1,567 @@
+//! The webtransport transport module is responsible for handling WebTransport sessions.
+//!
+//! The HTTP/3 connections accepted with `quinn` are served by [`serve_h3`]: it accepts the WebTransport
+//! sessions and gives the first bidirectional stream opened by the client in each session to [`on_init`].
+//! With another HTTP/3 stack, the session is accepted by the user and the stream is given directly to [`on_init`].
+//!
+//! On this stream, each engine.io packet is prefixed with a header containing its length,
+//! encoded the same way as websocket frame lengths:
+//! * `0..=125`: the length is stored in the first byte
+//! * `126`: the length is stored in the next 2 bytes (big endian)
+//! * `127`: the length is stored in the next 8 bytes (big endian)
+//!
+//! The most significant bit of the first byte is set when the payload is binary.
+//! Text payloads are regular engine.io packets (e.g. `4hello`) and binary payloads are raw data.
+
+use std::{collections::HashMap, future::poll_fn, sync::Arc, task::Poll};
+
+use bytes::Bytes;
+use futures_util::{stream::FuturesUnordered, StreamExt, TryFutureExt};
+use h3::{
+    error::Code,
+    ext::Protocol,
+    frame::FrameStream,
+    proto::{frame::Frame as H3Frame, varint::VarInt},
+    quic::{RecvStream, SendStream},
+    stream::BufRecvStream,
+    webtransport::SessionId,
+};
+use http::{request::Parts, Method, Response, StatusCode};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+    task::JoinHandle,
+};
+
+use crate::{
+    engine::EngineIo,
+    errors::Error,
+    handler::EngineIoHandler,
+    packet::{OpenPacket, Packet},
+    service::{is_valid_webtransport_req, ProtocolVersion, TransportType},
+    sid::Sid,
+    DisconnectReason, Socket, UpgradeError,
+};
+
+/// Flag set on the first byte of the header if the payload is binary
+const BINARY_FLAG: u8 = 0x80;
+
+/// The number of WebTransport sessions a client can open on the same HTTP/3 connection
+const MAX_SESSIONS: u64 = 16;
+
+/// A frame read from the webtransport stream
+#[derive(Debug, PartialEq)]
+enum Frame {
+    Text(String),
+    Binary(Bytes),
+}
+
+impl Frame {
+    fn len(&self) -> usize {
+        match self {
+            Frame::Text(data) => data.len(),
+            Frame::Binary(data) => data.len(),
+        }
+    }
+}
+
+/// Handle a new WebTransport stream
+///
+/// The first packet sent by the client must be an open packet:
+/// * If it contains a sid, the corresponding polling session is upgraded.
+/// * Otherwise a new session is created and an open packet is sent back.
+///
+/// Read packets from the stream and handle them, it will block until the stream is closed
+pub async fn on_init<H: EngineIoHandler, S>(
+    engine: Arc<EngineIo<H>>,
+    stream: S,
+    req_data: Parts,
+) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (rx, tx) = tokio::io::split(stream);
+    let (mut rx, mut tx) = (BufReader::new(rx), BufWriter::new(tx));
+    let max_payload = engine.config.max_payload;
+
+    #[derive(serde::Deserialize)]
+    struct OpenData {
+        sid: Sid,
+    }
+    // The first packet must be an open packet.
+    // If it contains data, it is the sid of the polling session to upgrade.
+    let sid = match read_frame(&mut rx, max_payload).await? {
+        Some(Frame::Text(packet)) if packet == "0" => None,
+        Some(Frame::Text(packet)) if packet.starts_with('0') => {
+            Some(serde_json::from_str::<OpenData>(&packet[1..])?.sid)
+        }
+        _ => return Err(Error::Upgrade),
+    };
+
+    let socket = if let Some(sid) = sid {
+        match engine.get_socket(sid) {
+            None => return Err(Error::UnknownSessionID(sid)),
+            Some(socket) if !socket.is_http() => return Err(Error::Upgrade),
+            Some(socket) => {
+                let upgrade = upgrade_handshake::<H, _, _>(&socket, &mut rx, &mut tx, max_payload);
+                let res = tokio::time::timeout(engine.config.upgrade_timeout, upgrade)
+                    .await
+                    .unwrap_or(Err(Error::UpgradeTimeout));
+                let upgrade_res = res.as_ref().map(|_| ()).map_err(UpgradeError::from);
+                engine
+                    .handler
+                    .on_upgrade(socket.clone(), TransportType::WebTransport, upgrade_res);
+                if let Err(e) = res {
+                    // The socket is kept on the polling transport
+                    tx.shutdown().await.ok();
+                    return Err(e);
+                }
+                socket
+            }
+        }
+    } else {
+        let socket = engine.create_session(
+            Sid::new(),
+            ProtocolVersion::V4,
+            TransportType::WebTransport,
+            req_data,
+            #[cfg(feature = "v3")]
+            true,
+        );
+        #[cfg(feature = "tracing")]
+        tracing::debug!("[sid={}] new webtransport connection", socket.id);
+        let packet = Packet::Open(OpenPacket::new(
+            TransportType::WebTransport,
+            socket.id,
+            &engine.config,
+        ));
+        write_packet(&mut tx, packet).await?;
+        tx.flush().await?;
+        engine.heartbeat.register(&socket);
+        socket
+    };
+
+    let tx_handle = forward_to_socket::<H, _>(socket.clone(), tx);
+
+    if let Err(ref e) = forward_to_handler(&engine, rx, &socket).await {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("[sid={}] error when handling packet: {:?}", socket.id, e);
+        if let Some(reason) = e.into() {
+            engine.close_session(socket.id, reason);
+        }
+    } else {
+        engine.close_session(socket.id, DisconnectReason::TransportClose);
+    }
+    tx_handle.abort();
+    Ok(())
+}
+
+/// An event of an HTTP/3 connection served by [`serve_h3`]
+enum Accepted<S> {
+    /// A new bidirectional stream, or `None` if the connection is closed
+    Stream(Option<S>),
+    /// The first frame of a bidirectional stream, which tells if it is a request or a WebTransport stream
+    Frame(
+        Box<FrameStream<S, Bytes>>,
+        Result<Option<H3Frame<h3::proto::frame::PayloadLen>>, h3::frame::FrameStreamError>,
+    ),
+}
+
+/// Serves an HTTP/3 connection accepted with `quinn`.
+///
+/// Every `CONNECT` request with the `webtransport` protocol that targets the engine.io path opens a session,
+/// other requests are answered with a `400 Bad Request`.
+/// The first bidirectional stream opened by the client in a session is given to [`on_init`],
+/// the session is closed with the engine.io session.
+///
+/// It returns when the connection is closed.
+pub(crate) async fn serve_h3<H: EngineIoHandler>(
+    engine: Arc<EngineIo<H>>,
+    conn: quinn::Connection,
+) -> Result<(), h3::error::ConnectionError> {
+    let mut conn = h3::server::builder()
+        .enable_webtransport(true)
+        .enable_extended_connect(true)
+        .max_webtransport_sessions(MAX_SESSIONS)
+        .build(h3_quinn::Connection::new(conn))
+        .await?;
+
+    // The sessions waiting for their first bidirectional stream
+    let mut sessions = HashMap::new();
+    // The streams waiting for their first frame
+    let mut pending = FuturesUnordered::new();
+    loop {
+        let accepted = poll_fn(|cx| match pending.poll_next_unpin(cx) {
+            Poll::Ready(Some((stream, frame))) => Poll::Ready(Ok(Accepted::Frame(stream, frame))),
+            _ => conn.poll_accept_request_stream(cx).map_ok(Accepted::Stream),
+        })
+        .await?;
+
+        match accepted {
+            Accepted::Stream(None) => return Ok(()),
+            Accepted::Stream(Some(stream)) => {
+                let mut stream = FrameStream::new(BufRecvStream::new(stream));
+                pending.push(async move {
+                    let frame = poll_fn(|cx| stream.poll_next(cx)).await;
+                    (Box::new(stream), frame)
+                });
+            }
+            Accepted::Frame(stream, Ok(Some(H3Frame::WebTransportStream(id)))) => {
+                let mut stream = stream.into_inner();
+                let Some((req, session)) = sessions.remove(&id) else {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("webtransport stream for an unknown session {:?}", id);
+                    stream.stop_sending(Code::H3_REQUEST_REJECTED.value());
+                    stream.reset(Code::H3_REQUEST_REJECTED.value());
+                    continue;
+                };
+                let engine = engine.clone();
+                tokio::spawn(async move {
+                    // The session lives as long as its engine.io stream
+                    let _session = session;
+                    if let Err(_e) = on_init(engine, stream, req).await {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("webtransport stream closed with error: {:?}", _e)
+                    }
+                });
+            }
+            Accepted::Frame(stream, frame) => {
+                let req = conn
+                    .create_resolver(*stream)
+                    .accept_with_frame(frame)
+                    .map(|req| req.resolve());
+                let (req, mut session) =
+                    match futures_util::future::ready(req).and_then(|req| req).await {
+                        Ok(req) => req,
+                        Err(_e) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!("invalid http/3 request: {:?}", _e);
+                            continue;
+                        }
+                    };
+                let (req, _) = req.into_parts();
+                let valid = req.method == Method::CONNECT
+                    && req.extensions.get::<Protocol>() == Some(&Protocol::WEB_TRANSPORT)
+                    && is_valid_webtransport_req(&req, &engine.config);
+                let res = Response::builder()
+                    .status(if valid {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::BAD_REQUEST
+                    })
+                    .header("sec-webtransport-http3-draft", "draft02")
+                    .body(())
+                    .unwrap();
+                match session.send_response(res).await {
+                    Ok(()) if valid => {
+                        // The id of a session is the id of its CONNECT stream
+                        let id = VarInt::from(session.id()).into_inner();
+                        sessions.insert(SessionId::try_from(id).unwrap(), (req, session));
+                    }
+                    _ => {
+                        session.finish().await.ok();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Forwards all packets received from the stream to a EngineIo [`Socket`]
+async fn forward_to_handler<H: EngineIoHandler, R>(
+    engine: &Arc<EngineIo<H>>,
+    mut rx: R,
+    socket: &Arc<Socket<H::Data>>,
+) -> Result<(), Error>
+where
+    R: AsyncRead + Unpin,
+{
+    while let Some(frame) = read_frame(&mut rx, engine.config.max_payload).await? {
+        socket.counters.received(1, frame.len());
+        match frame {
+            Frame::Text(msg) => {
+                let packet = Packet::try_from(msg)?;
+                socket.record_recv(TransportType::WebTransport, &packet);
+                match packet {
+                    Packet::Close => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("[sid={}] closing session", socket.id);
+                        engine.close_session(socket.id, DisconnectReason::TransportClose);
+                        break;
+                    }
+                    p @ (Packet::Pong | Packet::Ping) => {
+                        socket.recv_heartbeat(&p).map_err(Error::from)
+                    }
+                    Packet::Message(msg) => {
+                        engine.handler.on_message(msg, socket.clone());
+                        Ok(())
+                    }
+                    p => return Err(Error::BadPacket(p)),
+                }
+            }
+            Frame::Binary(data) => {
+                socket.record_recv_binary(TransportType::WebTransport, &data);
+                engine.handler.on_binary(data, socket.clone());
+                Ok(())
+            }
+        }?;
+        // Wait before reading the next packet if the handler applies backpressure
+        socket.read_ready().await;
+    }
+    Ok(())
+}
+
+/// Forwards all packets waiting to be sent to the stream
+///
+/// The stream is flushed only when the internal channel is drained
+fn forward_to_socket<H: EngineIoHandler, W>(
+    socket: Arc<Socket<H::Data>>,
+    mut tx: BufWriter<W>,
+) -> JoinHandle<()>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut internal_rx = socket.internal_rx.try_lock().unwrap();
+
+        // map a packet to a frame and write it to the stream
+        // It is declared as a macro rather than a closure to avoid ownership issues
+        macro_rules! map_fn {
+            ($item:ident) => {
+                let res = match $item {
+                    Packet::Close => {
+                        tx.shutdown().await.ok();
+                        internal_rx.close();
+                        break;
+                    }
+                    // A Noop Packet maybe sent by the server to upgrade from a polling connection
+                    // In the case that the packet was not poll in time it will remain in the buffer and therefore
+                    // it should be discarded here
+                    Packet::Noop => Ok(()),
+                    packet => write_packet(&mut tx, packet)
+                        .await
+                        .map(|len| socket.counters.sent(1, len)),
+                };
+                if let Err(_e) = res {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[sid={}] error sending packet: {}", socket.id, _e);
+                }
+            };
+        }
+
+        while let Some(items) = internal_rx.recv().await {
+            for item in items {
+                map_fn!(item);
+            }
+            // For every available packet we continue to send until the channel is drained
+            while let Ok(items) = internal_rx.try_recv() {
+                for item in items {
+                    map_fn!(item);
+                }
+            }
+
+            tx.flush().await.ok();
+        }
+    })
+}
+
+/// Upgrade a session from a polling request to a webtransport stream.
+///
+/// The handshake is the same as the websocket one, except that it is initiated
+/// by an open packet containing the sid of the session to upgrade:
+/// ```text
+/// CLIENT                                                 SERVER
+///│                                                      │
+///│            -----  WebTransport frames -----          │
+///│  ─────────────────────────────────────────────────►  │
+///│                    0{"sid":"..."}                    │ (open packet)
+///│  ─────────────────────────────────────────────────►  │
+///│                         2probe                       │ (ping packet)
+///│  ◄─────────────────────────────────────────────────  │
+///│                         3probe                       │ (pong packet)
+///│  ─────────────────────────────────────────────────►  │
+///│                         5                            │ (upgrade packet)
+///│                                                      │
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(socket, rx, tx), fields(sid = socket.id.to_string())))]
+async fn upgrade_handshake<H: EngineIoHandler, R, W>(
+    socket: &Arc<Socket<H::Data>>,
+    rx: &mut R,
+    tx: &mut W,
+    max_payload: u64,
+) -> Result<(), Error>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    #[cfg(feature = "tracing")]
+    tracing::debug!("webtransport connection upgrade");
+
+    // Fetch the next packet from the stream, it should be a PingUpgrade packet
+    match read_frame(rx, max_payload).await? {
+        Some(Frame::Text(msg)) => match record_recv(socket, Packet::try_from(msg)?) {
+            Packet::PingUpgrade => {
+                // Respond with a PongUpgrade packet
+                socket.record_send(TransportType::WebTransport, &Packet::PongUpgrade);
+                write_packet(tx, Packet::PongUpgrade).await?;
+                tx.flush().await?;
+            }
+            p => Err(Error::BadPacket(p))?,
+        },
+        _ => Err(Error::Upgrade)?,
+    };
+
+    // send a NOOP packet to any pending polling request so it closes gracefully
+    socket.send(Packet::Noop)?;
+
+    // Fetch the next packet from the stream, it should be an Upgrade packet
+    match read_frame(rx, max_payload).await? {
+        Some(Frame::Text(msg)) => match record_recv(socket, Packet::try_from(msg)?) {
+            Packet::Upgrade => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("webtransport upgraded successful")
+            }
+            p => Err(Error::BadPacket(p))?,
+        },
+        _ => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("unexpected webtransport frame before upgrade");
+            Err(Error::Upgrade)?
+        }
+    };
+
+    // wait for any polling connection to finish by waiting for the socket to be unlocked
+    let _ = socket.internal_rx.lock().await;
+    socket.upgrade_to_webtransport();
+    Ok(())
+}
+
+/// Read a frame from the stream. Returns `None` if the stream is closed.
+/// Records a packet received during the upgrade handshake and gives it back
+fn record_recv<D: Default + Send + Sync + 'static>(socket: &Socket<D>, packet: Packet) -> Packet {
+    socket.record_recv(TransportType::WebTransport, &packet);
+    packet
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(
+    rx: &mut R,
+    max_payload: u64,
+) -> Result<Option<Frame>, Error> {
+    let header = match rx.read_u8().await {
+        Ok(header) => header,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let len = match header & !BINARY_FLAG {
+        126 => rx.read_u16().await? as u64,
+        127 => rx.read_u64().await?,
+        len => len as u64,
+    };
+    if len > max_payload {
+        return Err(Error::PayloadTooLarge);
+    }
+
+    let mut data = vec![0; len as usize];
+    rx.read_exact(&mut data).await?;
+    if header & BINARY_FLAG == BINARY_FLAG {
+        Ok(Some(Frame::Binary(data.into())))
+    } else {
+        let data = String::from_utf8(data).map_err(|e| e.utf8_error())?;
+        Ok(Some(Frame::Text(data)))
+    }
+}
+
+/// Encode a packet with its header and write it to the stream.
+/// Binary packets are written as is, other packets are serialized as text.
+///
+/// Returns the length of the frame data
+async fn write_packet<W: AsyncWrite + Unpin>(tx: &mut W, packet: Packet) -> Result<usize, Error> {
+    let (data, binary): (Bytes, bool) = match packet {
+        Packet::Binary(data) | Packet::BinaryV3(data) => (data, true),
+        packet => {
+            let packet: String = packet.try_into()?;
+            (packet.into(), false)
+        }
+    };
+    let flag = if binary { BINARY_FLAG } else { 0 };
+    let len = data.len();
+    if len < 126 {
+        tx.write_u8(flag | len as u8).await?;
+    } else if len < 65536 {
+        tx.write_u8(flag | 126).await?;
+        tx.write_u16(len as u16).await?;
+    } else {
+        tx.write_u8(flag | 127).await?;
+        tx.write_u64(len as u64).await?;
+    }
+    tx.write_all(&data).await?;
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn roundtrip(packet: Packet) -> Frame {
+        let mut buf = Vec::new();
+        write_packet(&mut buf, packet).await.unwrap();
+        read_frame(&mut buf.as_slice(), u64::MAX)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn frame_header() {
+        let mut buf = Vec::new();
+        write_packet(&mut buf, Packet::Message("hello".into()))
+            .await
+            .unwrap();
+        assert_eq!(buf, b"\x064hello");
+
+        let mut buf = Vec::new();
+        write_packet(&mut buf, Packet::Binary(vec![1, 2, 3].into()))
+            .await
+            .unwrap();
+        assert_eq!(buf, [0x83, 1, 2, 3]);
+
+        let mut buf = Vec::new();
+        write_packet(&mut buf, Packet::Binary(vec![0; 300].into()))
+            .await
+            .unwrap();
+        assert_eq!(&buf[..3], [0x80 | 126, 0x01, 0x2c]);
+
+        let mut buf = Vec::new();
+        write_packet(&mut buf, Packet::Binary(vec![0; 70000].into()))
+            .await
+            .unwrap();
+        assert_eq!(&buf[..9], [0x80 | 127, 0, 0, 0, 0, 0, 1, 0x11, 0x70]);
+    }
+
+    #[tokio::test]
+    async fn frame_roundtrip() {
+        let frame = roundtrip(Packet::Message("hello".into())).await;
+        assert_eq!(frame, Frame::Text("4hello".into()));
+
+        let frame = roundtrip(Packet::PongUpgrade).await;
+        assert_eq!(frame, Frame::Text("3probe".into()));
+
+        let data = Bytes::from(vec![0xff; 70000]);
+        let frame = roundtrip(Packet::Binary(data.clone())).await;
+        assert_eq!(frame, Frame::Binary(data));
+    }
+
+    #[tokio::test]
+    async fn frame_too_large() {
+        let mut buf = Vec::new();
+        write_packet(&mut buf, Packet::Message("hello".into()))
+            .await
+            .unwrap();
+        let err = read_frame(&mut buf.as_slice(), 3).await.unwrap_err();
+        assert!(matches!(err, Error::PayloadTooLarge));
+    }
+
+    #[tokio::test]
+    async fn frame_eof() {
+        assert!(read_frame(&mut [].as_slice(), 100).await.unwrap().is_none());
+    }
+}
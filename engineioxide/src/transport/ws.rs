@@ -30,7 +30,7 @@ use crate::{
     service::ProtocolVersion,
     service::TransportType,
     sid::Sid,
-    DisconnectReason, Socket,
+    DisconnectReason, Socket, UpgradeError,
 };
 
 /// Create a response for websocket upgrade
@@ -48,6 +48,15 @@ fn ws_response<B>(ws_key: &HeaderValue) -> Result<Response<ResponseBody<B>>, htt
         .body(ResponseBody::empty_response())
 }
 
+/// The engine.io session served by a websocket connection
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum WsSession {
+    /// A new session, opened with the given id
+    New(Sid),
+    /// An existing polling session upgraded to websocket
+    Upgrade(Sid),
+}
+
 /// Upgrade a websocket request to create a websocket connection.
 ///
 /// If a sid is provided in the query it means that is is upgraded from an existing HTTP polling request.
@@ -67,12 +76,19 @@ pub fn new_req<R: Send + 'static, B, H: EngineIoHandler>(
         .ok_or(Error::HttpErrorResponse(StatusCode::BAD_REQUEST))?
         .clone();
 
+    // The id of a new session is generated before the upgrade to be set in the sticky-session cookie
+    let session = sid.map_or_else(|| WsSession::New(Sid::new()), WsSession::Upgrade);
+    let cookie = match (session, &engine.config.cookie) {
+        (WsSession::New(sid), Some(cookie)) => Some(cookie.to_header(sid)),
+        _ => None,
+    };
+
     tokio::spawn(async move {
         let conn = hyper::upgrade::on(req)
             .await
             .map(hyper_util::rt::TokioIo::new);
         let res = match conn {
-            Ok(conn) => on_init(engine, conn, protocol, sid, parts).await,
+            Ok(conn) => on_init(engine, conn, protocol, session, parts).await,
             Err(_e) => {
                 #[cfg(feature = "tracing")]
                 tracing::debug!("ws upgrade error: {}", _e);
@@ -92,7 +108,11 @@ pub fn new_req<R: Send + 'static, B, H: EngineIoHandler>(
         }
     });
 
-    Ok(ws_response(&ws_key)?)
+    let mut res = ws_response(&ws_key)?;
+    if let Some(cookie) = cookie {
+        res.headers_mut().append(http::header::SET_COOKIE, cookie);
+    }
+    Ok(res)
 }
 
 /// Handle a websocket connection upgrade
@@ -100,43 +120,55 @@ pub fn new_req<R: Send + 'static, B, H: EngineIoHandler>(
 /// Sends an open packet if it is not an upgrade from a polling request
 ///
 /// Read packets from the websocket and handle them, it will block until the connection is closed
-async fn on_init<H: EngineIoHandler, S>(
+pub(crate) async fn on_init<H: EngineIoHandler, S>(
     engine: Arc<EngineIo<H>>,
     conn: S,
     protocol: ProtocolVersion,
-    sid: Option<Sid>,
+    session: WsSession,
     req_data: Parts,
 ) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
     let ws_init = move || WebSocketStream::from_raw_socket(conn, Role::Server, None);
-    let (socket, ws) = if let Some(sid) = sid {
-        match engine.get_socket(sid) {
+    let (socket, ws) = match session {
+        WsSession::Upgrade(sid) => match engine.get_socket(sid) {
             None => return Err(Error::UnknownSessionID(sid)),
             Some(socket) if socket.is_ws() => return Err(Error::Upgrade),
             Some(socket) => {
                 let mut ws = ws_init().await;
-                upgrade_handshake::<H, S>(&socket, &mut ws).await?;
+                let upgrade = upgrade_handshake::<H, S>(&socket, &mut ws);
+                let res = tokio::time::timeout(engine.config.upgrade_timeout, upgrade)
+                    .await
+                    .unwrap_or(Err(Error::UpgradeTimeout));
+                let upgrade_res = res.as_ref().map(|_| ()).map_err(UpgradeError::from);
+                engine
+                    .handler
+                    .on_upgrade(socket.clone(), TransportType::Websocket, upgrade_res);
+                if let Err(e) = res {
+                    // The socket is kept on the polling transport
+                    ws.close(None).await.ok();
+                    return Err(e);
+                }
                 (socket, ws)
             }
+        },
+        WsSession::New(sid) => {
+            let socket = engine.create_session(
+                sid,
+                protocol,
+                TransportType::Websocket,
+                req_data,
+                #[cfg(feature = "v3")]
+                false,
+            );
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[sid={}] new websocket connection", socket.id);
+            let mut ws = ws_init().await;
+            init_handshake(socket.id, &mut ws, &engine.config).await?;
+            engine.heartbeat.register(&socket);
+            (socket, ws)
         }
-    } else {
-        let socket = engine.create_session(
-            protocol,
-            TransportType::Websocket,
-            req_data,
-            #[cfg(feature = "v3")]
-            false,
-        );
-        #[cfg(feature = "tracing")]
-        tracing::debug!("[sid={}] new websocket connection", socket.id);
-        let mut ws = ws_init().await;
-        init_handshake(socket.id, &mut ws, &engine.config).await?;
-        socket
-            .clone()
-            .spawn_heartbeat(engine.config.ping_interval, engine.config.ping_timeout);
-        (socket, ws)
     };
     let (tx, rx) = ws.split();
     let rx_handle = forward_to_socket::<H, S>(socket.clone(), tx);
@@ -164,43 +196,52 @@ where
     S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
     while let Some(msg) = rx.try_next().await? {
+        if msg.is_text() || msg.is_binary() {
+            socket.counters.received(1, msg.len());
+        }
         match msg {
-            Message::Text(msg) => match Packet::try_from(msg)? {
-                Packet::Close => {
-                    #[cfg(feature = "tracing")]
-                    tracing::debug!("[sid={}] closing session", socket.id);
-                    engine.close_session(socket.id, DisconnectReason::TransportClose);
-                    break;
-                }
-                Packet::Pong | Packet::Ping => socket
-                    .heartbeat_tx
-                    .try_send(())
-                    .map_err(|_| Error::HeartbeatTimeout),
-                Packet::Message(msg) => {
-                    engine.handler.on_message(msg, socket.clone());
-                    Ok(())
+            Message::Text(msg) => {
+                let packet = Packet::try_from(msg)?;
+                socket.record_recv(TransportType::Websocket, &packet);
+                match packet {
+                    Packet::Close => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("[sid={}] closing session", socket.id);
+                        engine.close_session(socket.id, DisconnectReason::TransportClose);
+                        break;
+                    }
+                    p @ (Packet::Pong | Packet::Ping) => {
+                        socket.recv_heartbeat(&p).map_err(Error::from)
+                    }
+                    Packet::Message(msg) => {
+                        engine.handler.on_message(msg, socket.clone());
+                        Ok(())
+                    }
+                    p => return Err(Error::BadPacket(p)),
                 }
-                p => return Err(Error::BadPacket(p)),
-            },
+            }
             Message::Binary(mut data) => {
                 if socket.protocol == ProtocolVersion::V3 && !data.is_empty() {
                     // The first byte is the message type, which we don't need.
                     let _ = data.remove(0);
                 }
+                socket.record_recv_binary(TransportType::Websocket, &data);
                 engine.handler.on_binary(data.into(), socket.clone());
                 Ok(())
-            },
+            }
             Message::Pong(bytes) => {
                 println!("Got a pong! {:?}", bytes);
                 Ok(())
-            },
+            }
             Message::Close(_) => break,
-            _ => { 
+            _ => {
                 println!("[sid={}] unexpected ws message", socket.id);
                 println!("{:?}", msg);
-                Ok(()) 
-            },
-        }?
+                Ok(())
+            }
+        }?;
+        // Wait before reading the next packet if the handler applies backpressure
+        socket.read_ready().await;
     }
     Ok(())
 }
@@ -230,7 +271,8 @@ where
                             // v3 protocol requires packet type as the first byte
                             bin.insert(0, 0x04);
                         }
-                        tx.feed(Message::Binary(bin)).await
+                        let len = bin.len();
+                        tx.feed(Message::Binary(bin)).await.map(|_| socket.counters.sent(1, len))
                     }
                     Packet::Close => {
                         tx.send(Message::Close(None)).await.ok();
@@ -243,7 +285,8 @@ where
                     Packet::Noop => Ok(()),
                     _ => {
                         let packet: String = $item.try_into().unwrap();
-                        tx.feed(Message::Text(packet)).await
+                        let len = packet.len();
+                        tx.feed(Message::Text(packet)).await.map(|_| socket.counters.sent(1, len))
                     }
                 };
                 if let Err(_e) = res {
@@ -321,9 +364,12 @@ where
         Some(Ok(Message::Text(d))) => d,
         _ => Err(Error::Upgrade)?,
     };
-    match Packet::try_from(msg)? {
+    let packet = Packet::try_from(msg)?;
+    socket.record_recv(TransportType::Websocket, &packet);
+    match packet {
         Packet::PingUpgrade => {
             // Respond with a PongUpgrade packet
+            socket.record_send(TransportType::Websocket, &Packet::PongUpgrade);
             ws.send(Message::Text(Packet::PongUpgrade.try_into()?))
                 .await?;
         }
@@ -347,7 +393,9 @@ where
             Err(Error::Upgrade)?
         }
     };
-    match Packet::try_from(msg)? {
+    let packet = Packet::try_from(msg)?;
+    socket.record_recv(TransportType::Websocket, &packet);
+    match packet {
         Packet::Upgrade => {
             #[cfg(feature = "tracing")]
             tracing::debug!("ws upgraded successful")
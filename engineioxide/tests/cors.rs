@@ -0,0 +1,140 @@
+//! Tests for the built-in CORS handling
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::{CorsConfig, EngineIoConfig},
+    handler::EngineIoHandler,
+    service::EngineIoService,
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use http::{
+    header::{
+        ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN,
+    },
+    HeaderName, Method, Request, Response, StatusCode,
+};
+use http_body_util::Empty;
+use hyper::body::Incoming;
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+
+mod fixture;
+
+use fixture::serve;
+
+#[derive(Debug, Clone)]
+struct MyHandler;
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        socket.emit(msg).ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+}
+
+async fn create_server(port: u16) {
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(300))
+        .ping_timeout(Duration::from_millis(200))
+        .cors(
+            CorsConfig::new()
+                .allow_origin("https://example.com")
+                .allow_credentials(true)
+                .allow_header(HeaderName::from_static("x-custom")),
+        )
+        .build();
+    serve(EngineIoService::with_config(MyHandler, config), port).await;
+}
+
+async fn send_req(port: u16, method: Method, origin: &str) -> Response<Incoming> {
+    let req = Request::builder()
+        .method(method)
+        .uri(format!(
+            "http://127.0.0.1:{port}/engine.io/?EIO=4&transport=polling"
+        ))
+        .header(ORIGIN, origin)
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    Client::builder(TokioExecutor::new())
+        .build_http()
+        .request(req)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn cors_preflight() {
+    create_server(3300).await;
+    let res = send_req(3300, Method::OPTIONS, "https://example.com").await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let headers = res.headers();
+    assert_eq!(
+        headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+        "https://example.com"
+    );
+    assert_eq!(
+        headers.get(ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(),
+        "true"
+    );
+    assert_eq!(
+        headers.get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+        "GET, POST, OPTIONS"
+    );
+    assert_eq!(
+        headers.get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+        "content-type, x-custom"
+    );
+}
+
+#[tokio::test]
+pub async fn cors_polling_handshake() {
+    create_server(3301).await;
+    let res = send_req(3301, Method::GET, "https://example.com").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+        "https://example.com"
+    );
+}
+
+#[tokio::test]
+pub async fn cors_origin_not_allowed() {
+    create_server(3302).await;
+    let res = send_req(3302, Method::OPTIONS, "https://evil.com").await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = send_req(3302, Method::GET, "https://evil.com").await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+    let req = Request::builder()
+        .uri("ws://127.0.0.1:3302/engine.io/?EIO=4&transport=websocket")
+        .header(ORIGIN, "https://evil.com")
+        .header("Host", "127.0.0.1:3302")
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .body(())
+        .unwrap();
+    let err = tokio_tungstenite::connect_async(req).await.unwrap_err();
+    match err {
+        tokio_tungstenite::tungstenite::Error::Http(res) => {
+            assert_eq!(res.status(), StatusCode::FORBIDDEN)
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
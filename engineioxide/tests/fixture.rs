@@ -1,3 +1,4 @@
+#![allow(dead_code)]
 use std::{
     collections::VecDeque,
     net::{IpAddr, Ipv4Addr, SocketAddr},
@@ -81,9 +82,13 @@ pub async fn create_server<H: EngineIoHandler>(handler: H, port: u16) {
         .max_payload(1e6 as u64)
         .build();
 
-    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
-
     let svc = EngineIoService::with_config(handler, config);
+    serve(svc, port).await;
+}
+
+/// Serve an already built [`EngineIoService`] on the given port
+pub async fn serve<H: EngineIoHandler>(svc: EngineIoService<H>, port: u16) {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
 
     let listener = TcpListener::bind(&addr).await.unwrap();
     tokio::spawn(async move {
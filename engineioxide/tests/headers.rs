@@ -0,0 +1,117 @@
+//! Tests for the custom response headers and the sticky-session cookie
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::{CookieConfig, EngineIoConfig},
+    handler::EngineIoHandler,
+    service::EngineIoService,
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use futures_util::StreamExt;
+use http::{header::SET_COOKIE, request::Parts, HeaderMap, HeaderValue, Method, Request};
+use http_body_util::{BodyExt, Empty};
+use hyper::body::Incoming;
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+
+mod fixture;
+
+use fixture::serve;
+
+#[derive(Debug, Clone)]
+struct MyHandler;
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        socket.emit(msg).ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+
+    fn on_initial_headers(&self, headers: &mut HeaderMap, _req: &Parts) {
+        headers.insert("x-initial", HeaderValue::from_static("true"));
+    }
+
+    fn on_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        headers.insert("x-method", req.method.as_str().parse().unwrap());
+    }
+}
+
+async fn create_server(port: u16) {
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(300))
+        .ping_timeout(Duration::from_millis(200))
+        .cookie(CookieConfig::new())
+        .build();
+    serve(EngineIoService::with_config(MyHandler, config), port).await;
+}
+
+async fn send_req(port: u16, method: Method, params: &str) -> http::Response<Incoming> {
+    let req = Request::builder()
+        .method(method)
+        .uri(format!(
+            "http://127.0.0.1:{port}/engine.io/?EIO=4&transport=polling{params}"
+        ))
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    Client::builder(TokioExecutor::new())
+        .build_http()
+        .request(req)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn handshake_headers() {
+    create_server(3400).await;
+    let mut res = send_req(3400, Method::GET, "").await;
+    let headers = res.headers().clone();
+    assert_eq!(headers.get("x-initial").unwrap(), "true");
+    assert_eq!(headers.get("x-method").unwrap(), "GET");
+
+    let body = res.body_mut().collect().await.unwrap().to_bytes();
+    let body = std::str::from_utf8(&body).unwrap();
+    let sid = body.split('"').nth(3).unwrap();
+    assert_eq!(
+        headers.get(SET_COOKIE).unwrap(),
+        &format!("io={sid}; Path=/; HttpOnly; SameSite=Lax")
+    );
+
+    let res = send_req(3400, Method::GET, &format!("&sid={sid}")).await;
+    let headers = res.headers();
+    assert!(headers.get("x-initial").is_none());
+    assert!(headers.get(SET_COOKIE).is_none());
+    assert_eq!(headers.get("x-method").unwrap(), "GET");
+}
+
+#[tokio::test]
+pub async fn websocket_handshake_headers() {
+    create_server(3401).await;
+    let (mut ws, res) = tokio_tungstenite::connect_async(
+        "ws://127.0.0.1:3401/engine.io/?EIO=4&transport=websocket",
+    )
+    .await
+    .unwrap();
+    let headers = res.headers();
+    assert_eq!(headers.get("x-initial").unwrap(), "true");
+    assert_eq!(headers.get("x-method").unwrap(), "GET");
+
+    let open = ws.next().await.unwrap().unwrap().into_text().unwrap();
+    let sid = open.split('"').nth(3).unwrap();
+    assert_eq!(
+        headers.get(SET_COOKIE).unwrap(),
+        &format!("io={sid}; Path=/; HttpOnly; SameSite=Lax")
+    );
+}
@@ -0,0 +1,239 @@
+//! Tests for the heartbeat strategies and the round trip time measurement
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    heartbeat::HeartbeatStrategy,
+    service::{EngineIoService, TransportType},
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+mod fixture;
+
+use fixture::{create_ws_connection, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    disconnect_tx: mpsc::Sender<DisconnectReason>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+        self.disconnect_tx.try_send(reason).unwrap();
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        if msg == "rtt" {
+            socket.emit(format!("{:?}", socket.rtt().is_some())).ok();
+        } else if msg == "pause" {
+            // Pause longer than the ping timeout
+            socket.pause_read();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                socket.resume_read();
+            });
+        }
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+}
+
+/// Pings websocket clients ten times more often than the advertised interval, v3 clients included
+#[derive(Debug)]
+struct FastWebsocket;
+impl HeartbeatStrategy for FastWebsocket {
+    fn next_ping(&self, interval: Duration, transport: TransportType) -> Duration {
+        match transport {
+            TransportType::Websocket => interval / 10,
+            _ => interval,
+        }
+    }
+    fn next_v3_ping(&self, interval: Duration, transport: TransportType) -> Option<Duration> {
+        Some(self.next_ping(interval, transport))
+    }
+}
+
+async fn create_server(port: u16) -> mpsc::Receiver<DisconnectReason> {
+    let (disconnect_tx, rx) = mpsc::channel(10);
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(1000))
+        .ping_timeout(Duration::from_millis(200))
+        .heartbeat(FastWebsocket)
+        .build();
+    serve(
+        EngineIoService::with_config(MyHandler { disconnect_tx }, config),
+        port,
+    )
+    .await;
+    rx
+}
+
+async fn next_text<S>(stream: &mut S) -> String
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    match stream.next().await {
+        Some(Ok(Message::Text(msg))) => msg.to_string(),
+        msg => panic!("unexpected message: {msg:?}"),
+    }
+}
+
+#[tokio::test]
+pub async fn heartbeat_strategy_and_rtt() {
+    let mut rx = create_server(3600).await;
+    let mut stream = create_ws_connection(3600).await;
+    // Open packet
+    next_text(&mut stream).await;
+
+    stream.send(Message::Text("4rtt".into())).await.unwrap();
+    assert_eq!(next_text(&mut stream).await, "4false");
+
+    for _ in 0..3 {
+        // The pings are sent every 100ms rather than every second
+        let ping = tokio::time::timeout(Duration::from_millis(300), next_text(&mut stream))
+            .await
+            .expect("ping should be sent with the strategy delay");
+        assert_eq!(ping, "2");
+        stream.send(Message::Text("3".into())).await.unwrap();
+    }
+
+    stream.send(Message::Text("4rtt".into())).await.unwrap();
+    assert_eq!(next_text(&mut stream).await, "4true");
+
+    // Without pong, the socket is closed after the ping timeout
+    assert_eq!(next_text(&mut stream).await, "2");
+    let reason = tokio::time::timeout(Duration::from_millis(500), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reason, DisconnectReason::HeartbeatTimeout);
+}
+
+#[cfg(feature = "v3")]
+#[tokio::test]
+pub async fn v3_server_ping() {
+    let mut rx = create_server(3603).await;
+    let (mut stream, _) = tokio_tungstenite::connect_async(
+        "ws://127.0.0.1:3603/engine.io/?EIO=3&transport=websocket",
+    )
+    .await
+    .unwrap();
+    // Open packet
+    next_text(&mut stream).await;
+
+    for _ in 0..3 {
+        // The server pings the v3 client with the strategy delay, the pongs of the client are not answered
+        let ping = tokio::time::timeout(Duration::from_millis(300), next_text(&mut stream))
+            .await
+            .expect("ping should be sent to the v3 client");
+        assert_eq!(ping, "2");
+        stream.send(Message::Text("3".into())).await.unwrap();
+    }
+    stream.send(Message::Text("4rtt".into())).await.unwrap();
+    loop {
+        match next_text(&mut stream).await.as_str() {
+            "2" => continue,
+            msg => break assert_eq!(msg, "4true"),
+        }
+    }
+
+    // The pings of the client are still answered
+    stream.send(Message::Text("2".into())).await.unwrap();
+    loop {
+        match next_text(&mut stream).await.as_str() {
+            "2" => continue,
+            msg => break assert_eq!(msg, "3"),
+        }
+    }
+
+    // The server pings do not keep alive a client that stops sending its own pings
+    let pings = tokio::spawn(async move {
+        let mut count = 0;
+        while let Some(Ok(Message::Text(msg))) = stream.next().await {
+            assert_eq!(msg, "2");
+            count += 1;
+            stream.send(Message::Text("3".into())).await.ok();
+        }
+        count
+    });
+    let reason = tokio::time::timeout(Duration::from_millis(1500), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reason, DisconnectReason::HeartbeatTimeout);
+    assert!(pings.await.unwrap() > 0);
+}
+
+#[tokio::test]
+pub async fn no_heartbeat_timeout_while_paused() {
+    let mut rx = create_server(3602).await;
+    let mut stream = create_ws_connection(3602).await;
+    // Open packet
+    next_text(&mut stream).await;
+
+    stream.send(Message::Text("4pause".into())).await.unwrap();
+    // The pongs are not read while reading is paused, the socket stays open
+    let answer_pings = async {
+        loop {
+            assert_eq!(next_text(&mut stream).await, "2");
+            stream.send(Message::Text("3".into())).await.unwrap();
+        }
+    };
+    tokio::time::timeout(Duration::from_millis(1000), answer_pings)
+        .await
+        .unwrap_err();
+    assert!(rx.try_recv().is_err());
+}
+
+/// Blocks the thread in the disconnect handler
+#[derive(Debug, Clone)]
+struct SlowHandler;
+
+impl EngineIoHandler for SlowHandler {
+    type Data = ();
+
+    fn on_connect(&self, _: Arc<Socket<()>>) {}
+    fn on_disconnect(&self, _: Arc<Socket<()>>, _: DisconnectReason) {
+        std::thread::sleep(Duration::from_millis(1000));
+    }
+    fn on_message(&self, _: Str, _: Arc<Socket<()>>) {}
+    fn on_binary(&self, _: Bytes, _: Arc<Socket<()>>) {}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+pub async fn slow_disconnect_handler() {
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(100))
+        .ping_timeout(Duration::from_millis(100))
+        .build();
+    serve(EngineIoService::with_config(SlowHandler, config), 3601).await;
+
+    // This socket never answers the pings, it is closed after the first timeout
+    let mut timed_out = create_ws_connection(3601).await;
+    next_text(&mut timed_out).await;
+    let mut stream = create_ws_connection(3601).await;
+    next_text(&mut stream).await;
+
+    // The heartbeat of the other sockets goes on while the disconnect handler is running
+    for _ in 0..10 {
+        let ping = tokio::time::timeout(Duration::from_millis(300), next_text(&mut stream))
+            .await
+            .expect("ping should not be delayed by the disconnect handler");
+        assert_eq!(ping, "2");
+        stream.send(Message::Text("3".into())).await.unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+//! Tests for the priority lane of control packets, under a flood of messages
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    service::EngineIoService,
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use futures_util::{SinkExt, StreamExt};
+use http::Method;
+use http_body_util::{BodyExt, Full};
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+mod fixture;
+
+use fixture::{create_polling_connection, create_ws_connection, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    disconnect_tx: mpsc::Sender<DisconnectReason>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+        self.disconnect_tx.try_send(reason).unwrap();
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        if msg == "flood" {
+            // Fill the buffer until it is full
+            while socket.emit("data").is_ok() {}
+        } else if msg == "close" {
+            for i in 0..7 {
+                socket.emit(format!("data{i}")).unwrap();
+            }
+            socket.close(DisconnectReason::ClosingServer);
+        } else if msg == "flood_close" {
+            while socket.emit("data").is_ok() {}
+            socket.close(DisconnectReason::ClosingServer);
+        }
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+}
+
+async fn create_server(port: u16) -> mpsc::Receiver<DisconnectReason> {
+    let (disconnect_tx, rx) = mpsc::channel(10);
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(100))
+        .ping_timeout(Duration::from_millis(200))
+        .max_buffer_size(8)
+        .build();
+    serve(
+        EngineIoService::with_config(MyHandler { disconnect_tx }, config),
+        port,
+    )
+    .await;
+    rx
+}
+
+async fn send_req(port: u16, sid: &str, method: Method, body: &'static str) -> String {
+    let req = http::Request::builder()
+        .method(method)
+        .uri(format!(
+            "http://127.0.0.1:{port}/engine.io/?EIO=4&transport=polling&sid={sid}"
+        ))
+        .body(Full::new(Bytes::from_static(body.as_bytes())))
+        .unwrap();
+    let res = Client::builder(TokioExecutor::new())
+        .build_http()
+        .request(req)
+        .await
+        .unwrap();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+pub async fn heartbeat_under_load() {
+    let mut rx = create_server(3500).await;
+    let sid = create_polling_connection(3500).await;
+
+    send_req(3500, &sid, Method::POST, "4flood").await;
+    for _ in 0..3 {
+        // The ping packet is sent even though the buffer is full, and before all the messages
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let payload = send_req(3500, &sid, Method::GET, "").await;
+        assert!(payload.starts_with("2\x1e4data"), "payload: {payload}");
+        send_req(3500, &sid, Method::POST, "3").await;
+        send_req(3500, &sid, Method::POST, "4flood").await;
+    }
+
+    assert!(rx.try_recv().is_err(), "socket should not be disconnected");
+}
+
+#[tokio::test]
+pub async fn close_after_messages() {
+    let mut rx = create_server(3501).await;
+    let mut ws = create_ws_connection(3501).await;
+    ws.next().await.unwrap().unwrap(); // Open packet
+
+    ws.send(Message::Text("4close".into())).await.unwrap();
+    // The close packet is sent after all the messages emitted before it
+    let mut msgs = Vec::new();
+    while let Some(Ok(msg)) = ws.next().await {
+        match msg {
+            Message::Text(msg) if msg == "2" => (),
+            Message::Text(msg) => msgs.push(msg),
+            Message::Close(_) => break,
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+    let expected: Vec<_> = (0..7).map(|i| format!("4data{i}")).collect();
+    assert_eq!(msgs, expected);
+    assert_eq!(rx.recv().await, Some(DisconnectReason::ClosingServer));
+}
+
+#[tokio::test]
+pub async fn close_on_full_buffer() {
+    let mut rx = create_server(3502).await;
+    let mut ws = create_ws_connection(3502).await;
+    ws.next().await.unwrap().unwrap(); // Open packet
+
+    ws.send(Message::Text("4flood_close".into())).await.unwrap();
+    // The close packet is not lost when the buffer is full and it is sent after all the messages
+    let mut msgs = Vec::new();
+    while let Some(Ok(msg)) = ws.next().await {
+        match msg {
+            Message::Text(msg) if msg == "2" => (),
+            Message::Text(msg) => msgs.push(msg),
+            Message::Close(_) => break,
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+    assert_eq!(msgs, vec!["4data"; 8]);
+    assert_eq!(rx.recv().await, Some(DisconnectReason::ClosingServer));
+}
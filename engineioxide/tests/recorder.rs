@@ -0,0 +1,271 @@
+//! Tests for the packet recorder and the session replay
+#![cfg(feature = "recorder")]
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    recorder::{Direction, Frame, RecordEvent, RecordReader, Recorder},
+    replay::{self, Session},
+    service::EngineIoService,
+    socket::{DisconnectReason, Socket},
+    ProtocolVersion, Str, TransportType,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+mod fixture;
+
+use fixture::{create_polling_connection, create_ws_connection, send_req, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    disconnect_tx: mpsc::UnboundedSender<DisconnectReason>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        socket.emit(format!("welcome {}", socket.id)).ok();
+    }
+    fn on_disconnect(&self, _socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        self.disconnect_tx.send(reason).ok();
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        socket.emit(msg).ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+}
+
+fn service(
+    recording: Option<&PathBuf>,
+) -> (
+    EngineIoService<MyHandler>,
+    mpsc::UnboundedReceiver<DisconnectReason>,
+) {
+    let (disconnect_tx, rx) = mpsc::unbounded_channel();
+    let mut config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(300))
+        .ping_timeout(Duration::from_millis(200));
+    if let Some(path) = recording {
+        config = config.recorder(Recorder::create(path).unwrap());
+    }
+    let svc = EngineIoService::with_config(MyHandler { disconnect_tx }, config.build());
+    (svc, rx)
+}
+
+/// Reads the recorded sessions once they are all closed,
+/// the records are written in the background by the recorder thread
+async fn read_sessions(path: &PathBuf) -> Vec<Session> {
+    for _ in 0..100 {
+        let sessions = RecordReader::open(path).and_then(replay::sessions);
+        if let Ok(sessions) = sessions {
+            let closed = sessions.iter().all(|session| {
+                matches!(
+                    session.records.last().map(|r| &r.event),
+                    Some(RecordEvent::Close { .. })
+                )
+            });
+            if !sessions.is_empty() && closed {
+                return sessions;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the sessions were not recorded");
+}
+
+/// Returns the direction and the frame of the packet records
+fn packets(session: &Session) -> Vec<(Direction, Frame)> {
+    session
+        .records
+        .iter()
+        .filter_map(|record| match &record.event {
+            RecordEvent::Packet {
+                direction, frame, ..
+            } => Some((*direction, frame.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+pub async fn record_and_replay_ws_session() {
+    let path = std::env::temp_dir().join("engineioxide-record-4000.eiorec");
+    let (svc, mut disconnect_rx) = service(Some(&path));
+    serve(svc, 4000).await;
+
+    let mut ws = create_ws_connection(4000).await;
+    ws.next().await.unwrap().unwrap(); // Open packet
+    let welcome = ws.next().await.unwrap().unwrap();
+    ws.send(Message::Text("4hello".into())).await.unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::Text("4hello".into())
+    );
+    ws.send(Message::Binary(vec![1, 2, 3])).await.unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::Binary(vec![1, 2, 3])
+    );
+    ws.send(Message::Text("1".into())).await.unwrap();
+    assert_eq!(
+        disconnect_rx.recv().await,
+        Some(DisconnectReason::TransportClose)
+    );
+
+    let sessions = read_sessions(&path).await;
+    assert_eq!(sessions.len(), 1);
+    let session = &sessions[0];
+    assert_eq!(session.protocol, ProtocolVersion::V4);
+    assert_eq!(
+        session.records.first().unwrap().event,
+        RecordEvent::Open {
+            protocol: ProtocolVersion::V4,
+            transport: TransportType::Websocket
+        }
+    );
+    assert_eq!(
+        session.records.last().unwrap().event,
+        RecordEvent::Close {
+            reason: DisconnectReason::TransportClose
+        }
+    );
+    let packets = packets(session);
+    assert!(
+        matches!(&packets[0], (Direction::Outgoing, Frame::Text(open)) if open.starts_with("0{"))
+    );
+    assert_eq!(
+        packets[1..],
+        [
+            (
+                Direction::Outgoing,
+                Frame::Text(welcome.into_text().unwrap())
+            ),
+            (Direction::Incoming, Frame::Text("4hello".into())),
+            (Direction::Outgoing, Frame::Text("4hello".into())),
+            (
+                Direction::Incoming,
+                Frame::Binary(Bytes::from_static(&[1, 2, 3]))
+            ),
+            (
+                Direction::Outgoing,
+                Frame::Binary(Bytes::from_static(&[1, 2, 3]))
+            ),
+            (Direction::Incoming, Frame::Text("1".into())),
+        ]
+    );
+
+    // The session is reproduced against a new server, with a new sid
+    let (svc, mut disconnect_rx) = service(None);
+    let report = svc
+        .replay_session(session, Duration::from_millis(500))
+        .await
+        .unwrap();
+    assert!(report.matches(), "{report:?}");
+    assert_eq!(report.received.len(), 3);
+    assert_eq!(
+        disconnect_rx.recv().await,
+        Some(DisconnectReason::TransportClose)
+    );
+}
+
+#[tokio::test]
+pub async fn replay_mismatch() {
+    let path = std::env::temp_dir().join("engineioxide-record-4001.eiorec");
+    let (svc, mut disconnect_rx) = service(Some(&path));
+    serve(svc, 4001).await;
+
+    let mut ws = create_ws_connection(4001).await;
+    ws.next().await.unwrap().unwrap(); // Open packet
+    ws.next().await.unwrap().unwrap(); // Welcome message
+    ws.send(Message::Text("4hello".into())).await.unwrap();
+    ws.next().await.unwrap().unwrap();
+    ws.send(Message::Text("1".into())).await.unwrap();
+    disconnect_rx.recv().await.unwrap();
+
+    // The recorded echo is altered so that the replayed server does not match it anymore
+    let mut session = read_sessions(&path).await.remove(0);
+    for record in &mut session.records {
+        if let RecordEvent::Packet {
+            direction: Direction::Outgoing,
+            frame: frame @ Frame::Text(_),
+            ..
+        } = &mut record.event
+        {
+            if *frame == Frame::Text("4hello".into()) {
+                *frame = Frame::Text("4bye".into());
+            }
+        }
+    }
+
+    let (svc, _) = service(None);
+    let report = svc
+        .replay_session(&session, Duration::from_millis(500))
+        .await
+        .unwrap();
+    assert!(!report.matches());
+    assert_eq!(report.expected[1], Frame::Text("4bye".into()));
+    assert_eq!(report.received[1], Frame::Text("4hello".into()));
+}
+
+#[tokio::test]
+pub async fn record_polling_session() {
+    let path = std::env::temp_dir().join("engineioxide-record-4002.eiorec");
+    let (svc, _) = service(Some(&path));
+    serve(svc, 4002).await;
+
+    let sid = create_polling_connection(4002).await;
+    send_req(
+        4002,
+        format!("transport=polling&sid={sid}"),
+        http::Method::POST,
+        Some("4hello".into()),
+    )
+    .await;
+    let body = send_req(
+        4002,
+        format!("transport=polling&sid={sid}"),
+        http::Method::GET,
+        None,
+    )
+    .await;
+    assert!(body.ends_with("4hello"), "{body}");
+    send_req(
+        4002,
+        format!("transport=polling&sid={sid}"),
+        http::Method::POST,
+        Some("1".into()),
+    )
+    .await;
+
+    let sessions = read_sessions(&path).await;
+    let session = &sessions[0];
+    assert_eq!(session.sid.to_string(), sid);
+    let incoming: Vec<_> = session
+        .records
+        .iter()
+        .filter_map(|record| match &record.event {
+            RecordEvent::Packet {
+                direction: Direction::Incoming,
+                transport,
+                frame,
+            } => Some((*transport, frame.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        incoming,
+        [
+            (TransportType::Polling, Frame::Text("4hello".into())),
+            (TransportType::Polling, Frame::Text("1".into())),
+        ]
+    );
+}
@@ -0,0 +1,147 @@
+//! Tests for the socket statistics
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    service::EngineIoService,
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use futures_util::{SinkExt, StreamExt};
+use http_body::Frame;
+use http_body_util::StreamBody;
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+mod fixture;
+
+use fixture::{create_polling_connection, send_req, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    connect_tx: mpsc::Sender<Arc<Socket<()>>>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+        self.connect_tx.try_send(socket).unwrap();
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        socket.emit(msg).ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        socket.emit_binary(data).ok();
+    }
+}
+
+async fn create_server(port: u16) -> mpsc::Receiver<Arc<Socket<()>>> {
+    let (connect_tx, rx) = mpsc::channel(10);
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(1000))
+        .ping_timeout(Duration::from_millis(1000))
+        .build();
+    serve(
+        EngineIoService::with_config(MyHandler { connect_tx }, config),
+        port,
+    )
+    .await;
+    rx
+}
+
+#[tokio::test]
+pub async fn polling_and_upgrade_stats() {
+    let mut rx = create_server(3700).await;
+    let sid = create_polling_connection(3700).await;
+    let socket = rx.recv().await.unwrap();
+
+    let stats = socket.stats();
+    assert_eq!(stats.packets_received, 0);
+    assert_eq!(stats.transport_switches, 0);
+    assert_eq!(stats.upgraded_at, None);
+    assert_eq!(stats.rtt, None);
+
+    let params = format!("transport=polling&sid={sid}");
+    send_req(
+        3700,
+        params.clone(),
+        http::Method::POST,
+        Some("4hello".into()),
+    )
+    .await;
+    let stats = socket.stats();
+    assert_eq!(stats.packets_received, 1);
+    assert_eq!(stats.bytes_received, 6);
+    // The echo is waiting for the next polling request
+    assert_eq!(stats.buffered_packets, 1);
+    assert_eq!(stats.packets_sent, 0);
+
+    send_req(3700, params, http::Method::GET, None).await;
+    let stats = socket.stats();
+    assert_eq!(stats.buffered_packets, 0);
+    assert_eq!(stats.packets_sent, 1);
+    assert_eq!(stats.bytes_sent, 6);
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://127.0.0.1:3700/engine.io/?EIO=4&transport=websocket&sid={sid}"
+    ))
+    .await
+    .unwrap();
+    ws.send(Message::Text("2probe".into())).await.unwrap();
+    ws.next().await.unwrap().unwrap();
+    ws.send(Message::Text("5".into())).await.unwrap();
+    ws.send(Message::Text("4hi".into())).await.unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::Text("4hi".into())
+    );
+
+    let stats = socket.stats();
+    assert_eq!(stats.transport_switches, 1);
+    assert!(stats.upgraded_at.is_some());
+    assert_eq!(stats.packets_received, 2);
+    assert_eq!(stats.bytes_received, 9);
+    assert_eq!(stats.packets_sent, 2);
+    assert_eq!(stats.bytes_sent, 9);
+}
+
+#[tokio::test]
+pub async fn chunked_body_and_heartbeat_stats() {
+    let mut rx = create_server(3701).await;
+    let sid = create_polling_connection(3701).await;
+    let socket = rx.recv().await.unwrap();
+
+    // A streamed body has no length, it is sent with the chunked encoding
+    let chunks = ["4hel", "lo\x1e4wor", "ld"]
+        .map(|chunk| Ok::<_, Infallible>(Frame::data(Bytes::from(chunk))));
+    let req = http::Request::post(format!(
+        "http://127.0.0.1:3701/engine.io/?EIO=4&transport=polling&sid={sid}"
+    ))
+    .body(StreamBody::new(futures_util::stream::iter(chunks)))
+    .unwrap();
+    let res = Client::builder(TokioExecutor::new())
+        .build_http()
+        .request(req)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let stats = socket.stats();
+    assert_eq!(stats.packets_received, 2);
+    assert_eq!(stats.bytes_received, 13);
+    assert_eq!(stats.buffered_packets, 2);
+
+    // The ping waiting for the next polling request is counted too
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(socket.stats().buffered_packets, 3);
+}
@@ -0,0 +1,164 @@
+//! Tests for the transport upgrade process and its timeout
+use std::{sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    service::{EngineIoService, TransportType},
+    socket::{DisconnectReason, Socket, UpgradeError},
+    Str,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+mod fixture;
+
+use fixture::{create_polling_connection, send_req, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    upgrade_tx: mpsc::Sender<(TransportType, Result<(), UpgradeError>)>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        println!("Ping pong message {:?}", msg);
+        socket
+            .emit(format!("{}:{}", msg, socket.transport_type() as u8))
+            .ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        println!("Ping pong binary message {:?}", data);
+        socket.emit_binary(data).ok();
+    }
+
+    fn on_upgrade(
+        &self,
+        socket: Arc<Socket<()>>,
+        transport: TransportType,
+        res: Result<(), UpgradeError>,
+    ) {
+        println!("socket upgrade {} to {:?}: {:?}", socket.id, transport, res);
+        self.upgrade_tx.try_send((transport, res)).unwrap();
+    }
+}
+
+async fn create_server(port: u16) -> mpsc::Receiver<(TransportType, Result<(), UpgradeError>)> {
+    let (upgrade_tx, rx) = mpsc::channel(10);
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(1000))
+        .ping_timeout(Duration::from_millis(1000))
+        .upgrade_timeout(Duration::from_millis(100))
+        .build();
+    serve(
+        EngineIoService::with_config(MyHandler { upgrade_tx }, config),
+        port,
+    )
+    .await;
+    rx
+}
+
+async fn upgrade_ws_connection(port: u16, sid: &str) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://127.0.0.1:{port}/engine.io/?EIO=4&transport=websocket&sid={sid}"
+    ))
+    .await
+    .unwrap();
+    ws.send(Message::Text("2probe".into())).await.unwrap();
+    let msg = ws.next().await.unwrap().unwrap();
+    assert_eq!(msg, Message::Text("3probe".into()));
+    ws
+}
+
+async fn recv_upgrade(
+    rx: &mut mpsc::Receiver<(TransportType, Result<(), UpgradeError>)>,
+) -> (TransportType, Result<(), UpgradeError>) {
+    tokio::time::timeout(Duration::from_millis(500), rx.recv())
+        .await
+        .expect("timeout waiting for the upgrade result")
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn ws_upgrade_success() {
+    let mut rx = create_server(3200).await;
+    let sid = create_polling_connection(3200).await;
+    let mut ws = upgrade_ws_connection(3200, &sid).await;
+    ws.send(Message::Text("5".into())).await.unwrap();
+
+    assert_eq!(
+        recv_upgrade(&mut rx).await,
+        (TransportType::Websocket, Ok(()))
+    );
+
+    ws.send(Message::Text("4hello".into())).await.unwrap();
+    let msg = ws.next().await.unwrap().unwrap();
+    assert_eq!(msg, Message::Text("4hello:2".into()));
+}
+
+#[tokio::test]
+pub async fn ws_upgrade_timeout() {
+    let mut rx = create_server(3201).await;
+    let sid = create_polling_connection(3201).await;
+    let mut ws = upgrade_ws_connection(3201, &sid).await;
+
+    assert_eq!(
+        recv_upgrade(&mut rx).await,
+        (TransportType::Websocket, Err(UpgradeError::Timeout))
+    );
+    // The websocket connection is closed by the server
+    assert!(matches!(
+        ws.next().await,
+        Some(Ok(Message::Close(_))) | None | Some(Err(_))
+    ));
+
+    // The client can still use the polling transport
+    let noop = send_req(
+        3201,
+        format!("transport=polling&sid={sid}"),
+        http::Method::GET,
+        None,
+    )
+    .await;
+    assert_eq!(noop, "");
+    send_req(
+        3201,
+        format!("transport=polling&sid={sid}"),
+        http::Method::POST,
+        Some("4hello".into()),
+    )
+    .await;
+    let msg = send_req(
+        3201,
+        format!("transport=polling&sid={sid}"),
+        http::Method::GET,
+        None,
+    )
+    .await;
+    assert_eq!(msg, "hello:1");
+}
+
+#[tokio::test]
+pub async fn ws_upgrade_bad_handshake() {
+    let mut rx = create_server(3202).await;
+    let sid = create_polling_connection(3202).await;
+    let mut ws = upgrade_ws_connection(3202, &sid).await;
+    ws.send(Message::Text("4hello".into())).await.unwrap();
+
+    assert_eq!(
+        recv_upgrade(&mut rx).await,
+        (TransportType::Websocket, Err(UpgradeError::BadHandshake))
+    );
+}
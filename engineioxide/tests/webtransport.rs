@@ -0,0 +1,315 @@
+//! Tests for the webtransport transport
+//! The HTTP/3 sessions are opened with a local quinn client,
+//! the other tests simulate the bidirectional stream with a duplex pipe.
+#![cfg(feature = "webtransport")]
+
+use std::{future::poll_fn, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use engineioxide::{
+    config::EngineIoConfig,
+    handler::EngineIoHandler,
+    service::{EngineIoService, TransportType},
+    socket::{DisconnectReason, Socket},
+    Str,
+};
+use http::{Request, StatusCode};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, Join},
+    sync::mpsc,
+};
+
+mod fixture;
+
+use fixture::{create_polling_connection, serve};
+
+#[derive(Debug, Clone)]
+struct MyHandler {
+    disconnect_tx: mpsc::Sender<DisconnectReason>,
+}
+
+impl EngineIoHandler for MyHandler {
+    type Data = ();
+
+    fn on_connect(&self, socket: Arc<Socket<()>>) {
+        println!("socket connect {}", socket.id);
+    }
+    fn on_disconnect(&self, socket: Arc<Socket<()>>, reason: DisconnectReason) {
+        println!("socket disconnect {}: {:?}", socket.id, reason);
+        self.disconnect_tx.try_send(reason).unwrap();
+    }
+
+    fn on_message(&self, msg: Str, socket: Arc<Socket<()>>) {
+        println!("Ping pong message {:?}", msg);
+        socket
+            .emit(format!("{}:{}", msg, socket.transport_type() as u8))
+            .ok();
+    }
+
+    fn on_binary(&self, data: Bytes, socket: Arc<Socket<()>>) {
+        println!("Ping pong binary message {:?}", data);
+        socket.emit_binary(data).ok();
+    }
+}
+
+fn create_svc(disconnect_tx: mpsc::Sender<DisconnectReason>) -> EngineIoService<MyHandler> {
+    let config = EngineIoConfig::builder()
+        .ping_interval(Duration::from_millis(300))
+        .ping_timeout(Duration::from_millis(200))
+        .transports([
+            TransportType::Polling,
+            TransportType::Websocket,
+            TransportType::WebTransport,
+        ])
+        .build();
+    EngineIoService::with_config(MyHandler { disconnect_tx }, config)
+}
+
+/// Open a simulated webtransport stream on the service
+fn connect(svc: &EngineIoService<MyHandler>) -> DuplexStream {
+    let (client, server) = tokio::io::duplex(1024);
+    let req = Request::connect("https://127.0.0.1/engine.io/?EIO=4&transport=webtransport")
+        .body(())
+        .unwrap()
+        .into_parts()
+        .0;
+    tokio::spawn(svc.webtransport_session(req, server));
+    client
+}
+
+/// Starts a quinn endpoint serving the HTTP/3 connections with a self-signed certificate.
+/// Returns the certificate to trust.
+fn serve_h3(
+    svc: EngineIoService<MyHandler>,
+    port: u16,
+) -> rustls::pki_types::CertificateDer<'static> {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let cert_der = cert.cert.der().clone();
+    let key = rustls::pki_types::PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+    let mut tls = rustls::ServerConfig::builder_with_provider(
+        rustls::crypto::ring::default_provider().into(),
+    )
+    .with_protocol_versions(&[&rustls::version::TLS13])
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(vec![cert_der.clone()], key.into())
+    .unwrap();
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let tls = quinn::crypto::rustls::QuicServerConfig::try_from(tls).unwrap();
+    let config = quinn::ServerConfig::with_crypto(Arc::new(tls));
+    let endpoint = quinn::Endpoint::server(config, ([127, 0, 0, 1], port).into()).unwrap();
+
+    tokio::spawn(async move {
+        while let Some(incoming) = endpoint.accept().await {
+            let svc = svc.clone();
+            tokio::spawn(async move {
+                svc.serve_webtransport(incoming.await.unwrap()).await;
+            });
+        }
+    });
+    cert_der
+}
+
+/// Opens a WebTransport session with a quinn client and returns the status of the `CONNECT` response
+/// and the first bidirectional stream of the session
+async fn connect_h3(
+    port: u16,
+    cert: rustls::pki_types::CertificateDer<'static>,
+    path: &str,
+) -> (StatusCode, Join<quinn::RecvStream, quinn::SendStream>) {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert).unwrap();
+    let mut tls = rustls::ClientConfig::builder_with_provider(
+        rustls::crypto::ring::default_provider().into(),
+    )
+    .with_protocol_versions(&[&rustls::version::TLS13])
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let tls = quinn::crypto::rustls::QuicClientConfig::try_from(tls).unwrap();
+    let mut endpoint = quinn::Endpoint::client(([127, 0, 0, 1], 0).into()).unwrap();
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(tls)));
+    let conn = endpoint
+        .connect(([127, 0, 0, 1], port).into(), "localhost")
+        .unwrap()
+        .await
+        .unwrap();
+
+    let (mut driver, mut send_request) = h3::client::builder()
+        .enable_extended_connect(true)
+        .build::<_, _, Bytes>(h3_quinn::Connection::new(conn.clone()))
+        .await
+        .unwrap();
+    tokio::spawn(async move { poll_fn(|cx| driver.poll_close(cx)).await });
+
+    let req = Request::connect(format!("https://localhost:{port}{path}"))
+        .extension(h3::ext::Protocol::WEB_TRANSPORT)
+        .body(())
+        .unwrap();
+    let mut session = send_request.send_request(req).await.unwrap();
+    let status = session.recv_response().await.unwrap().status();
+    // The CONNECT stream is kept open for the whole session
+    tokio::spawn(async move {
+        let _session = session;
+        let _send_request = send_request;
+        std::future::pending::<()>().await
+    });
+
+    // A WebTransport stream starts with its type (0x41) and the id of its session (the CONNECT stream id: 0)
+    let (mut tx, rx) = conn.open_bi().await.unwrap();
+    tx.write_all(&[0x40, 0x41, 0x00]).await.unwrap();
+    (status, tokio::io::join(rx, tx))
+}
+
+async fn send<S: AsyncWrite + Unpin>(stream: &mut S, data: &[u8], binary: bool) {
+    assert!(data.len() < 126);
+    let flag = if binary { 0x80 } else { 0 };
+    stream.write_u8(flag | data.len() as u8).await.unwrap();
+    stream.write_all(data).await.unwrap();
+}
+
+async fn recv<S: AsyncRead + Unpin>(stream: &mut S) -> (Vec<u8>, bool) {
+    let header = tokio::time::timeout(Duration::from_millis(200), stream.read_u8())
+        .await
+        .expect("timeout waiting for a frame")
+        .unwrap();
+    let len = match header & 0x7f {
+        126 => stream.read_u16().await.unwrap() as usize,
+        127 => stream.read_u64().await.unwrap() as usize,
+        len => len as usize,
+    };
+    let mut data = vec![0; len];
+    stream.read_exact(&mut data).await.unwrap();
+    (data, header & 0x80 != 0)
+}
+
+#[tokio::test]
+pub async fn webtransport_session() {
+    let (disconnect_tx, mut rx) = mpsc::channel(10);
+    let svc = create_svc(disconnect_tx);
+    let mut stream = connect(&svc);
+
+    send(&mut stream, b"0", false).await;
+    let (open, binary) = recv(&mut stream).await;
+    assert!(!binary);
+    assert_eq!(open[0], b'0');
+    assert!(std::str::from_utf8(&open)
+        .unwrap()
+        .contains("\"upgrades\":[]"));
+
+    send(&mut stream, b"4hello", false).await;
+    assert_eq!(recv(&mut stream).await, (b"4hello:4".to_vec(), false));
+
+    send(&mut stream, &[1, 2, 3], true).await;
+    assert_eq!(recv(&mut stream).await, (vec![1, 2, 3], true));
+
+    send(&mut stream, b"1", false).await;
+    let reason = tokio::time::timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for DisconnectReason::TransportClose")
+        .unwrap();
+    assert_eq!(reason, DisconnectReason::TransportClose);
+}
+
+#[tokio::test]
+pub async fn h3_webtransport_session() {
+    let (disconnect_tx, mut rx) = mpsc::channel(10);
+    let cert = serve_h3(create_svc(disconnect_tx), 3101);
+    let (status, mut stream) =
+        connect_h3(3101, cert, "/engine.io/?EIO=4&transport=webtransport").await;
+    assert_eq!(status, StatusCode::OK);
+
+    send(&mut stream, b"0", false).await;
+    let (open, binary) = recv(&mut stream).await;
+    assert!(!binary);
+    assert_eq!(open[0], b'0');
+
+    send(&mut stream, b"4hello", false).await;
+    assert_eq!(recv(&mut stream).await, (b"4hello:4".to_vec(), false));
+
+    send(&mut stream, &[1, 2, 3], true).await;
+    assert_eq!(recv(&mut stream).await, (vec![1, 2, 3], true));
+
+    send(&mut stream, b"1", false).await;
+    let reason = tokio::time::timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for DisconnectReason::TransportClose")
+        .unwrap();
+    assert_eq!(reason, DisconnectReason::TransportClose);
+}
+
+#[tokio::test]
+pub async fn h3_invalid_request() {
+    let (disconnect_tx, _rx) = mpsc::channel(10);
+    let cert = serve_h3(create_svc(disconnect_tx), 3102);
+    let (status, _) = connect_h3(
+        3102,
+        cert.clone(),
+        "/socket.io/?EIO=4&transport=webtransport",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = connect_h3(3102, cert, "/engine.io/?EIO=4&transport=websocket").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+pub async fn webtransport_heartbeat() {
+    let (disconnect_tx, mut rx) = mpsc::channel(10);
+    let svc = create_svc(disconnect_tx);
+    let mut stream = connect(&svc);
+
+    send(&mut stream, b"0", false).await;
+    recv(&mut stream).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(recv(&mut stream).await, (b"2".to_vec(), false));
+
+    let reason = tokio::time::timeout(Duration::from_millis(500), rx.recv())
+        .await
+        .expect("timeout waiting for DisconnectReason::HeartbeatTimeout")
+        .unwrap();
+    assert_eq!(reason, DisconnectReason::HeartbeatTimeout);
+}
+
+#[tokio::test]
+pub async fn polling_to_webtransport_upgrade() {
+    let (disconnect_tx, _rx) = mpsc::channel(10);
+    let svc = create_svc(disconnect_tx);
+    serve(svc.clone(), 3100).await;
+
+    let sid = create_polling_connection(3100).await;
+    let mut stream = connect(&svc);
+
+    send(
+        &mut stream,
+        format!("0{{\"sid\":\"{sid}\"}}").as_bytes(),
+        false,
+    )
+    .await;
+    send(&mut stream, b"2probe", false).await;
+    assert_eq!(recv(&mut stream).await, (b"3probe".to_vec(), false));
+    send(&mut stream, b"5", false).await;
+
+    send(&mut stream, b"4hello", false).await;
+    assert_eq!(recv(&mut stream).await, (b"4hello:4".to_vec(), false));
+}
+
+#[tokio::test]
+pub async fn webtransport_not_allowed() {
+    let (disconnect_tx, _rx) = mpsc::channel(10);
+    let svc = EngineIoService::with_config(MyHandler { disconnect_tx }, EngineIoConfig::default());
+    let (mut client, server) = tokio::io::duplex(1024);
+    let req = Request::connect("https://127.0.0.1/engine.io/?EIO=4&transport=webtransport")
+        .body(())
+        .unwrap()
+        .into_parts()
+        .0;
+    svc.webtransport_session(req, server).await;
+
+    // The server half is dropped without any data being written
+    assert_eq!(
+        client.read_u8().await.unwrap_err().kind(),
+        std::io::ErrorKind::UnexpectedEof
+    );
+}
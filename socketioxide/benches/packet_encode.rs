@@ -1,8 +1,9 @@
 use bytes::Bytes;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use engineioxide::sid::Sid;
+use serde::Serialize;
 use socketioxide::{
-    packet::{Packet, PacketData},
+    packet::{EncodedPacket, Packet, PacketData},
     ProtocolVersion,
 };
 fn criterion_benchmark(c: &mut Criterion) {
@@ -150,5 +151,64 @@ fn criterion_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// A large payload broadcasted to a room
+#[derive(Serialize, Clone)]
+struct Message {
+    id: u64,
+    user: String,
+    content: String,
+    tags: Vec<String>,
+}
+
+/// Compare the encoding of a broadcasted event:
+/// * serialized to a `Value` then re-encoded for each socket
+/// * serialized once to an [`EncodedPacket`] shared between all the sockets
+fn broadcast_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("socketio_packet/broadcast");
+    let messages: Vec<_> = (0..100)
+        .map(|id| Message {
+            id,
+            user: format!("user-{id}"),
+            content: "Lorem ipsum dolor sit amet, consectetur adipiscing elit".repeat(4),
+            tags: vec!["rust".into(), "socket.io".into()],
+        })
+        .collect();
+
+    for room_size in [1, 100, 1000] {
+        group.bench_with_input(
+            BenchmarkId::new("Value re-encoded per socket", room_size),
+            &room_size,
+            |b, &room_size| {
+                b.iter(|| {
+                    let data = serde_json::to_value(black_box(&messages)).unwrap();
+                    let packet = Packet::event("/custom_nsp", "messages", data);
+                    for _ in 0..room_size {
+                        let _: String = black_box(packet.clone().into());
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("EncodedPacket shared", room_size),
+            &room_size,
+            |b, &room_size| {
+                b.iter(|| {
+                    let packet = EncodedPacket::event(
+                        "/custom_nsp",
+                        "messages",
+                        black_box(&messages),
+                        vec![],
+                    )
+                    .unwrap();
+                    for _ in 0..room_size {
+                        black_box(packet.clone().into_parts());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark, broadcast_benchmark);
 criterion_main!(benches);
@@ -19,7 +19,9 @@ use serde::de::DeserializeOwned;
 use serde_json::Value;
 use tokio::{sync::oneshot::Receiver, time::Timeout};
 
-use crate::{adapter::Adapter, errors::AckError, extract::SocketRef, packet::Packet, SocketError};
+use crate::{
+    adapter::Adapter, errors::AckError, extract::SocketRef, packet::EncodedPacket, SocketError,
+};
 
 /// An acknowledgement sent by the client.
 /// It contains the data sent by the client and the binary payloads if there are any.
@@ -146,7 +148,7 @@ impl AckInnerStream {
     /// The [`AckInnerStream`] will wait for the default timeout specified in the config
     /// (5s by default) if no custom timeout is specified.
     pub fn broadcast<A: Adapter>(
-        packet: Packet<'static>,
+        packet: EncodedPacket,
         sockets: Vec<SocketRef<A>>,
         duration: Option<Duration>,
     ) -> Self {
@@ -158,7 +160,7 @@ impl AckInnerStream {
 
         let duration = duration.unwrap_or_else(|| sockets.first().unwrap().config.ack_timeout);
         for socket in sockets {
-            let rx = socket.send_with_ack(packet.clone());
+            let rx = socket.send_with_ack(&packet);
             rxs.push(AckResultWithId {
                 result: tokio::time::timeout(duration, rx),
                 id: socket.id,
@@ -311,7 +313,7 @@ mod test {
     use engineioxide::sid::Sid;
     use futures_util::StreamExt;
 
-    use crate::{adapter::LocalAdapter, ns::Namespace, socket::Socket};
+    use crate::{adapter::LocalAdapter, ns::Namespace, packet::Packet, socket::Socket};
 
     use super::*;
 
@@ -326,8 +328,7 @@ mod test {
     async fn broadcast_ack() {
         let socket = create_socket();
         let socket2 = create_socket();
-        let mut packet = Packet::event("/", "test", "test".into());
-        packet.inner.set_ack_id(1);
+        let packet = Packet::event("/", "test", "test".into()).into();
         let socks = vec![socket.clone().into(), socket2.clone().into()];
         let stream: AckStream<String> = AckInnerStream::broadcast(packet, socks, None).into();
 
@@ -382,8 +383,7 @@ mod test {
     async fn broadcast_ack_with_deserialize_error() {
         let socket = create_socket();
         let socket2 = create_socket();
-        let mut packet = Packet::event("/", "test", "test".into());
-        packet.inner.set_ack_id(1);
+        let packet = Packet::event("/", "test", "test".into()).into();
         let socks = vec![socket.clone().into(), socket2.clone().into()];
         let stream: AckStream<String> = AckInnerStream::broadcast(packet, socks, None).into();
 
@@ -446,8 +446,7 @@ mod test {
     async fn broadcast_ack_with_closed_socket() {
         let socket = create_socket();
         let socket2 = create_socket();
-        let mut packet = Packet::event("/", "test", "test".into());
-        packet.inner.set_ack_id(1);
+        let packet = Packet::event("/", "test", "test".into()).into();
         let socks = vec![socket.clone().into(), socket2.clone().into()];
         let stream: AckStream<String> = AckInnerStream::broadcast(packet, socks, None).into();
 
@@ -501,8 +500,7 @@ mod test {
     async fn broadcast_ack_with_timeout() {
         let socket = create_socket();
         let socket2 = create_socket();
-        let mut packet = Packet::event("/", "test", "test".into());
-        packet.inner.set_ack_id(1);
+        let packet = Packet::event("/", "test", "test".into()).into();
         let socks = vec![socket.clone().into(), socket2.clone().into()];
         let stream: AckStream<String> =
             AckInnerStream::broadcast(packet, socks, Some(Duration::from_millis(10))).into();
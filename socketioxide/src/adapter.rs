@@ -4,6 +4,7 @@
 //! Other adapters can be made to share the state between multiple servers.
 
 use std::{
+    any::{Any, TypeId},
     borrow::Cow,
     collections::{HashMap, HashSet},
     convert::Infallible,
@@ -12,6 +13,10 @@ use std::{
 };
 
 use engineioxide::sid::Sid;
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    Deserialize, Serialize,
+};
 
 use crate::{
     ack::AckInnerStream,
@@ -19,15 +24,208 @@ use crate::{
     extract::SocketRef,
     ns::Namespace,
     operators::RoomParam,
-    packet::Packet,
+    packet::EncodedPacket,
     DisconnectError,
 };
 
-/// A room identifier
-pub type Room = Cow<'static, str>;
+/// A room identifier.
+///
+/// A room can be identified by a string, an integer or a tagged integer such as `("user", 42)`.
+/// Integer rooms are hashed and compared without any string allocation,
+/// so that they can be used for ids coming from a database without formatting them.
+///
+/// Note that a tagged room is not equal to the string room with the same [`Display`](std::fmt::Display)
+/// representation: `Room::Tagged("user".into(), 42)` is different from `Room::Str("user:42".into())`.
+/// They also stay distinct once serialized for a remote adapter:
+/// * a [`Room::Str`] is serialized as a string, like the rooms of the previous versions,
+/// * a [`Room::Int`] is serialized as an unsigned integer,
+/// * a [`Room::Tagged`] is serialized as a `(tag, id)` tuple (e.g. `["user",42]` in JSON).
+///
+/// **Breaking change**: rooms used to be a `Cow<'static, str>`.
+/// Rooms are still given as strings to the operators (e.g. `socket.join("room")` or `io.to("room")`),
+/// but the rooms returned by the adapter (e.g. [`SocketIo::rooms`](crate::SocketIo::rooms)) are now [`Room`]s.
+/// They can be converted back to a string with [`Room::as_str`], [`Display`](std::fmt::Display)
+/// or the `From<Room>` impls of [`String`] and [`Cow<'static, str>`](Cow).
+///
+/// ```
+/// # use socketioxide::{SocketIo, adapter::Room, extract::*};
+/// let (_, io) = SocketIo::new_svc();
+/// io.ns("/", |socket: SocketRef| {
+///     let user_id: u64 = 42;
+///     // Join the rooms "general", 42 and "user:42"
+///     socket.join(["general"]).ok();
+///     socket.join(user_id).ok();
+///     socket.join(("user", user_id)).ok();
+///
+///     assert_eq!(Room::from(("user", user_id)).to_string(), "user:42");
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Room {
+    /// A room identified by a string
+    Str(Cow<'static, str>),
+    /// A room identified by an integer
+    Int(u64),
+    /// A room identified by a tag and an integer, displayed as `tag:id`
+    Tagged(Cow<'static, str>, u64),
+}
+
+impl Room {
+    /// Returns the room name if it is a [`Room::Str`]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Room::Str(room) => Some(room),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the [`Display`](std::fmt::Display) representation of the room starts with `prefix`
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        match self {
+            Room::Str(room) => room.starts_with(prefix),
+            Room::Int(_) | Room::Tagged(..) => self.to_string().starts_with(prefix),
+        }
+    }
+}
+
+impl std::fmt::Display for Room {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Room::Str(room) => f.write_str(room),
+            Room::Int(id) => write!(f, "{id}"),
+            Room::Tagged(tag, id) => write!(f, "{tag}:{id}"),
+        }
+    }
+}
+
+/// String rooms are serialized as strings, integer rooms as numbers and tagged rooms as `(tag, id)` tuples
+impl Serialize for Room {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Room::Str(room) => serializer.serialize_str(room),
+            Room::Int(id) => serializer.serialize_u64(*id),
+            Room::Tagged(tag, id) => (tag, id).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Room {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RoomVisitor;
+        impl<'de> Visitor<'de> for RoomVisitor {
+            type Value = Room;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a string, an unsigned integer or a (tag, id) tuple")
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Room, E> {
+                Ok(Room::Str(Cow::Owned(v.to_owned())))
+            }
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Room, E> {
+                Ok(Room::Str(Cow::Owned(v)))
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Room, E> {
+                Ok(Room::Int(v))
+            }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Room, E> {
+                u64::try_from(v)
+                    .map(Room::Int)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Room, A::Error> {
+                let tag: String = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let id = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Room::Tagged(Cow::Owned(tag), id))
+            }
+        }
+        deserializer.deserialize_any(RoomVisitor)
+    }
+}
+
+impl PartialEq<str> for Room {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+impl PartialEq<&str> for Room {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+impl PartialEq<String> for Room {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
+    }
+}
+
+/// Converts a room back to its [`Display`](std::fmt::Display) representation
+impl From<Room> for Cow<'static, str> {
+    fn from(room: Room) -> Self {
+        match room {
+            Room::Str(room) => room,
+            room => Cow::Owned(room.to_string()),
+        }
+    }
+}
+/// Converts a room back to its [`Display`](std::fmt::Display) representation
+impl From<Room> for String {
+    fn from(room: Room) -> Self {
+        Cow::from(room).into_owned()
+    }
+}
+
+impl From<Cow<'static, str>> for Room {
+    fn from(room: Cow<'static, str>) -> Self {
+        Room::Str(room)
+    }
+}
+impl From<&'static str> for Room {
+    fn from(room: &'static str) -> Self {
+        Room::Str(Cow::Borrowed(room))
+    }
+}
+impl From<String> for Room {
+    fn from(room: String) -> Self {
+        Room::Str(Cow::Owned(room))
+    }
+}
+impl From<u64> for Room {
+    fn from(id: u64) -> Self {
+        Room::Int(id)
+    }
+}
+impl From<u32> for Room {
+    fn from(id: u32) -> Self {
+        Room::Int(id.into())
+    }
+}
+impl From<usize> for Room {
+    fn from(id: usize) -> Self {
+        Room::Int(id as u64)
+    }
+}
+impl From<(&'static str, u64)> for Room {
+    fn from((tag, id): (&'static str, u64)) -> Self {
+        Room::Tagged(Cow::Borrowed(tag), id)
+    }
+}
+impl From<(String, u64)> for Room {
+    fn from((tag, id): (String, u64)) -> Self {
+        Room::Tagged(Cow::Owned(tag), id)
+    }
+}
+impl From<Sid> for Room {
+    fn from(sid: Sid) -> Self {
+        Room::Str(Cow::Owned(sid.to_string()))
+    }
+}
 
 /// Flags that can be used to modify the behavior of the broadcast methods.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BroadcastFlags {
     /// Broadcast only to the current server
     Local,
@@ -36,7 +234,12 @@ pub enum BroadcastFlags {
 }
 
 /// Options that can be used to modify the behavior of the broadcast methods.
-#[derive(Clone, Debug, Default)]
+///
+/// They can be serialized to be sent to the other servers by a remote adapter.
+/// New options may be added in the future, so they are built from [`BroadcastOptions::default`]
+/// rather than with a struct literal.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct BroadcastOptions {
     /// The flags to apply to the broadcast.
     pub flags: HashSet<BroadcastFlags>,
@@ -44,6 +247,11 @@ pub struct BroadcastOptions {
     pub rooms: HashSet<Room>,
     /// The rooms to exclude from the broadcast.
     pub except: HashSet<Room>,
+    /// The sockets to broadcast to, in addition to the sockets in `rooms`.
+    /// It is `None` if no socket was selected by id, an empty set selects no socket.
+    pub sids: Option<HashSet<Sid>>,
+    /// The sockets to exclude from the broadcast.
+    pub except_sids: HashSet<Sid>,
     /// The socket id of the sender.
     pub sid: Option<Sid>,
 }
@@ -76,12 +284,18 @@ pub trait Adapter: std::fmt::Debug + Send + Sync + 'static {
     fn del_all(&self, sid: Sid) -> Result<(), Self::Error>;
 
     /// Broadcasts the packet to the sockets that match the [`BroadcastOptions`].
-    fn broadcast(&self, packet: Packet<'_>, opts: BroadcastOptions) -> Result<(), BroadcastError>;
+    ///
+    /// The packet is already serialized so it can be shared between all the sockets.
+    fn broadcast(
+        &self,
+        packet: EncodedPacket,
+        opts: BroadcastOptions,
+    ) -> Result<(), BroadcastError>;
 
     /// Broadcasts the packet to the sockets that match the [`BroadcastOptions`] and return a stream of ack responses.
     fn broadcast_with_ack(
         &self,
-        packet: Packet<'static>,
+        packet: EncodedPacket,
         opts: BroadcastOptions,
         timeout: Option<Duration>,
     ) -> AckInnerStream;
@@ -106,20 +320,106 @@ pub trait Adapter: std::fmt::Debug + Send + Sync + 'static {
 
     /// Disconnects the sockets that match the [`BroadcastOptions`].
     fn disconnect_socket(&self, opts: BroadcastOptions) -> Result<(), Vec<DisconnectError>>;
+    /// Disconnects the sockets that match the [`BroadcastOptions`] with a custom reason code and message.
+    ///
+    /// The default implementation disconnects the sockets returned by [`Adapter::fetch_sockets`].
+    fn disconnect_socket_with(
+        &self,
+        opts: BroadcastOptions,
+        code: u16,
+        message: Cow<'static, str>,
+    ) -> Result<(), Vec<DisconnectError>>
+    where
+        Self: Sized,
+    {
+        let sockets = self
+            .fetch_sockets(opts)
+            .map_err(|e| vec![DisconnectError::Adapter(e.into())])?;
+        let errors: Vec<_> = sockets
+            .into_iter()
+            .filter_map(|sock| sock.disconnect_with(code, message.clone()).err())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 
     /// Returns all the rooms for this adapter.
     fn rooms(&self) -> Result<Vec<Room>, Self::Error>;
 
+    /// Returns the number of sockets in the room.
+    ///
+    /// The default implementation counts the [`sockets`](Adapter::sockets) of the room.
+    fn room_len(&self, room: &Room) -> Result<usize, Self::Error> {
+        Ok(self.sockets(room.clone())?.len())
+    }
+    /// Returns true if at least one socket is in the room.
+    ///
+    /// The default implementation relies on [`Adapter::room_len`].
+    fn room_exists(&self, room: &Room) -> Result<bool, Self::Error> {
+        Ok(self.room_len(room)? > 0)
+    }
+    /// Returns all the non-empty rooms whose name starts with the given prefix.
+    ///
+    /// The default implementation filters the [`rooms`](Adapter::rooms) of the adapter.
+    fn rooms_with_prefix(&self, prefix: &str) -> Result<Vec<Room>, Self::Error> {
+        let mut rooms = Vec::new();
+        for room in self.rooms()? {
+            if room.starts_with(prefix) && self.room_exists(&room)? {
+                rooms.push(room);
+            }
+        }
+        Ok(rooms)
+    }
+
+    /// Returns a clone of the metadata of type `T` attached to the room.
+    ///
+    /// The default implementation does not support metadata and always returns `None`.
+    fn room_meta<T>(&self, _room: &Room) -> Result<Option<T>, Self::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        Ok(None)
+    }
+    /// Attaches metadata of type `T` to the room, replacing any previous value of the same type.
+    ///
+    /// Metadata can only be attached to a non-empty room. It returns `false` if the room is empty.
+    /// The metadata must be removed by the adapter when the last socket leaves the room.
+    ///
+    /// The default implementation does not support metadata and always returns `false`.
+    fn set_room_meta<T>(&self, _room: &Room, _value: T) -> Result<bool, Self::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        Ok(false)
+    }
+    /// Removes the metadata of type `T` from the room and returns it.
+    ///
+    /// The default implementation does not support metadata and always returns `None`.
+    fn remove_room_meta<T>(&self, _room: &Room) -> Result<Option<T>, Self::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        Ok(None)
+    }
+
     //TODO: implement
     // fn server_side_emit(&self, packet: Packet, opts: BroadcastOptions) -> Result<u64, Error>;
     // fn persist_session(&self, sid: i64);
     // fn restore_session(&self, sid: i64) -> Session;
 }
 
+/// A typed map of metadata attached to a room.
+type RoomMetaMap = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
 /// The default adapter. Store the state in memory.
 #[derive(Debug)]
 pub struct LocalAdapter {
     rooms: RwLock<HashMap<Room, HashSet<Sid>>>,
+    meta: RwLock<HashMap<Room, RoomMetaMap>>,
     ns: Weak<Namespace<Self>>,
 }
 
@@ -135,6 +435,7 @@ impl Adapter for LocalAdapter {
     fn new(ns: Weak<Namespace<Self>>) -> Self {
         Self {
             rooms: HashMap::new().into(),
+            meta: HashMap::new().into(),
             ns,
         }
     }
@@ -149,6 +450,9 @@ impl Adapter for LocalAdapter {
         let mut rooms = self.rooms.write().unwrap();
         rooms.clear();
         rooms.shrink_to_fit();
+        let mut meta = self.meta.write().unwrap();
+        meta.clear();
+        meta.shrink_to_fit();
         Ok(())
     }
 
@@ -167,8 +471,10 @@ impl Adapter for LocalAdapter {
     fn del(&self, sid: Sid, rooms: impl RoomParam) -> Result<(), Infallible> {
         let mut rooms_map = self.rooms.write().unwrap();
         for room in rooms.into_room_iter() {
-            if let Some(room) = rooms_map.get_mut(&room) {
-                room.remove(&sid);
+            if let Some(sockets) = rooms_map.get_mut(&room) {
+                if sockets.remove(&sid) && sockets.is_empty() {
+                    self.meta.write().unwrap().remove(&room);
+                }
             }
         }
         Ok(())
@@ -176,13 +482,19 @@ impl Adapter for LocalAdapter {
 
     fn del_all(&self, sid: Sid) -> Result<(), Infallible> {
         let mut rooms_map = self.rooms.write().unwrap();
-        for room in rooms_map.values_mut() {
-            room.remove(&sid);
+        for (room, sockets) in rooms_map.iter_mut() {
+            if sockets.remove(&sid) && sockets.is_empty() {
+                self.meta.write().unwrap().remove(room);
+            }
         }
         Ok(())
     }
 
-    fn broadcast(&self, packet: Packet<'_>, opts: BroadcastOptions) -> Result<(), BroadcastError> {
+    fn broadcast(
+        &self,
+        packet: EncodedPacket,
+        opts: BroadcastOptions,
+    ) -> Result<(), BroadcastError> {
         let sockets = self.apply_opts(opts);
 
         #[cfg(feature = "tracing")]
@@ -200,7 +512,7 @@ impl Adapter for LocalAdapter {
 
     fn broadcast_with_ack(
         &self,
-        packet: Packet<'static>,
+        packet: EncodedPacket,
         opts: BroadcastOptions,
         timeout: Option<Duration>,
     ) -> AckInnerStream {
@@ -225,7 +537,7 @@ impl Adapter for LocalAdapter {
     }
 
     //TODO: make this operation O(1)
-    fn socket_rooms(&self, sid: Sid) -> Result<Vec<Cow<'static, str>>, Infallible> {
+    fn socket_rooms(&self, sid: Sid) -> Result<Vec<Room>, Infallible> {
         let rooms_map = self.rooms.read().unwrap();
         Ok(rooms_map
             .iter()
@@ -270,9 +582,94 @@ impl Adapter for LocalAdapter {
         }
     }
 
+    fn disconnect_socket_with(
+        &self,
+        opts: BroadcastOptions,
+        code: u16,
+        message: Cow<'static, str>,
+    ) -> Result<(), Vec<DisconnectError>> {
+        let errors: Vec<_> = self
+            .apply_opts(opts)
+            .into_iter()
+            .filter_map(|sock| sock.disconnect_with(code, message.clone()).err())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     fn rooms(&self) -> Result<Vec<Room>, Self::Error> {
         Ok(self.rooms.read().unwrap().keys().cloned().collect())
     }
+
+    fn room_len(&self, room: &Room) -> Result<usize, Infallible> {
+        let rooms_map = self.rooms.read().unwrap();
+        Ok(rooms_map.get(room).map(HashSet::len).unwrap_or_default())
+    }
+
+    fn room_exists(&self, room: &Room) -> Result<bool, Infallible> {
+        Ok(self.room_len(room)? > 0)
+    }
+
+    fn rooms_with_prefix(&self, prefix: &str) -> Result<Vec<Room>, Infallible> {
+        let rooms_map = self.rooms.read().unwrap();
+        Ok(rooms_map
+            .iter()
+            .filter(|(room, sockets)| room.starts_with(prefix) && !sockets.is_empty())
+            .map(|(room, _)| room.clone())
+            .collect())
+    }
+
+    fn room_meta<T>(&self, room: &Room) -> Result<Option<T>, Infallible>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        let meta = self.meta.read().unwrap();
+        Ok(meta
+            .get(room)
+            .and_then(|map| map.get(&TypeId::of::<T>()))
+            .and_then(|val| val.downcast_ref::<T>())
+            .cloned())
+    }
+
+    fn set_room_meta<T>(&self, room: &Room, value: T) -> Result<bool, Infallible>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        // The rooms lock is held so that the room cannot be emptied while the metadata is attached
+        let rooms_map = self.rooms.read().unwrap();
+        let Some((room, _)) = rooms_map.get_key_value(room).filter(|(_, s)| !s.is_empty()) else {
+            return Ok(false);
+        };
+        self.meta
+            .write()
+            .unwrap()
+            .entry(room.clone())
+            .or_default()
+            .insert(TypeId::of::<T>(), Box::new(value));
+        Ok(true)
+    }
+
+    fn remove_room_meta<T>(&self, room: &Room) -> Result<Option<T>, Infallible>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        let mut meta = self.meta.write().unwrap();
+        let Some(map) = meta.get_mut(room) else {
+            return Ok(None);
+        };
+        let val = map
+            .remove(&TypeId::of::<T>())
+            .and_then(|val| val.downcast::<T>().ok())
+            .map(|val| *val);
+        if map.is_empty() {
+            meta.remove(room);
+        }
+        Ok(val)
+    }
 }
 
 impl LocalAdapter {
@@ -280,20 +677,25 @@ impl LocalAdapter {
     fn apply_opts(&self, opts: BroadcastOptions) -> Vec<SocketRef<Self>> {
         let rooms = opts.rooms;
 
-        let except = self.get_except_sids(&opts.except);
+        let except = self.get_except_sids(&opts.except, opts.except_sids);
         let ns = self.ns.upgrade().unwrap();
-        if !rooms.is_empty() {
+        if !rooms.is_empty() || opts.sids.is_some() {
             let rooms_map = self.rooms.read().unwrap();
-            rooms
+            // A socket selected through several rooms or by its id must be selected once
+            let sids: HashSet<Sid> = rooms
                 .iter()
                 .filter_map(|room| rooms_map.get(room))
                 .flatten()
+                .chain(opts.sids.iter().flatten())
+                .copied()
+                .collect();
+            sids.into_iter()
                 .filter(|sid| {
-                    !except.contains(*sid)
+                    !except.contains(sid)
                         && (!opts.flags.contains(&BroadcastFlags::Broadcast)
-                            || opts.sid.map(|s| s != **sid).unwrap_or(true))
+                            || opts.sid.map(|s| s != *sid).unwrap_or(true))
                 })
-                .filter_map(|sid| ns.get_socket(*sid).ok())
+                .filter_map(|sid| ns.get_socket(sid).ok())
                 .map(SocketRef::from)
                 .collect()
         } else if opts.flags.contains(&BroadcastFlags::Broadcast) {
@@ -312,8 +714,11 @@ impl LocalAdapter {
         }
     }
 
-    fn get_except_sids(&self, except: &HashSet<Room>) -> HashSet<Sid> {
-        let mut except_sids = HashSet::new();
+    fn get_except_sids(
+        &self,
+        except: &HashSet<Room>,
+        mut except_sids: HashSet<Sid>,
+    ) -> HashSet<Sid> {
         let rooms_map = self.rooms.read().unwrap();
         for room in except {
             if let Some(sockets) = rooms_map.get(room) {
@@ -350,8 +755,8 @@ mod test {
         adapter.add_all(socket, ["room1", "room2"]).unwrap();
         let rooms_map = adapter.rooms.read().unwrap();
         assert_eq!(rooms_map.len(), 2);
-        assert_eq!(rooms_map.get("room1").unwrap().len(), 1);
-        assert_eq!(rooms_map.get("room2").unwrap().len(), 1);
+        assert_eq!(rooms_map.get(&Room::from("room1")).unwrap().len(), 1);
+        assert_eq!(rooms_map.get(&Room::from("room2")).unwrap().len(), 1);
     }
 
     #[tokio::test]
@@ -363,8 +768,8 @@ mod test {
         adapter.del(socket, "room1").unwrap();
         let rooms_map = adapter.rooms.read().unwrap();
         assert_eq!(rooms_map.len(), 2);
-        assert_eq!(rooms_map.get("room1").unwrap().len(), 0);
-        assert_eq!(rooms_map.get("room2").unwrap().len(), 1);
+        assert_eq!(rooms_map.get(&Room::from("room1")).unwrap().len(), 0);
+        assert_eq!(rooms_map.get(&Room::from("room2")).unwrap().len(), 1);
     }
 
     #[tokio::test]
@@ -376,8 +781,8 @@ mod test {
         adapter.del_all(socket).unwrap();
         let rooms_map = adapter.rooms.read().unwrap();
         assert_eq!(rooms_map.len(), 2);
-        assert_eq!(rooms_map.get("room1").unwrap().len(), 0);
-        assert_eq!(rooms_map.get("room2").unwrap().len(), 0);
+        assert_eq!(rooms_map.get(&Room::from("room1")).unwrap().len(), 0);
+        assert_eq!(rooms_map.get(&Room::from("room2")).unwrap().len(), 0);
     }
 
     #[tokio::test]
@@ -418,8 +823,14 @@ mod test {
         let rooms_map = adapter.rooms.read().unwrap();
 
         assert_eq!(rooms_map.len(), 2);
-        assert!(rooms_map.get("room1").unwrap().contains(&socket));
-        assert!(rooms_map.get("room2").unwrap().contains(&socket));
+        assert!(rooms_map
+            .get(&Room::from("room1"))
+            .unwrap()
+            .contains(&socket));
+        assert!(rooms_map
+            .get(&Room::from("room2"))
+            .unwrap()
+            .contains(&socket));
     }
 
     #[tokio::test]
@@ -440,8 +851,14 @@ mod test {
             let rooms_map = adapter.rooms.read().unwrap();
 
             assert_eq!(rooms_map.len(), 2);
-            assert!(rooms_map.get("room1").unwrap().contains(&socket));
-            assert!(rooms_map.get("room2").unwrap().contains(&socket));
+            assert!(rooms_map
+                .get(&Room::from("room1"))
+                .unwrap()
+                .contains(&socket));
+            assert!(rooms_map
+                .get(&Room::from("room2"))
+                .unwrap()
+                .contains(&socket));
         }
 
         let mut opts = BroadcastOptions {
@@ -455,8 +872,11 @@ mod test {
             let rooms_map = adapter.rooms.read().unwrap();
 
             assert_eq!(rooms_map.len(), 2);
-            assert!(rooms_map.get("room1").unwrap().contains(&socket));
-            assert!(rooms_map.get("room2").unwrap().is_empty());
+            assert!(rooms_map
+                .get(&Room::from("room1"))
+                .unwrap()
+                .contains(&socket));
+            assert!(rooms_map.get(&Room::from("room2")).unwrap().is_empty());
         }
     }
 
@@ -578,4 +998,199 @@ mod test {
         let sockets = adapter.fetch_sockets(opts).unwrap();
         assert_eq!(sockets.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_apply_opts_sids() {
+        let socket0 = Sid::new();
+        let socket1 = Sid::new();
+        let socket2 = Sid::new();
+        let ns = Namespace::new_dummy([socket0, socket1, socket2]);
+        let adapter = LocalAdapter::new(Arc::downgrade(&ns));
+        adapter.add_all(socket0, ["room1"]).unwrap();
+        adapter.add_all(socket1, ["room1"]).unwrap();
+
+        // socket 0 is the sender and selects itself and socket 2 explicitly
+        let opts = BroadcastOptions {
+            sid: Some(socket0),
+            sids: Some(hash_set![socket0, socket2]),
+            ..Default::default()
+        };
+        let mut sockets: Vec<_> = adapter.fetch_sockets(opts).unwrap();
+        sockets.sort_by_key(|s| s.id);
+        let mut expected = vec![socket0, socket2];
+        expected.sort();
+        assert_eq!(sockets.iter().map(|s| s.id).collect::<Vec<_>>(), expected);
+
+        // sockets selected both by room and by id are selected once
+        let opts = BroadcastOptions {
+            rooms: hash_set!["room1".into()],
+            sids: Some(hash_set![socket1, socket2]),
+            ..Default::default()
+        };
+        assert_eq!(adapter.fetch_sockets(opts).unwrap().len(), 3);
+
+        // an empty set of ids selects no socket, not even the sender
+        let opts = BroadcastOptions {
+            sid: Some(socket0),
+            sids: Some(hash_set![]),
+            ..Default::default()
+        };
+        assert!(adapter.fetch_sockets(opts).unwrap().is_empty());
+
+        let mut opts = BroadcastOptions {
+            sid: Some(socket0),
+            except_sids: hash_set![socket1],
+            ..Default::default()
+        };
+        opts.flags.insert(BroadcastFlags::Broadcast);
+        let sockets = adapter.fetch_sockets(opts).unwrap();
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].id, socket2);
+    }
+
+    #[tokio::test]
+    async fn test_room_len_and_exists() {
+        let socket0 = Sid::new();
+        let socket1 = Sid::new();
+        let ns = Namespace::new_dummy([socket0, socket1]);
+        let adapter = LocalAdapter::new(Arc::downgrade(&ns));
+        adapter.add_all(socket0, ["room1", "room2"]).unwrap();
+        adapter.add_all(socket1, ["room1"]).unwrap();
+
+        assert_eq!(adapter.room_len(&Room::from("room1")).unwrap(), 2);
+        assert_eq!(adapter.room_len(&Room::from("room2")).unwrap(), 1);
+        assert_eq!(adapter.room_len(&Room::from("room3")).unwrap(), 0);
+        assert!(adapter.room_exists(&Room::from("room2")).unwrap());
+        assert!(!adapter.room_exists(&Room::from("room3")).unwrap());
+
+        adapter.del(socket0, "room2").unwrap();
+        assert_eq!(adapter.room_len(&Room::from("room2")).unwrap(), 0);
+        assert!(!adapter.room_exists(&Room::from("room2")).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_typed_rooms() {
+        let socket0 = Sid::new();
+        let socket1 = Sid::new();
+        let ns = Namespace::new_dummy([socket0, socket1]);
+        let adapter = LocalAdapter::new(Arc::downgrade(&ns));
+        adapter.add_all(socket0, [1, 2]).unwrap();
+        adapter.add_all(socket1, ("user", 1)).unwrap();
+        adapter.add_all(socket1, "user:2").unwrap();
+
+        assert_eq!(adapter.room_len(&Room::Int(1)).unwrap(), 1);
+        assert_eq!(adapter.room_len(&Room::from(("user", 1))).unwrap(), 1);
+        // A tagged room is not equal to a string room with the same representation
+        assert_eq!(adapter.room_len(&Room::from(("user", 2))).unwrap(), 0);
+        assert_eq!(adapter.room_len(&Room::from("user:1")).unwrap(), 0);
+
+        let mut rooms = adapter.socket_rooms(socket1).unwrap();
+        rooms.sort();
+        assert_eq!(rooms, [Room::from("user:2"), Room::from(("user", 1))]);
+        assert_eq!(adapter.rooms_with_prefix("user:").unwrap().len(), 2);
+
+        let opts = BroadcastOptions {
+            rooms: hash_set![Room::Int(2), Room::from(("user", 1))],
+            ..Default::default()
+        };
+        assert_eq!(adapter.fetch_sockets(opts).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_broadcast_options_serde() {
+        let opts = BroadcastOptions {
+            flags: hash_set![BroadcastFlags::Broadcast],
+            rooms: hash_set![Room::from("room1"), Room::Int(1)],
+            except: hash_set![Room::from(("user", 2))],
+            sids: Some(hash_set![Sid::new()]),
+            except_sids: hash_set![Sid::new()],
+            sid: Some(Sid::new()),
+        };
+        let json = serde_json::to_string(&opts).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BroadcastOptions>(&json).unwrap(),
+            opts
+        );
+    }
+
+    #[test]
+    fn test_room_serde() {
+        let rooms = [
+            Room::from("user:42"),
+            Room::Int(42),
+            Room::from(("user", 42)),
+        ];
+        let json = serde_json::to_string(&rooms).unwrap();
+        assert_eq!(json, r#"["user:42",42,["user",42]]"#);
+        let decoded: Vec<Room> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, rooms);
+        assert!(serde_json::from_str::<Room>("-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rooms_with_prefix() {
+        let socket = Sid::new();
+        let ns = Namespace::new_dummy([socket]);
+        let adapter = LocalAdapter::new(Arc::downgrade(&ns));
+        adapter
+            .add_all(socket, ["game:1", "game:2", "chat:1"])
+            .unwrap();
+        adapter.del(socket, "game:2").unwrap();
+
+        assert_eq!(adapter.rooms_with_prefix("game:").unwrap(), ["game:1"]);
+        assert_eq!(adapter.rooms_with_prefix("").unwrap().len(), 2);
+        assert!(adapter.rooms_with_prefix("lobby").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_room_meta() {
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Topic(String);
+
+        let socket0 = Sid::new();
+        let socket1 = Sid::new();
+        let ns = Namespace::new_dummy([socket0, socket1]);
+        let adapter = LocalAdapter::new(Arc::downgrade(&ns));
+        assert!(!adapter.set_room_meta(&Room::from("room1"), 1u8).unwrap());
+
+        adapter.add_all(socket0, ["room1"]).unwrap();
+        adapter.add_all(socket1, ["room1"]).unwrap();
+        assert!(adapter.set_room_meta(&Room::from("room1"), 1u8).unwrap());
+        assert!(adapter
+            .set_room_meta(&Room::from("room1"), Topic("rust".into()))
+            .unwrap());
+        assert_eq!(
+            adapter.room_meta::<u8>(&Room::from("room1")).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            adapter.room_meta::<Topic>(&Room::from("room1")).unwrap(),
+            Some(Topic("rust".into()))
+        );
+        assert_eq!(
+            adapter.room_meta::<u16>(&Room::from("room1")).unwrap(),
+            None
+        );
+
+        assert_eq!(
+            adapter
+                .remove_room_meta::<u8>(&Room::from("room1"))
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(adapter.room_meta::<u8>(&Room::from("room1")).unwrap(), None);
+
+        // The metadata is kept until the last socket leaves the room
+        adapter.del(socket0, "room1").unwrap();
+        assert!(adapter
+            .room_meta::<Topic>(&Room::from("room1"))
+            .unwrap()
+            .is_some());
+        adapter.del_all(socket1).unwrap();
+        assert_eq!(
+            adapter.room_meta::<Topic>(&Room::from("room1")).unwrap(),
+            None
+        );
+        assert!(adapter.meta.read().unwrap().is_empty());
+    }
 }
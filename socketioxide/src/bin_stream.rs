@@ -0,0 +1,246 @@
+//! Streaming of the binary attachments of an event, see [`BinStream`].
+//!
+//! The attachments received by the [`Client`](crate::client::Client) are forwarded with a [`BinStreamTx`]
+//! to the [`BinStream`] extracted by the handler.
+//! When too many attachments are waiting to be consumed, reading from the engine.io transport is paused.
+//! The connection is closed if it stays paused for longer than the
+//! [`max_read_pause`](crate::SocketIoBuilder::max_read_pause) setting.
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use engineioxide::Socket as EIoSocket;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::{client::SocketData, errors::BinStreamError};
+
+/// The number of attachments that can wait to be consumed before pausing the transport
+const BIN_STREAM_BUFFER: usize = 8;
+
+type Item = Result<Bytes, BinStreamError>;
+
+/// State shared between the [`BinStream`] and its [`BinStreamTx`]
+#[derive(Debug)]
+struct Shared {
+    /// The number of attachments sent to the stream and not consumed yet
+    queued: Mutex<usize>,
+    esocket: Arc<EIoSocket<SocketData>>,
+}
+
+impl Shared {
+    fn push(&self, tx: &mpsc::UnboundedSender<Item>, item: Item) {
+        let mut queued = self.queued.lock().unwrap();
+        if tx.send(item).is_ok() {
+            *queued += 1;
+            if *queued == BIN_STREAM_BUFFER {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[sid={}] pausing transport for bin stream", self.esocket.id);
+                self.esocket.pause_read();
+            }
+        }
+    }
+
+    fn pop(&self) {
+        let mut queued = self.queued.lock().unwrap();
+        if *queued == BIN_STREAM_BUFFER {
+            self.esocket.resume_read();
+        }
+        *queued = queued.saturating_sub(1);
+    }
+}
+
+/// An Extractor that yields the binary attachments of an event as soon as they are received,
+/// instead of waiting for all of them like the [`Bin`](crate::extract::Bin) extractor.
+/// It consumes the binary attachments so it should be the last argument of the handler.
+///
+/// The handler is called as soon as the event is received, the json data is available right away.
+/// When the handler does not consume the attachments fast enough, the server stops reading from the transport
+/// until the stream is polled again.
+///
+/// The total size of the attachments is limited by [`SocketIoBuilder::max_bin_stream_size`].
+/// When it is exceeded, a [`BinStreamError::LimitExceeded`] is yielded and the stream ends.
+///
+/// [`SocketIoBuilder::max_bin_stream_size`]: crate::SocketIoBuilder#method.max_bin_stream_size
+///
+/// ### Example
+/// ```
+/// # use socketioxide::{SocketIo, extract::*};
+/// # use futures_util::StreamExt;
+/// let (_, io) = SocketIo::new_svc();
+/// io.ns("/", |s: SocketRef| {
+///     s.on("upload", |Data::<String>(name), mut stream: BinStream| async move {
+///         let mut size = 0;
+///         while let Some(chunk) = stream.next().await {
+///             match chunk {
+///                 Ok(chunk) => size += chunk.len(),
+///                 Err(e) => return println!("upload of {name} failed: {e}"),
+///             }
+///         }
+///         println!("received {size} bytes for {name}");
+///     });
+/// });
+/// ```
+#[derive(Debug)]
+pub struct BinStream {
+    rx: mpsc::UnboundedReceiver<Item>,
+    shared: Arc<Shared>,
+    /// The number of attachments not yielded yet
+    remaining: usize,
+    done: bool,
+}
+
+impl BinStream {
+    /// The number of attachments that are still expected on this stream
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl Stream for BinStream {
+    type Item = Result<Bytes, BinStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let item = match self.rx.poll_recv(cx) {
+            Poll::Ready(Some(item)) => item,
+            Poll::Ready(None) => {
+                self.done = true;
+                let res = (self.remaining > 0).then_some(Err(BinStreamError::Closed));
+                return Poll::Ready(res);
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+        self.shared.pop();
+        match item {
+            Ok(_) => self.remaining -= 1,
+            Err(_) => self.done = true,
+        }
+        Poll::Ready(Some(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl Drop for BinStream {
+    fn drop(&mut self) {
+        // Remaining attachments are discarded, the transport must not stay paused
+        self.rx.close();
+        let mut queued = self.shared.queued.lock().unwrap();
+        if *queued >= BIN_STREAM_BUFFER {
+            self.shared.esocket.resume_read();
+        }
+        *queued = 0;
+    }
+}
+
+/// The sending half of a [`BinStream`], stored in the [`SocketData`] until all the attachments are received.
+#[derive(Debug)]
+pub(crate) struct BinStreamTx {
+    tx: mpsc::UnboundedSender<Item>,
+    shared: Arc<Shared>,
+    /// The number of attachments not received yet
+    remaining: usize,
+    /// The number of bytes that can still be received before reaching the limit
+    limit: usize,
+    overflowed: bool,
+}
+
+impl BinStreamTx {
+    /// Forwards an attachment to the stream.
+    /// Once the limit is exceeded, an error is sent and the next attachments are dropped.
+    ///
+    /// Returns true if all the attachments have been received
+    pub fn send(&mut self, data: Bytes) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        if !self.overflowed {
+            match self.limit.checked_sub(data.len()) {
+                Some(limit) => {
+                    self.limit = limit;
+                    self.shared.push(&self.tx, Ok(data));
+                }
+                None => {
+                    self.overflowed = true;
+                    self.shared
+                        .push(&self.tx, Err(BinStreamError::LimitExceeded));
+                }
+            }
+        }
+        self.remaining == 0
+    }
+}
+
+/// Creates a [`BinStream`] expecting `count` attachments for a maximum of `limit` bytes
+pub(crate) fn channel(
+    esocket: Arc<EIoSocket<SocketData>>,
+    count: usize,
+    limit: usize,
+) -> (BinStreamTx, BinStream) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let shared = Arc::new(Shared {
+        queued: Mutex::new(0),
+        esocket,
+    });
+    let tx = BinStreamTx {
+        tx,
+        shared: shared.clone(),
+        remaining: count,
+        limit,
+        overflowed: false,
+    };
+    let stream = BinStream {
+        rx,
+        shared,
+        remaining: count,
+        done: false,
+    };
+    (tx, stream)
+}
+
+#[cfg(test)]
+mod test {
+    use engineioxide::sid::Sid;
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn bin_stream_backpressure() {
+        let esocket = EIoSocket::new_dummy(Sid::new(), Box::new(|_, _| {}));
+        let (mut tx, mut stream) = channel(esocket.clone(), 16, usize::MAX);
+        for i in 0..BIN_STREAM_BUFFER {
+            assert!(!esocket.is_read_paused());
+            tx.send(Bytes::from(vec![i as u8]));
+        }
+        assert!(esocket.is_read_paused());
+
+        assert_eq!(stream.next().await, Some(Ok(Bytes::from_static(&[0]))));
+        assert!(!esocket.is_read_paused());
+        tx.send(Bytes::new());
+        assert!(esocket.is_read_paused());
+
+        // Dropping the stream resumes the transport
+        drop(stream);
+        assert!(!esocket.is_read_paused());
+        tx.send(Bytes::new());
+        assert!(!esocket.is_read_paused());
+    }
+
+    #[tokio::test]
+    async fn bin_stream_closed() {
+        let esocket = EIoSocket::new_dummy(Sid::new(), Box::new(|_, _| {}));
+        let (mut tx, mut stream) = channel(esocket, 2, usize::MAX);
+        assert!(!tx.send(Bytes::from_static(&[1])));
+        drop(tx);
+        assert_eq!(stream.next().await, Some(Ok(Bytes::from_static(&[1]))));
+        assert_eq!(stream.next().await, Some(Err(BinStreamError::Closed)));
+        assert_eq!(stream.next().await, None);
+    }
+}
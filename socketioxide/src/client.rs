@@ -7,12 +7,19 @@ use engineioxide::handler::EngineIoHandler;
 use engineioxide::socket::{DisconnectReason as EIoDisconnectReason, Socket as EIoSocket};
 use engineioxide::Str;
 use futures_util::{FutureExt, TryFutureExt};
+use http::{request::Parts, HeaderMap};
 
 use engineioxide::sid::Sid;
 use tokio::sync::oneshot;
+use tokio_util::task::TaskTracker;
 
 use crate::adapter::Adapter;
-use crate::handler::ConnectHandler;
+use crate::bin_stream::BinStreamTx;
+use crate::handler::{
+    BoxedConnectMiddleware, ConnectHandler, ConnectMiddleware, EventRequest, EventService,
+    MakeErasedHandler,
+};
+use crate::interceptor::intercept_emit;
 use crate::socket::DisconnectReason;
 use crate::ProtocolVersion;
 use crate::{
@@ -26,6 +33,10 @@ use crate::{
 pub struct Client<A: Adapter> {
     pub(crate) config: Arc<SocketIoConfig>,
     ns: RwLock<HashMap<Cow<'static, str>, Arc<Namespace<A>>>>,
+    middlewares: RwLock<Vec<BoxedConnectMiddleware<A>>>,
+    event_service: RwLock<EventService<A>>,
+    /// Tracks the async handler tasks of all the sockets
+    tasks: TaskTracker,
 }
 
 impl<A: Adapter> Client<A> {
@@ -36,6 +47,9 @@ impl<A: Adapter> Client<A> {
         Self {
             config,
             ns: RwLock::new(HashMap::new()),
+            middlewares: RwLock::new(Vec::new()),
+            event_service: RwLock::new(EventService::new()),
+            tasks: TaskTracker::new(),
         }
     }
 
@@ -52,9 +66,19 @@ impl<A: Adapter> Client<A> {
         if let Some(ns) = self.get_ns(ns_path) {
             let esocket = esocket.clone();
             let config = self.config.clone();
+            let middlewares = self.middlewares.read().unwrap().clone();
+            let event_service = self.event_service.read().unwrap().clone();
+            let tasks = self.tasks.clone();
             tokio::spawn(async move {
                 if ns
-                    .connect(esocket.id, esocket.clone(), auth, config)
+                    .connect(
+                        esocket.clone(),
+                        auth,
+                        config,
+                        middlewares,
+                        event_service,
+                        tasks,
+                    )
                     .await
                     .is_ok()
                 {
@@ -74,6 +98,11 @@ impl<A: Adapter> Client<A> {
             Ok(())
         } else {
             let packet: String = Packet::connect_error(ns_path, "Invalid namespace").into();
+            let interceptor = self.config.interceptor.as_deref();
+            let Some((packet, _)) = intercept_emit(interceptor, esocket.id, packet.into(), vec![])
+            else {
+                return Ok(());
+            };
             if let Err(_e) = esocket.emit(packet) {
                 #[cfg(feature = "tracing")]
                 tracing::error!("error while sending invalid namespace packet: {}", _e);
@@ -93,6 +122,35 @@ impl<A: Adapter> Client<A> {
         }
     }
 
+    /// If the packet is a binary event listened by a handler with a [`BinStream`](crate::extract::BinStream) extractor,
+    /// the handler is called right away and the next binary payloads will be forwarded to the stream.
+    ///
+    /// Otherwise the packet is given back so it can be cached until all the binary payloads are received
+    fn sock_open_bin_stream(
+        &self,
+        packet: Packet<'static>,
+        esocket: &EIoSocket<SocketData>,
+    ) -> Option<Packet<'static>> {
+        let socket = match packet.inner {
+            PacketData::BinaryEvent(ref e, _, _) => self
+                .get_ns(&packet.ns)
+                .and_then(|ns| ns.get_socket(esocket.id).ok())
+                .filter(|s| s.is_bin_stream_event(e)),
+            _ => None,
+        };
+        let Some(socket) = socket else {
+            return Some(packet);
+        };
+        if let PacketData::BinaryEvent(e, bin, ack) = packet.inner {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[sid={}] streaming binary event {}", esocket.id, e);
+            if let Some(tx) = socket.recv_bin_stream(&e, bin, ack) {
+                esocket.data.partial_bin_stream.lock().unwrap().replace(tx);
+            }
+        }
+        None
+    }
+
     /// Spawn a task that will close the socket if it is not connected to a namespace
     /// after the [`SocketIoConfig::connect_timeout`] duration
     fn spawn_connect_timeout_task(&self, socket: Arc<EIoSocket<SocketData>>) {
@@ -122,6 +180,33 @@ impl<A: Adapter> Client<A> {
         self.ns.write().unwrap().insert(path, ns);
     }
 
+    /// Adds a middleware called for every namespace connection, before the namespace middlewares
+    pub fn add_middleware<M, T>(&self, middleware: M)
+    where
+        M: ConnectMiddleware<A, T>,
+        T: Send + Sync + 'static,
+    {
+        let middleware = MakeErasedHandler::new_middleware_boxed(middleware);
+        self.middlewares.write().unwrap().push(middleware);
+    }
+
+    /// Adds the middlewares registered on the [`SocketIoBuilder`](crate::SocketIoBuilder)
+    pub(crate) fn extend_middlewares(&self, middlewares: Vec<BoxedConnectMiddleware<A>>) {
+        self.middlewares.write().unwrap().extend(middlewares);
+    }
+
+    /// Wraps the dispatch of the events of the sockets connected afterwards with a layer
+    pub fn add_event_layer<L>(&self, layer: L)
+    where
+        L: tower::Layer<EventService<A>>,
+        L::Service: tower::Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+        <L::Service as tower::Service<EventRequest<A>>>::Error: Into<tower::BoxError>,
+        <L::Service as tower::Service<EventRequest<A>>>::Future: Send + 'static,
+    {
+        let mut svc = self.event_service.write().unwrap();
+        *svc = svc.clone().layer(layer);
+    }
+
     /// Deletes a namespace handler and closes all the connections to it
     pub fn delete_ns(&self, path: &str) {
         #[cfg(feature = "v4")]
@@ -156,6 +241,13 @@ impl<A: Adapter> Client<A> {
         tracing::debug!("all namespaces closed");
     }
 
+    /// Waits for all the async handler tasks to complete
+    pub(crate) async fn wait_handlers(&self) {
+        self.tasks.close();
+        self.tasks.wait().await;
+        self.tasks.reopen();
+    }
+
     #[cfg(socketioxide_test)]
     pub async fn new_dummy_sock(
         self: Arc<Self>,
@@ -211,6 +303,10 @@ pub struct SocketData {
     /// Stored here until all the binary payloads are received
     pub partial_bin_packet: Mutex<Option<Packet<'static>>>,
 
+    /// Sender of the binary payloads that are being streamed to a [`BinStream`](crate::extract::BinStream)
+    /// Stored here until all the binary payloads are received
+    pub(crate) partial_bin_stream: Mutex<Option<BinStreamTx>>,
+
     /// Channel used to notify the socket that it has been connected to a namespace for v5
     pub connect_recv_tx: Mutex<Option<oneshot::Sender<()>>>,
 }
@@ -243,6 +339,8 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
     fn on_disconnect(&self, socket: Arc<EIoSocket<SocketData>>, reason: EIoDisconnectReason) {
         #[cfg(feature = "tracing")]
         tracing::debug!("eio socket disconnected");
+        // Ends the pending binary stream, if any
+        socket.data.partial_bin_stream.lock().unwrap().take();
         let socks: Vec<_> = self
             .ns
             .read()
@@ -251,18 +349,11 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
             .filter_map(|ns| ns.get_socket(socket.id).ok())
             .collect();
 
-        let _res: Result<Vec<_>, _> = socks
-            .into_iter()
-            .map(|s| s.close(reason.clone().into()))
-            .collect();
-
         #[cfg(feature = "tracing")]
-        match _res {
-            Ok(vec) => {
-                tracing::debug!("disconnect handle spawned for {} namespaces", vec.len())
-            }
-            Err(_e) => {
-                tracing::debug!("error while disconnecting socket: {}", _e)
+        tracing::debug!("disconnect handle spawned for {} namespaces", socks.len());
+        for s in socks {
+            if let Err(err) = s.clone().close(reason.clone().into()) {
+                s.report_error(None, None, err.into());
             }
         }
     }
@@ -270,6 +361,13 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
     fn on_message(&self, msg: Str, socket: Arc<EIoSocket<SocketData>>) {
         #[cfg(feature = "tracing")]
         tracing::debug!("Received message: {:?}", msg);
+        let msg = match &self.config.interceptor {
+            Some(interceptor) => match interceptor.on_message(socket.id, msg) {
+                Some(msg) => msg,
+                None => return,
+            },
+            None => msg,
+        };
         let packet = match Packet::try_from(msg) {
             Ok(packet) => packet,
             Err(_e) => {
@@ -287,13 +385,15 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
                 .sock_connect(auth, &packet.ns, &socket)
                 .map_err(Into::into),
             PacketData::BinaryEvent(_, _, _) | PacketData::BinaryAck(_, _) => {
-                // Cache-in the socket data until all the binary payloads are received
-                socket
-                    .data
-                    .partial_bin_packet
-                    .lock()
-                    .unwrap()
-                    .replace(packet);
+                if let Some(packet) = self.sock_open_bin_stream(packet, &socket) {
+                    // Cache-in the socket data until all the binary payloads are received
+                    socket
+                        .data
+                        .partial_bin_packet
+                        .lock()
+                        .unwrap()
+                        .replace(packet);
+                }
                 Ok(())
             }
             _ => self.sock_propagate_packet(packet, socket.id),
@@ -315,6 +415,19 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
     ///
     /// If the packet is complete, it is propagated to the namespace
     fn on_binary(&self, data: Bytes, socket: Arc<EIoSocket<SocketData>>) {
+        let data = match &self.config.interceptor {
+            Some(interceptor) => interceptor.on_binary(socket.id, data),
+            None => data,
+        };
+        {
+            let mut stream = socket.data.partial_bin_stream.lock().unwrap();
+            if let Some(tx) = stream.as_mut() {
+                if tx.send(data) {
+                    stream.take();
+                }
+                return;
+            }
+        }
         if apply_payload_on_packet(data, &socket) {
             if let Some(packet) = socket.data.partial_bin_packet.lock().unwrap().take() {
                 if let Err(ref err) = self.sock_propagate_packet(packet, socket.id) {
@@ -331,6 +444,18 @@ impl<A: Adapter> EngineIoHandler for Client<A> {
             }
         }
     }
+
+    fn on_initial_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        if let Some(hook) = &self.config.on_initial_headers {
+            hook.call(headers, req);
+        }
+    }
+
+    fn on_headers(&self, headers: &mut HeaderMap, req: &Parts) {
+        if let Some(hook) = &self.config.on_headers {
+            hook.call(headers, req);
+        }
+    }
 }
 
 /// Utility that applies an incoming binary payload to a partial binary packet
@@ -342,7 +467,11 @@ fn apply_payload_on_packet(data: Bytes, socket: &EIoSocket<SocketData>) -> bool
     tracing::debug!("[sid={}] applying payload on packet", socket.id);
     if let Some(ref mut packet) = *socket.data.partial_bin_packet.lock().unwrap() {
         match packet.inner {
-            PacketData::BinaryEvent(_, ref mut bin, _) | PacketData::BinaryAck(ref mut bin, _) => {
+            PacketData::BinaryEvent(_, ref mut bin, _) => {
+                bin.add_payload(data);
+                bin.is_complete()
+            }
+            PacketData::BinaryAck(ref mut bin, _) => {
                 bin.add_payload(data);
                 bin.is_complete()
             }
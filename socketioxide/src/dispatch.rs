@@ -0,0 +1,234 @@
+//! Execution of the async message handlers of a socket according to the [`DispatchMode`]
+//! and the in-flight handler limit of the [`SocketIoConfig`](crate::SocketIoConfig).
+//!
+//! The spawned handlers are tracked by the [`TaskTracker`] of the server, so that they can be awaited
+//! when it is closed, and can observe the disconnection of their socket through its [`CancellationToken`].
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use engineioxide::Socket as EIoSocket;
+use futures_util::future::BoxFuture;
+use tokio::sync::mpsc;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::client::SocketData;
+
+/// The order in which the async message handlers of a socket are executed.
+///
+/// Sync handlers are always executed when the event is received, except in the sequential modes
+/// where they are queued with the async ones to keep the order of the events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DispatchMode {
+    /// Each async handler is spawned in its own task as soon as the event is received,
+    /// the events of a socket are processed concurrently and can complete out of order.
+    #[default]
+    Concurrent,
+    /// The events of a socket are processed one at a time, in the order they are received.
+    Sequential,
+    /// The events with the same name are processed one at a time, in the order they are received.
+    /// Events with different names are processed concurrently.
+    SequentialPerEvent,
+}
+
+type HandlerFut = BoxFuture<'static, ()>;
+
+/// Executes the handler futures of a socket
+#[derive(Debug)]
+pub(crate) struct Dispatcher {
+    mode: DispatchMode,
+    in_flight: Arc<InFlight>,
+    /// The tracker of all the handler tasks of the server
+    tasks: TaskTracker,
+    /// Cancelled when the socket is closed
+    cancel: CancellationToken,
+    /// The queue of the socket in [`DispatchMode::Sequential`]
+    queue: Mutex<Option<mpsc::UnboundedSender<HandlerFut>>>,
+    /// The queues of each event in [`DispatchMode::SequentialPerEvent`]
+    event_queues: Mutex<HashMap<String, mpsc::UnboundedSender<HandlerFut>>>,
+}
+
+impl Dispatcher {
+    pub fn new(
+        mode: DispatchMode,
+        max_in_flight: Option<usize>,
+        esocket: Arc<EIoSocket<SocketData>>,
+        tasks: TaskTracker,
+    ) -> Self {
+        Self {
+            mode,
+            tasks,
+            cancel: CancellationToken::new(),
+            in_flight: Arc::new(InFlight {
+                max: max_in_flight,
+                state: Mutex::new(InFlightState::default()),
+                esocket,
+            }),
+            queue: Mutex::new(None),
+            event_queues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if the events can be processed as soon as they are received
+    pub fn is_concurrent(&self) -> bool {
+        self.mode == DispatchMode::Concurrent
+    }
+
+    /// Executes a handler future for the given event according to the dispatch mode.
+    ///
+    /// If there is an in-flight limit, it counts as in flight until it completes.
+    pub fn spawn(&self, event: &str, fut: HandlerFut) {
+        let fut: HandlerFut = match self.in_flight.max {
+            Some(_) => {
+                let guard = self.in_flight.acquire();
+                Box::pin(async move {
+                    fut.await;
+                    drop(guard);
+                })
+            }
+            None => fut,
+        };
+
+        match self.mode {
+            DispatchMode::Concurrent => {
+                self.tasks.spawn(fut);
+            }
+            DispatchMode::Sequential => {
+                let mut queue = self.queue.lock().unwrap();
+                queue
+                    .get_or_insert_with(|| spawn_queue(&self.tasks))
+                    .send(fut)
+                    .ok();
+            }
+            DispatchMode::SequentialPerEvent => {
+                let mut queues = self.event_queues.lock().unwrap();
+                match queues.get(event) {
+                    Some(queue) => queue.send(fut).ok(),
+                    None => queues
+                        .entry(event.to_string())
+                        .or_insert_with(|| spawn_queue(&self.tasks))
+                        .send(fut)
+                        .ok(),
+                };
+            }
+        }
+    }
+
+    /// Returns true if there is an in-flight limit
+    pub fn has_in_flight_limit(&self) -> bool {
+        self.in_flight.max.is_some()
+    }
+
+    /// Returns a guard to hold while an acknowledgement is awaited, if there is an in-flight limit.
+    ///
+    /// The transport is not paused while a guard is held so that the acknowledgement can be read.
+    pub fn await_ack(&self) -> Option<AckGuard> {
+        self.has_in_flight_limit()
+            .then(|| self.in_flight.await_ack())
+    }
+
+    /// Spawns a tracked task outside of the dispatch mode and the in-flight limit
+    pub fn spawn_tracked<F>(&self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(fut);
+    }
+
+    /// The token cancelled when the socket is closed
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Cancels the token of the socket and drops the queues.
+    /// The handlers already queued are still executed.
+    pub fn close(&self) {
+        self.cancel.cancel();
+        self.queue.lock().unwrap().take();
+        self.event_queues.lock().unwrap().clear();
+    }
+}
+
+/// Spawns a task executing the futures sent to the returned queue one after the other
+fn spawn_queue(tasks: &TaskTracker) -> mpsc::UnboundedSender<HandlerFut> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<HandlerFut>();
+    tasks.spawn(async move {
+        while let Some(fut) = rx.recv().await {
+            // Each handler runs in its own task so that a panic does not stop the queue
+            tokio::spawn(fut).await.ok();
+        }
+    });
+    tx
+}
+
+/// The number of handlers in flight for a socket.
+/// Reading from the transport is paused while the limit is reached,
+/// unless a handler of the socket awaits an acknowledgement that could then never be read.
+#[derive(Debug)]
+struct InFlight {
+    max: Option<usize>,
+    state: Mutex<InFlightState>,
+    esocket: Arc<EIoSocket<SocketData>>,
+}
+
+#[derive(Debug, Default)]
+struct InFlightState {
+    handlers: usize,
+    acks: usize,
+    paused: bool,
+}
+
+impl InFlight {
+    /// Pauses or resumes the transport according to the state.
+    /// It is called with the lock held so that concurrent updates cannot interleave.
+    fn update(&self, state: &mut InFlightState) {
+        let pause = self.max.is_some_and(|max| state.handlers >= max) && state.acks == 0;
+        if pause && !state.paused {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(sid = ?self.esocket.id, "max in-flight handlers reached, pausing transport");
+            self.esocket.pause_read();
+        } else if !pause && state.paused {
+            self.esocket.resume_read();
+        }
+        state.paused = pause;
+    }
+
+    fn acquire(self: &Arc<Self>) -> InFlightGuard {
+        let mut state = self.state.lock().unwrap();
+        state.handlers += 1;
+        self.update(&mut state);
+        InFlightGuard(self.clone())
+    }
+
+    fn await_ack(self: &Arc<Self>) -> AckGuard {
+        let mut state = self.state.lock().unwrap();
+        state.acks += 1;
+        self.update(&mut state);
+        AckGuard(self.clone())
+    }
+}
+
+/// Releases an in-flight handler when dropped, even if it panicked or was cancelled
+struct InFlightGuard(Arc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.handlers -= 1;
+        self.0.update(&mut state);
+    }
+}
+
+/// Keeps reading from the transport while an acknowledgement is awaited, released when dropped
+#[derive(Debug)]
+pub(crate) struct AckGuard(Arc<InFlight>);
+
+impl Drop for AckGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.acks -= 1;
+        self.0.update(&mut state);
+    }
+}
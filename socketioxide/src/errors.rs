@@ -1,6 +1,10 @@
 use engineioxide::{sid::Sid, socket::DisconnectReason as EIoDisconnectReason};
+use serde_json::Value;
 use std::fmt::{Debug, Display};
-use tokio::{sync::mpsc::error::TrySendError, time::error::Elapsed};
+use tokio::{
+    sync::mpsc::error::{SendTimeoutError, TrySendError},
+    time::error::Elapsed,
+};
 
 /// Error type for socketio
 #[derive(thiserror::Error, Debug)]
@@ -26,6 +30,59 @@ pub enum Error {
 
 pub(crate) struct ConnectFail;
 
+/// A structured error returned by a [`ConnectMiddleware`](crate::handler::ConnectMiddleware) to refuse a connection.
+///
+/// The client receives a `connect_error` packet with the message and the optional data,
+/// exposed as `err.message` and `err.data` by the socket.io client.
+/// Any other error type is sent with its [`Display`] implementation as message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectError {
+    message: String,
+    data: Option<Value>,
+}
+
+impl ConnectError {
+    /// Creates a new [`ConnectError`] with the given message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Attaches additional data to the error, sent in the `data` field of the `connect_error` packet
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// The message of the error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The additional data of the error, if any
+    pub fn data(&self) -> Option<&Value> {
+        self.data.as_ref()
+    }
+
+    /// Converts the error returned by a middleware, keeping the data if it is already a [`ConnectError`]
+    pub(crate) fn from_middleware<E: Display + 'static>(err: E) -> Self {
+        let mut err = Some(err);
+        match (&mut err as &mut dyn std::any::Any).downcast_mut::<Option<ConnectError>>() {
+            Some(err) => err.take().unwrap(),
+            None => Self::new(err.unwrap().to_string()),
+        }
+    }
+}
+
+impl Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+impl std::error::Error for ConnectError {}
+
 /// Error type for ack operations.
 #[derive(thiserror::Error, Debug)]
 pub enum AckError<T> {
@@ -74,7 +131,7 @@ pub enum SendError<T> {
 }
 
 /// Error type when using the underlying engine.io socket
-#[derive(thiserror::Error)]
+#[derive(thiserror::Error, Clone)]
 pub enum SocketError<T> {
     /// The socket channel is full.
     /// You might need to increase the channel size with the [`SocketIoBuilder::max_buffer_size`] method.
@@ -111,6 +168,22 @@ pub enum DisconnectError {
     Adapter(#[from] AdapterError),
 }
 
+/// Error yielded by a [`BinStream`](crate::extract::BinStream) when the binary attachments
+/// of an event cannot be fully received.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BinStreamError {
+    /// The attachments exceed the limit set with [`SocketIoBuilder::max_bin_stream_size`].
+    /// The remaining attachments of the event are discarded.
+    ///
+    /// [`SocketIoBuilder::max_bin_stream_size`]: crate::SocketIoBuilder#method.max_bin_stream_size
+    #[error("binary stream size limit exceeded")]
+    LimitExceeded,
+
+    /// The socket was closed before all the attachments were received.
+    #[error("socket closed before receiving all the attachments")]
+    Closed,
+}
+
 /// Error type for the [`Adapter`](crate::adapter::Adapter) trait.
 #[derive(Debug, thiserror::Error)]
 pub struct AdapterError(#[from] pub Box<dyn std::error::Error + Send + Sync>);
@@ -128,6 +201,14 @@ impl SocketError<()> {
         }
     }
 }
+impl<T> SocketError<T> {
+    pub(crate) fn without_value(self) -> SocketError<()> {
+        match self {
+            Self::InternalChannelFull(_) => SocketError::InternalChannelFull(()),
+            Self::Closed(_) => SocketError::Closed(()),
+        }
+    }
+}
 impl<T> From<TrySendError<T>> for SocketError<()> {
     fn from(value: TrySendError<T>) -> Self {
         match value {
@@ -136,6 +217,14 @@ impl<T> From<TrySendError<T>> for SocketError<()> {
         }
     }
 }
+impl<T> From<SendTimeoutError<T>> for SocketError<()> {
+    fn from(value: SendTimeoutError<T>) -> Self {
+        match value {
+            SendTimeoutError::Timeout(_) => Self::InternalChannelFull(()),
+            SendTimeoutError::Closed(_) => Self::Closed(()),
+        }
+    }
+}
 
 impl From<Vec<SocketError<()>>> for BroadcastError {
     /// Converts a vector of `SendError` into a `BroadcastError`.
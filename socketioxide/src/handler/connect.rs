@@ -18,11 +18,18 @@
 //! Middlewares can be sync or async and can be chained.
 //! They are defined with the [`ConnectMiddleware`] trait which is automatically implemented for any
 //! closure with up to 16 arguments with the following signature:
-//! * `FnOnce(*args) -> Result<(), E> where E: Display`
-//! * `async FnOnce(*args) -> Result<(), E> where E: Display`
+//! * `FnOnce(*args) -> Result<T, E> where T: Clone, E: Display`
+//! * `async FnOnce(*args) -> Result<T, E> where T: Clone, E: Display`
 //!
 //! Arguments must implement the [`FromConnectParts`] trait in the exact same way than handlers.
 //!
+//! The value returned by a middleware is kept until the connect handler is called.
+//! The next middlewares and the handler can retrieve it with the
+//! [`MiddlewareValue`](super::extract::MiddlewareValue) extractor.
+//!
+//! If a middleware returns a [`ConnectError`], its message and data are sent to the client
+//! in the `connect_error` packet. Any other error is sent with its [`Display`](std::fmt::Display) message.
+//!
 //! ## Example with sync closures
 //! ```rust
 //! # use socketioxide::SocketIo;
@@ -111,20 +118,54 @@
 //! let (_, io) = SocketIo::new_layer();
 //! io.ns("/", handler.with(middleware).with(other_middleware));
 //! ```
+//!
+//! ## Global middlewares
+//! Middlewares registered with [`SocketIo::use_middleware`](crate::SocketIo::use_middleware) are called for
+//! every namespace connection, before the middlewares of the namespace.
+//!
+//! ## Example with middleware values and structured errors
+//!
+//! ```rust
+//! # use socketioxide::handler::ConnectHandler;
+//! # use socketioxide::extract::*;
+//! # use socketioxide::{SocketIo, ConnectError};
+//! # use serde_json::json;
+//! #[derive(Clone)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! fn auth(Data(token): Data<String>) -> Result<User, ConnectError> {
+//!     match token.as_str() {
+//!         "secret" => Ok(User { name: "admin".into() }),
+//!         _ => Err(ConnectError::new("unauthorized").with_data(json!({ "retry": false }))),
+//!     }
+//! }
+//!
+//! fn handler(s: SocketRef, MiddlewareValue(user): MiddlewareValue<User>) {
+//!     println!("{} connected with id: {}", user.name, s.id);
+//! }
+//!
+//! let (_, io) = SocketIo::new_layer();
+//! io.ns("/", handler.with(auth));
+//! ```
 
 use std::pin::Pin;
 use std::sync::Arc;
 
 use futures_core::Future;
 
-use crate::{adapter::Adapter, socket::Socket};
+use crate::{adapter::Adapter, errors::ConnectError, socket::Socket};
 
 use super::MakeErasedHandler;
 
 /// A Type Erased [`ConnectHandler`] so it can be stored in a HashMap
 pub(crate) type BoxedConnectHandler<A> = Box<dyn ErasedConnectHandler<A>>;
 
-type MiddlewareRes = Result<(), Box<dyn std::fmt::Display + Send>>;
+/// A Type Erased [`ConnectMiddleware`] shared by all the namespaces
+pub(crate) type BoxedConnectMiddleware<A> = Arc<dyn ErasedConnectMiddleware<A>>;
+
+type MiddlewareRes = Result<(), ConnectError>;
 type MiddlewareResFut<'a> = Pin<Box<dyn Future<Output = MiddlewareRes> + Send + 'a>>;
 
 pub(crate) trait ErasedConnectHandler<A: Adapter>: Send + Sync + 'static {
@@ -153,7 +194,7 @@ pub trait FromConnectParts<A: Adapter>: Sized {
 
 /// Define a middleware for the connect event.
 /// It is implemented for closures with up to 16 arguments.
-/// They must implement the [`FromConnectParts`] trait and return `Result<(), E> where E: Display`.
+/// They must implement the [`FromConnectParts`] trait and return `Result<T, E> where T: Clone, E: Display`.
 ///
 /// * See the [`connect`](super::connect) module doc for more details on connect middlewares.
 /// * See the [`extract`](super::extract) module doc for more details on available extractors.
@@ -273,6 +314,36 @@ where
     }
 }
 
+pub(crate) trait ErasedConnectMiddleware<A: Adapter>: Send + Sync + 'static {
+    fn call<'a>(&'a self, s: Arc<Socket<A>>, auth: &'a Option<String>) -> MiddlewareResFut<'a>;
+}
+
+impl<A: Adapter> std::fmt::Debug for dyn ErasedConnectMiddleware<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ConnectMiddleware")
+    }
+}
+
+impl<A: Adapter, T, M> MakeErasedHandler<M, A, T>
+where
+    M: ConnectMiddleware<A, T> + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    pub fn new_middleware_boxed(inner: M) -> BoxedConnectMiddleware<A> {
+        Arc::new(MakeErasedHandler::new(inner))
+    }
+}
+
+impl<A: Adapter, T, M> ErasedConnectMiddleware<A> for MakeErasedHandler<M, A, T>
+where
+    M: ConnectMiddleware<A, T> + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    fn call<'a>(&'a self, s: Arc<Socket<A>>, auth: &'a Option<String>) -> MiddlewareResFut<'a> {
+        Box::pin(self.handler.call(s, auth))
+    }
+}
+
 impl<A: Adapter, T, H> ErasedConnectHandler<A> for MakeErasedHandler<H, A, T>
 where
     H: ConnectHandler<A, T> + Send + Sync + 'static,
@@ -386,7 +457,7 @@ macro_rules! impl_handler_async {
                 )*
 
                 let fut = (self.clone())($($ty,)*);
-                tokio::spawn(fut);
+                s.spawn_tracked(fut);
 
             }
         }
@@ -427,11 +498,12 @@ macro_rules! impl_middleware_async {
         [$($ty:ident),*]
     ) => {
         #[allow(non_snake_case, unused)]
-        impl<A, F, Fut, E, $($ty,)*> ConnectMiddleware<A, (private::Async, $($ty,)*)> for F
+        impl<A, F, Fut, R, E, $($ty,)*> ConnectMiddleware<A, (private::Async, $($ty,)*)> for F
         where
             F: FnOnce($($ty,)*) -> Fut + Send + Sync + Clone + 'static,
-            Fut: Future<Output = Result<(), E>> + Send + 'static,
+            Fut: Future<Output = Result<R, E>> + Send + 'static,
             A: Adapter,
+            R: Clone + Send + Sync + 'static,
             E: std::fmt::Display + Send + 'static,
             $( $ty: FromConnectParts<A> + Send, )*
         {
@@ -442,18 +514,21 @@ macro_rules! impl_middleware_async {
                         Err(e) => {
                             #[cfg(feature = "tracing")]
                             tracing::error!("Error while extracting data: {}", e);
-                            return Err(Box::new(e) as _);
+                            return Err(ConnectError::new(e.to_string()));
                         },
                     };
                 )*
 
-                let res = (self.clone())($($ty,)*).await;
-                if let Err(e) = res {
-                    #[cfg(feature = "tracing")]
-                    tracing::trace!("middleware returned error: {}", e);
-                    Err(Box::new(e) as _)
-                } else {
-                    Ok(())
+                match (self.clone())($($ty,)*).await {
+                    Ok(value) => {
+                        s.insert_middleware_value(value);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::trace!("middleware returned error: {}", e);
+                        Err(ConnectError::from_middleware(e))
+                    }
                 }
             }
         }
@@ -465,10 +540,11 @@ macro_rules! impl_middleware {
         [$($ty:ident),*]
     ) => {
         #[allow(non_snake_case, unused)]
-        impl<A, F, E, $($ty,)*> ConnectMiddleware<A, (private::Sync, $($ty,)*)> for F
+        impl<A, F, R, E, $($ty,)*> ConnectMiddleware<A, (private::Sync, $($ty,)*)> for F
         where
-            F: FnOnce($($ty,)*) -> Result<(), E> + Send + Sync + Clone + 'static,
+            F: FnOnce($($ty,)*) -> Result<R, E> + Send + Sync + Clone + 'static,
             A: Adapter,
+            R: Clone + Send + Sync + 'static,
             E: std::fmt::Display + Send + 'static,
             $( $ty: FromConnectParts<A> + Send, )*
         {
@@ -479,18 +555,21 @@ macro_rules! impl_middleware {
                         Err(e) => {
                             #[cfg(feature = "tracing")]
                             tracing::error!("Error while extracting data: {}", e);
-                            return Err(Box::new(e) as _);
+                            return Err(ConnectError::new(e.to_string()));
                         },
                     };
                 )*
 
-                let res = (self.clone())($($ty,)*);
-                if let Err(e) = res {
-                    #[cfg(feature = "tracing")]
-                    tracing::trace!("middleware returned error: {}", e);
-                    Err(Box::new(e) as _)
-                } else {
-                    Ok(())
+                match (self.clone())($($ty,)*) {
+                    Ok(value) => {
+                        s.insert_middleware_value(value);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::trace!("middleware returned error: {}", e);
+                        Err(ConnectError::from_middleware(e))
+                    }
                 }
             }
         }
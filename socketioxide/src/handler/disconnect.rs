@@ -146,7 +146,7 @@ macro_rules! impl_handler_async {
         {
             fn call(&self, s: Arc<Socket<A>>, reason: DisconnectReason) {
                 $(
-                    let $ty = match $ty::from_disconnect_parts(&s, reason) {
+                    let $ty = match $ty::from_disconnect_parts(&s, reason.clone()) {
                         Ok(v) => v,
                         Err(_e) => {
                             #[cfg(feature = "tracing")]
@@ -157,7 +157,7 @@ macro_rules! impl_handler_async {
                 )*
 
                 let fut = (self.clone())($($ty,)*);
-                tokio::spawn(fut);
+                s.spawn_tracked(fut);
 
             }
         }
@@ -177,7 +177,7 @@ macro_rules! impl_handler {
         {
             fn call(&self, s: Arc<Socket<A>>, reason: DisconnectReason) {
                 $(
-                    let $ty = match $ty::from_disconnect_parts(&s, reason) {
+                    let $ty = match $ty::from_disconnect_parts(&s, reason.clone()) {
                         Ok(v) => v,
                         Err(_e) => {
                             #[cfg(feature = "tracing")]
@@ -0,0 +1,112 @@
+//! [`ErrorHandler`] trait and types used to handle the errors that cannot be returned to the user code.
+//!
+//! Some errors happen outside of any user code and would otherwise only be logged with the `tracing` feature:
+//! * An extractor failed and the message handler was not called (e.g. a [`Data`](super::extract::Data) deserialization error).
+//! * The value returned by a message handler could not be sent as an acknowledgement because the socket is closed
+//!   or its buffer is full.
+//! * The adapter failed to remove a socket that was disconnected.
+//! * A [`tower`] layer wrapping the message handlers returned an error (e.g. a timeout).
+//!
+//! These errors are reported as a [`HandlerError`] to the hook set with [`SocketIo::ns_on_error`](crate::SocketIo::ns_on_error)
+//! for the namespace of the socket, or to the global hook set with [`SocketIoBuilder::on_error`](crate::SocketIoBuilder::on_error).
+//!
+//! The hook can return an [`ErrorReply`] to answer the client.
+//!
+//! ## Example
+//! ```rust
+//! # use socketioxide::{SocketIo, extract::*, handler::error::{ErrorCause, ErrorReply, HandlerError}};
+//! # use serde_json::json;
+//! let (svc, io) = SocketIo::builder()
+//!     .on_error(|err: &HandlerError| {
+//!         println!("error on socket {} ({}): {}", err.sid, err.ns, err.cause);
+//!         match err.cause {
+//!             // Tell the client that its data is invalid
+//!             ErrorCause::Extract(_) => Some(ErrorReply::Ack(json!({ "error": "invalid data" }))),
+//!             _ => None,
+//!         }
+//!     })
+//!     .build_svc();
+//!
+//! io.ns("/", |s: SocketRef| {
+//!     s.on("message", |s: SocketRef, Data::<String>(msg)| {
+//!         s.broadcast().emit("message", msg).ok();
+//!     });
+//! });
+//! ```
+use std::{borrow::Cow, fmt::Debug};
+
+use serde_json::Value;
+
+use crate::{errors::AdapterError, socket::Sid, SocketError};
+
+/// An error that happened while handling an event of a socket,
+/// reported to the [`ErrorHandler`] of its namespace.
+#[derive(Debug, thiserror::Error)]
+#[error("{cause}")]
+pub struct HandlerError {
+    /// The id of the socket
+    pub sid: Sid,
+    /// The namespace of the socket
+    pub ns: Cow<'static, str>,
+    /// The name of the event being handled or emitted, if any
+    pub event: Option<String>,
+    /// The ack id of the event received, if the client expects an acknowledgement
+    pub ack_id: Option<i64>,
+    /// The cause of the error
+    pub cause: ErrorCause,
+}
+
+/// The cause of a [`HandlerError`].
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorCause {
+    /// An extractor failed, the message handler was not called
+    #[error("error while extracting data: {0}")]
+    Extract(Box<dyn std::error::Error + Send + Sync>),
+
+    /// An event or an ack could not be sent to the client
+    #[error("error sending data through the engine.io socket: {0:?}")]
+    Send(SocketError<()>),
+
+    /// An error returned by the adapter that could not be returned to the user code
+    #[error("adapter error: {0}")]
+    Adapter(#[from] AdapterError),
+
+    /// An error returned by the [`tower`] layers wrapping the message handlers,
+    /// see the [`service`](super::service) module
+    #[error("event service error: {0}")]
+    Service(tower::BoxError),
+}
+
+/// A reply sent to the client by an [`ErrorHandler`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorReply {
+    /// Answers the event with an acknowledgement containing the given data.
+    /// It is ignored if the client did not expect an acknowledgement.
+    Ack(Value),
+    /// Emits an event with the given data to the client
+    Emit(Cow<'static, str>, Value),
+}
+
+/// A hook called with the errors of the sockets of a namespace.
+///
+/// It is implemented for closures taking a [`&HandlerError`](HandlerError) and returning an optional [`ErrorReply`].
+pub trait ErrorHandler: Send + Sync + 'static {
+    /// Handles the error and returns the reply to send to the client, if any
+    fn call(&self, err: &HandlerError) -> Option<ErrorReply>;
+}
+
+impl<F> ErrorHandler for F
+where
+    F: Fn(&HandlerError) -> Option<ErrorReply> + Send + Sync + 'static,
+{
+    #[inline(always)]
+    fn call(&self, err: &HandlerError) -> Option<ErrorReply> {
+        self(err)
+    }
+}
+
+impl Debug for dyn ErrorHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ErrorHandler")
+    }
+}
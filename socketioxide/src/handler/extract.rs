@@ -12,13 +12,20 @@
 //!     - for [`ConnectHandler`](super::ConnectHandler) and [`ConnectMiddleware`](super::ConnectMiddleware):
 //! extracts and deserialize to json the auth data
 //!     - for [`MessageHandler`](super::MessageHandler): extracts and deserialize to json the message data
+//! * [`DataArgs`] and [`TryDataArgs`]: extracts and deserialize all the arguments of the message as a sequence (e.g. a tuple)
+//! * [`Arg`]: extracts and deserialize the argument of the message at a given position
+//! * [`RawData`]: extracts the raw json payload of the message, to deserialize it lazily or to borrowed types
 //! * [`SocketRef`]: extracts a reference to the [`Socket`]
 //! * [`Bin`]: extract a binary payload for a given message. Because it consumes the event it should be the last argument
+//! * [`BinStream`]: stream the binary payloads of a message as they are received. It should also be the last argument
 //! * [`AckSender`]: Can be used to send an ack response to the current message event
 //! * [`ProtocolVersion`](crate::ProtocolVersion): extracts the protocol version
 //! * [`TransportType`](crate::TransportType): extracts the transport type
+//! * [`SocketStats`](crate::SocketStats): extracts a snapshot of the connection statistics
 //! * [`DisconnectReason`]: extracts the reason of the disconnection
+//! * [`CancellationToken`]: extracts a token cancelled when the socket is disconnected
 //! * [`State`]: extracts a reference to a state previously set with [`SocketIoBuilder::with_state`](crate::io::SocketIoBuilder).
+//! * [`MiddlewareValue`]: extracts a value returned by a previous [`ConnectMiddleware`](super::ConnectMiddleware)
 //!
 //! ### You can also implement your own Extractor with the [`FromConnectParts`], [`FromMessageParts`] and [`FromDisconnectParts`] traits
 //! When implementing these traits, if you clone the [`Arc<Socket>`] make sure that it is dropped at least when the socket is disconnected.
@@ -29,6 +36,7 @@
 //! # use bytes::Bytes;
 //! # use socketioxide::handler::{FromConnectParts, FromMessageParts};
 //! # use socketioxide::adapter::Adapter;
+//! # use socketioxide::packet::RawPayload;
 //! # use socketioxide::socket::Socket;
 //! # use std::sync::Arc;
 //! # use std::convert::Infallible;
@@ -66,7 +74,7 @@
 //!
 //!     fn from_message_parts(
 //!         s: &Arc<Socket<A>>,
-//!         _: &mut serde_json::Value,
+//!         _: &mut RawPayload,
 //!         _: &mut Vec<Bytes>,
 //!         _: &Option<i64>,
 //!     ) -> Result<Self, UserIdNotFound> {
@@ -87,6 +95,7 @@
 //! let (svc, io) = SocketIo::new_svc();
 //! io.ns("/", handler);
 //! // Use the service with your favorite http server
+use std::borrow::Cow;
 use std::convert::Infallible;
 use std::sync::Arc;
 
@@ -97,26 +106,17 @@ use crate::errors::{DisconnectError, SendError};
 use crate::socket::DisconnectReason;
 use crate::{
     adapter::{Adapter, LocalAdapter},
-    packet::Packet,
+    packet::{EncodedPacket, RawPayload},
     socket::Socket,
 };
 use bytes::Bytes;
 use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value;
 
+pub use crate::bin_stream::BinStream;
 #[cfg(feature = "state")]
 #[cfg_attr(docsrs, doc(cfg(feature = "state")))]
 pub use state_extract::*;
-
-/// Utility function to unwrap an array with a single element
-fn upwrap_array(v: &mut Value) {
-    match v {
-        Value::Array(vec) if vec.len() == 1 => {
-            *v = vec.pop().unwrap();
-        }
-        _ => (),
-    }
-}
+pub use tokio_util::sync::CancellationToken;
 
 /// An Extractor that returns the serialized auth data without checking errors.
 /// If a deserialization error occurs, the [`ConnectHandler`](super::ConnectHandler) won't be called
@@ -143,12 +143,11 @@ where
     type Error = serde_json::Error;
     fn from_message_parts(
         _: &Arc<Socket<A>>,
-        v: &mut serde_json::Value,
+        v: &mut RawPayload,
         _: &mut Vec<Bytes>,
         _: &Option<i64>,
     ) -> Result<Self, Self::Error> {
-        upwrap_array(v);
-        serde_json::from_value(v.clone()).map(Data)
+        v.deserialize().map(Data)
     }
 }
 
@@ -177,12 +176,114 @@ where
     type Error = Infallible;
     fn from_message_parts(
         _: &Arc<Socket<A>>,
-        v: &mut serde_json::Value,
+        v: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Infallible> {
+        Ok(TryData(v.deserialize()))
+    }
+}
+
+/// An Extractor that returns all the deserialized arguments of the event as a sequence (e.g. a tuple).
+///
+/// Unlike [`Data`], a single argument is not unwrapped:
+/// a client calling `socket.emit("move", x, y, meta)` can be handled with `DataArgs<(i32, i32, Meta)>`
+/// and `socket.emit("list", [1, 2])` with `DataArgs<(Vec<i32>,)>`.
+/// If a deserialization error occurs, the [`MessageHandler`](super::MessageHandler) won't be called
+/// and an error log will be print if the `tracing` feature is enabled.
+pub struct DataArgs<T: DeserializeOwned>(pub T);
+impl<T, A> FromMessageParts<A> for DataArgs<T>
+where
+    T: DeserializeOwned,
+    A: Adapter,
+{
+    type Error = serde_json::Error;
+    fn from_message_parts(
+        _: &Arc<Socket<A>>,
+        v: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Self::Error> {
+        v.deserialize_args().map(DataArgs)
+    }
+}
+
+/// An Extractor that returns all the deserialized arguments of the event as a sequence (e.g. a tuple)
+/// with a `Result` type in case of error. See [`DataArgs`].
+pub struct TryDataArgs<T: DeserializeOwned>(pub Result<T, serde_json::Error>);
+impl<T, A> FromMessageParts<A> for TryDataArgs<T>
+where
+    T: DeserializeOwned,
+    A: Adapter,
+{
+    type Error = Infallible;
+    fn from_message_parts(
+        _: &Arc<Socket<A>>,
+        v: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Infallible> {
+        Ok(TryDataArgs(v.deserialize_args()))
+    }
+}
+
+/// An Extractor that returns the deserialized argument of the event at the position `N`.
+/// If the argument is missing or if a deserialization error occurs,
+/// the [`MessageHandler`](super::MessageHandler) won't be called.
+/// ```rust
+/// # use socketioxide::{SocketIo, extract::*};
+/// let (_, io) = SocketIo::new_svc();
+/// io.ns("/", |s: SocketRef| {
+///     // Client side: `socket.emit("move", x, y)`
+///     s.on("move", |Arg(x): Arg<0, i32>, Arg(y): Arg<1, i32>| {
+///         println!("moving to {x}, {y}");
+///     });
+/// });
+/// ```
+pub struct Arg<const N: usize, T: DeserializeOwned>(pub T);
+impl<const N: usize, T, A> FromMessageParts<A> for Arg<N, T>
+where
+    T: DeserializeOwned,
+    A: Adapter,
+{
+    type Error = serde_json::Error;
+    fn from_message_parts(
+        _: &Arc<Socket<A>>,
+        v: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Self::Error> {
+        v.deserialize_arg(N).map(Arg)
+    }
+}
+
+/// An Extractor that returns the raw payload of the event, without deserializing it.
+///
+/// It can be used to forward the payload as is, to deserialize only some of its arguments
+/// or to deserialize it to borrowed types:
+/// ```rust
+/// # use socketioxide::{SocketIo, extract::*};
+/// # use serde_json::value::RawValue;
+/// let (_, io) = SocketIo::new_svc();
+/// io.ns("/", |s: SocketRef| {
+///     s.on("event", |RawData(payload)| {
+///         let args: Vec<&RawValue> = payload.args().unwrap();
+///         let data: Result<(&str, u32), _> = payload.deserialize();
+///     });
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct RawData(pub RawPayload);
+
+impl<A: Adapter> FromMessageParts<A> for RawData {
+    type Error = Infallible;
+    fn from_message_parts(
+        _: &Arc<Socket<A>>,
+        v: &mut RawPayload,
         _: &mut Vec<Bytes>,
         _: &Option<i64>,
     ) -> Result<Self, Infallible> {
-        upwrap_array(v);
-        Ok(TryData(serde_json::from_value(v.clone())))
+        Ok(RawData(v.clone()))
     }
 }
 /// An Extractor that returns a reference to a [`Socket`].
@@ -199,7 +300,7 @@ impl<A: Adapter> FromMessageParts<A> for SocketRef<A> {
     type Error = Infallible;
     fn from_message_parts(
         s: &Arc<Socket<A>>,
-        _: &mut serde_json::Value,
+        _: &mut RawPayload,
         _: &mut Vec<Bytes>,
         _: &Option<i64>,
     ) -> Result<Self, Infallible> {
@@ -247,6 +348,17 @@ impl<A: Adapter> SocketRef<A> {
     pub fn disconnect(self) -> Result<(), DisconnectError> {
         self.0.disconnect()
     }
+
+    /// Disconnect the socket from the current namespace with a custom reason,
+    /// see [`Socket::disconnect_with`] for more details.
+    #[inline(always)]
+    pub fn disconnect_with(
+        self,
+        code: u16,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Result<(), DisconnectError> {
+        self.0.disconnect_with(code, message)
+    }
 }
 
 /// An Extractor that returns the binary data of the message.
@@ -256,7 +368,7 @@ impl<A: Adapter> FromMessage<A> for Bin {
     type Error = Infallible;
     fn from_message(
         _: Arc<Socket<A>>,
-        _: serde_json::Value,
+        _: RawPayload,
         bin: Vec<Bytes>,
         _: Option<i64>,
     ) -> Result<Self, Infallible> {
@@ -264,6 +376,19 @@ impl<A: Adapter> FromMessage<A> for Bin {
     }
 }
 
+impl<A: Adapter> FromMessage<A> for BinStream {
+    type Error = Infallible;
+    const BIN_STREAM: bool = true;
+    fn from_message(
+        s: Arc<Socket<A>>,
+        _: RawPayload,
+        _: Vec<Bytes>,
+        _: Option<i64>,
+    ) -> Result<Self, Infallible> {
+        Ok(s.take_bin_stream())
+    }
+}
+
 /// An Extractor to send an ack response corresponding to the current event.
 /// If the client sent a normal message without expecting an ack, the ack callback will do nothing.
 #[derive(Debug)]
@@ -274,9 +399,10 @@ pub struct AckSender<A: Adapter = LocalAdapter> {
 }
 impl<A: Adapter> FromMessageParts<A> for AckSender<A> {
     type Error = Infallible;
+    const HANDLES_ACK: bool = true;
     fn from_message_parts(
         s: &Arc<Socket<A>>,
-        _: &mut serde_json::Value,
+        _: &mut RawPayload,
         _: &mut Vec<Bytes>,
         ack_id: &Option<i64>,
     ) -> Result<Self, Infallible> {
@@ -299,24 +425,16 @@ impl<A: Adapter> AckSender<A> {
     }
 
     /// Send the ack response to the client.
+    ///
+    /// Array-like data is sent as multiple arguments, use [`Args`](crate::packet::Args)
+    /// to send a single array argument or no argument at all.
     pub fn send<T: Serialize>(self, data: T) -> Result<(), SendError<T>> {
-        use crate::socket::PermitExt;
         if let Some(ack_id) = self.ack_id {
-            let permit = match self.socket.reserve() {
+            let permit = match self.socket.reserve_control() {
                 Ok(permit) => permit,
-                Err(e) => {
-                    #[cfg(feature = "tracing")]
-                    tracing::debug!("sending error during emit message: {e:?}");
-                    return Err(e.with_value(data).into());
-                }
-            };
-            let ns = self.socket.ns();
-            let data = serde_json::to_value(data)?;
-            let packet = if self.binary.is_empty() {
-                Packet::ack(ns, data, ack_id)
-            } else {
-                Packet::bin_ack(ns, data, self.binary, ack_id)
+                Err(e) => return Err(e.with_value(data).into()),
             };
+            let packet = EncodedPacket::ack(self.socket.ns(), &data, self.binary, ack_id)?;
             permit.send(packet);
             Ok(())
         } else {
@@ -335,7 +453,7 @@ impl<A: Adapter> FromMessageParts<A> for crate::ProtocolVersion {
     type Error = Infallible;
     fn from_message_parts(
         s: &Arc<Socket<A>>,
-        _: &mut serde_json::Value,
+        _: &mut RawPayload,
         _: &mut Vec<Bytes>,
         _: &Option<i64>,
     ) -> Result<Self, Infallible> {
@@ -359,7 +477,7 @@ impl<A: Adapter> FromMessageParts<A> for crate::TransportType {
     type Error = Infallible;
     fn from_message_parts(
         s: &Arc<Socket<A>>,
-        _: &mut serde_json::Value,
+        _: &mut RawPayload,
         _: &mut Vec<Bytes>,
         _: &Option<i64>,
     ) -> Result<Self, Infallible> {
@@ -373,6 +491,54 @@ impl<A: Adapter> FromDisconnectParts<A> for crate::TransportType {
     }
 }
 
+impl<A: Adapter> FromConnectParts<A> for crate::SocketStats {
+    type Error = Infallible;
+    fn from_connect_parts(s: &Arc<Socket<A>>, _: &Option<String>) -> Result<Self, Infallible> {
+        Ok(s.stats())
+    }
+}
+impl<A: Adapter> FromMessageParts<A> for crate::SocketStats {
+    type Error = Infallible;
+    fn from_message_parts(
+        s: &Arc<Socket<A>>,
+        _: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Infallible> {
+        Ok(s.stats())
+    }
+}
+impl<A: Adapter> FromDisconnectParts<A> for crate::SocketStats {
+    type Error = Infallible;
+    fn from_disconnect_parts(s: &Arc<Socket<A>>, _: DisconnectReason) -> Result<Self, Infallible> {
+        Ok(s.stats())
+    }
+}
+
+impl<A: Adapter> FromConnectParts<A> for CancellationToken {
+    type Error = Infallible;
+    fn from_connect_parts(s: &Arc<Socket<A>>, _: &Option<String>) -> Result<Self, Infallible> {
+        Ok(s.cancellation_token())
+    }
+}
+impl<A: Adapter> FromMessageParts<A> for CancellationToken {
+    type Error = Infallible;
+    fn from_message_parts(
+        s: &Arc<Socket<A>>,
+        _: &mut RawPayload,
+        _: &mut Vec<Bytes>,
+        _: &Option<i64>,
+    ) -> Result<Self, Infallible> {
+        Ok(s.cancellation_token())
+    }
+}
+impl<A: Adapter> FromDisconnectParts<A> for CancellationToken {
+    type Error = Infallible;
+    fn from_disconnect_parts(s: &Arc<Socket<A>>, _: DisconnectReason) -> Result<Self, Infallible> {
+        Ok(s.cancellation_token())
+    }
+}
+
 impl<A: Adapter> FromDisconnectParts<A> for DisconnectReason {
     type Error = Infallible;
     fn from_disconnect_parts(
@@ -383,6 +549,51 @@ impl<A: Adapter> FromDisconnectParts<A> for DisconnectReason {
     }
 }
 
+/// An Extractor that returns a clone of the value returned by a previous [`ConnectMiddleware`](super::ConnectMiddleware).
+/// It is only available to the connect middlewares and the [`ConnectHandler`](super::ConnectHandler).
+///
+/// If no middleware returned a value of this type, the middleware chain stops and a `connect_error` packet is sent.
+///
+/// ### Example
+/// ```
+/// # use socketioxide::{SocketIo, handler::ConnectHandler, extract::{SocketRef, MiddlewareValue}};
+/// #[derive(Clone)]
+/// struct User(String);
+///
+/// let (_, io) = SocketIo::new_svc();
+/// let auth = |s: SocketRef| Ok::<_, std::convert::Infallible>(User(s.id.to_string()));
+/// let handler = |MiddlewareValue(user): MiddlewareValue<User>| {
+///     println!("User connected: {}", user.0);
+/// };
+/// io.ns("/", handler.with(auth));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MiddlewareValue<T>(pub T);
+
+/// No middleware returned a value of the requested type and therefore the handler won't be called.
+#[derive(Debug, thiserror::Error)]
+#[error("Middleware value not found")]
+pub struct MiddlewareValueNotFound;
+
+impl<T> std::ops::Deref for MiddlewareValue<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<A: Adapter, T: Clone + Send + Sync + 'static> FromConnectParts<A> for MiddlewareValue<T> {
+    type Error = MiddlewareValueNotFound;
+    fn from_connect_parts(
+        s: &Arc<Socket<A>>,
+        _: &Option<String>,
+    ) -> Result<Self, MiddlewareValueNotFound> {
+        s.middleware_value::<T>()
+            .map(MiddlewareValue)
+            .ok_or(MiddlewareValueNotFound)
+    }
+}
+
 #[cfg(feature = "state")]
 mod state_extract {
     use super::*;
@@ -454,7 +665,7 @@ mod state_extract {
         type Error = StateNotFound;
         fn from_message_parts(
             _: &Arc<Socket<A>>,
-            _: &mut serde_json::Value,
+            _: &mut RawPayload,
             _: &mut Vec<Bytes>,
             _: &Option<i64>,
         ) -> Result<Self, StateNotFound> {
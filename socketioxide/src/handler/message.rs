@@ -9,6 +9,9 @@
 //!
 //! Handlers can be _optionally_ async.
 //!
+//! The value returned by a handler is sent back as the acknowledgement of the event if the client requested one,
+//! see [`IntoAck`] for the supported return types.
+//!
 //! ## Example with sync closures
 //! ```rust
 //! # use socketioxide::SocketIo;
@@ -73,12 +76,20 @@
 //! ```
 use std::sync::Arc;
 
+use crate::packet::RawPayload;
 use bytes::Bytes;
 use futures_core::Future;
+use futures_util::future::BoxFuture;
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::adapter::Adapter;
 use crate::socket::Socket;
+use crate::SendError;
+
+use super::extract::AckSender;
+
+use super::error::ErrorCause;
 
 use super::MakeErasedHandler;
 
@@ -86,7 +97,14 @@ use super::MakeErasedHandler;
 pub(crate) type BoxedMessageHandler<A> = Box<dyn ErasedMessageHandler<A>>;
 
 pub(crate) trait ErasedMessageHandler<A: Adapter>: Send + Sync + 'static {
-    fn call(&self, s: Arc<Socket<A>>, v: Value, p: Vec<Bytes>, ack_id: Option<i64>);
+    fn call(
+        &self,
+        s: Arc<Socket<A>>,
+        v: RawPayload,
+        p: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause>;
+    fn is_bin_stream(&self) -> bool;
 }
 
 /// Define a handler for the connect event.
@@ -101,8 +119,25 @@ pub(crate) trait ErasedMessageHandler<A: Adapter>: Send + Sync + 'static {
     )
 )]
 pub trait MessageHandler<A: Adapter, T>: Send + Sync + 'static {
-    /// Call the handler with the given arguments
-    fn call(&self, s: Arc<Socket<A>>, v: Value, p: Vec<Bytes>, ack_id: Option<i64>);
+    /// Call the handler with the given arguments.
+    ///
+    /// Sync handlers are run to completion while async handlers return their future, which must be polled
+    /// until the handler has completed.
+    /// It returns an error if an extractor failed, in which case the handler is not called.
+    fn call(
+        &self,
+        s: Arc<Socket<A>>,
+        v: RawPayload,
+        p: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause>;
+
+    /// Returns true if the handler consumes the binary attachments as a stream,
+    /// in which case it is called before the attachments are received
+    #[doc(hidden)]
+    fn is_bin_stream(&self) -> bool {
+        false
+    }
 
     #[doc(hidden)]
     fn phantom(&self) -> std::marker::PhantomData<T> {
@@ -128,8 +163,19 @@ where
     A: Adapter,
 {
     #[inline(always)]
-    fn call(&self, s: Arc<Socket<A>>, v: Value, p: Vec<Bytes>, ack_id: Option<i64>) {
-        self.handler.call(s, v, p, ack_id);
+    fn call(
+        &self,
+        s: Arc<Socket<A>>,
+        v: RawPayload,
+        p: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause> {
+        self.handler.call(s, v, p, ack_id)
+    }
+
+    #[inline(always)]
+    fn is_bin_stream(&self) -> bool {
+        self.handler.is_bin_stream()
     }
 }
 
@@ -147,7 +193,8 @@ mod private {
 }
 
 /// A trait used to extract arguments from the message event.
-/// The `Result` associated type is used to return an error if the extraction fails, in this case the handler is not called.
+/// The `Result` associated type is used to return an error if the extraction fails, in this case the handler is not called
+/// and the error is reported to the [`ErrorHandler`](super::ErrorHandler) of the namespace.
 ///
 /// * See the [`message`](super::message) module doc for more details on message handler.
 /// * See the [`extract`](super::extract) module doc for more details on available extractors.
@@ -159,13 +206,17 @@ mod private {
 )]
 pub trait FromMessageParts<A: Adapter>: Sized {
     /// The error type returned by the extractor
-    type Error: std::error::Error + 'static;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Set to true by extractors that take over the acknowledgement of the event
+    #[doc(hidden)]
+    const HANDLES_ACK: bool = false;
 
     /// Extract the arguments from the message event.
     /// If it fails, the handler is not called.
     fn from_message_parts(
         s: &Arc<Socket<A>>,
-        v: &mut Value,
+        v: &mut RawPayload,
         p: &mut Vec<Bytes>,
         ack_id: &Option<i64>,
     ) -> Result<Self, Self::Error>;
@@ -184,13 +235,21 @@ pub trait FromMessageParts<A: Adapter>: Sized {
 )]
 pub trait FromMessage<A: Adapter, M = private::ViaRequest>: Sized {
     /// The error type returned by the extractor
-    type Error: std::error::Error + 'static;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Set to true by extractors that consume the binary attachments as a stream
+    #[doc(hidden)]
+    const BIN_STREAM: bool = false;
+
+    /// Set to true by extractors that take over the acknowledgement of the event
+    #[doc(hidden)]
+    const HANDLES_ACK: bool = false;
 
     /// Extract the arguments from the message event.
     /// If it fails, the handler is not called
     fn from_message(
         s: Arc<Socket<A>>,
-        v: Value,
+        v: RawPayload,
         p: Vec<Bytes>,
         ack_id: Option<i64>,
     ) -> Result<Self, Self::Error>;
@@ -203,9 +262,10 @@ where
     A: Adapter,
 {
     type Error = T::Error;
+    const HANDLES_ACK: bool = T::HANDLES_ACK;
     fn from_message(
         s: Arc<Socket<A>>,
-        mut v: Value,
+        mut v: RawPayload,
         mut p: Vec<Bytes>,
         ack_id: Option<i64>,
     ) -> Result<Self, Self::Error> {
@@ -213,27 +273,131 @@ where
     }
 }
 
+/// A value returned by a [`MessageHandler`], sent back to the client as the acknowledgement of the event
+/// if the client requested one.
+///
+/// It is implemented for:
+/// * `()`: nothing is returned.
+/// * [`Value`]: the value is sent as is.
+/// * `Result<T, E>` where `T` implements [`Serialize`] and `E` implements [`Display`](std::fmt::Display):
+///   the `Ok` value is sent as is and an error is sent as a `{ "error": "<message>" }` object.
+///
+/// If the client requested an acknowledgement but the handler returned nothing and did not take an
+/// [`AckSender`], a warning is logged and an error is sent so that the client does not wait forever.
+///
+/// ## Example
+/// ```rust
+/// # use socketioxide::{SocketIo, extract::*};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Deserialize, Serialize)]
+/// struct User {
+///     id: u64,
+///     name: String,
+/// }
+///
+/// async fn get_user(Data(id): Data<u64>) -> Result<User, std::io::Error> {
+///     Ok(User { id, name: "John".to_string() })
+/// }
+///
+/// let (svc, io) = SocketIo::new_svc();
+/// io.ns("/", |s: SocketRef| {
+///     s.on("get_user", get_user);
+/// });
+/// ```
+pub trait IntoAck {
+    /// Converts the value into the data of the acknowledgement, `None` if there is nothing to send
+    fn into_ack(self) -> Option<Value>;
+}
+
+impl IntoAck for () {
+    #[inline(always)]
+    fn into_ack(self) -> Option<Value> {
+        None
+    }
+}
+
+impl IntoAck for Value {
+    #[inline(always)]
+    fn into_ack(self) -> Option<Value> {
+        Some(self)
+    }
+}
+
+impl<T, E> IntoAck for Result<T, E>
+where
+    T: Serialize,
+    E: std::fmt::Display,
+{
+    fn into_ack(self) -> Option<Value> {
+        let res = match self {
+            Ok(data) => serde_json::to_value(data).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        Some(res.unwrap_or_else(ack_error))
+    }
+}
+
+fn ack_error(message: impl Into<String>) -> Value {
+    serde_json::json!({ "error": message.into() })
+}
+
+/// Sends the value returned by a handler as the acknowledgement of the event
+fn send_ack<A: Adapter>(s: Arc<Socket<A>>, ack_id: i64, res: impl IntoAck) {
+    let data = res.into_ack().unwrap_or_else(|| {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(?s.id, ack_id, "an acknowledgement was requested but the handler did not return any");
+        ack_error("no acknowledgement returned by the handler")
+    });
+    // The ack cannot be returned to the user code, sending errors are reported to the error handler
+    if let Err(SendError::Socket(e)) = AckSender::new(s.clone(), Some(ack_id)).send(data) {
+        s.report_error(None, Some(ack_id), ErrorCause::Send(e.without_value()));
+    }
+}
+
 /// Empty Async handler
-impl<A, F, Fut> MessageHandler<A, (private::Async,)> for F
+impl<A, F, Fut, R> MessageHandler<A, (private::Async,)> for F
 where
     F: FnOnce() -> Fut + Send + Sync + Clone + 'static,
-    Fut: Future<Output = ()> + Send + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: IntoAck + Send + 'static,
     A: Adapter,
 {
-    fn call(&self, _: Arc<Socket<A>>, _: Value, _: Vec<Bytes>, _: Option<i64>) {
+    fn call(
+        &self,
+        s: Arc<Socket<A>>,
+        _: RawPayload,
+        _: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause> {
         let fut = (self.clone())();
-        tokio::spawn(fut);
+        Ok(Some(match ack_id {
+            Some(ack_id) => Box::pin(async move { send_ack(s, ack_id, fut.await) }),
+            None => Box::pin(async move {
+                fut.await;
+            }),
+        }))
     }
 }
 
 /// Empty Sync handler
-impl<A, F> MessageHandler<A, (private::Sync,)> for F
+impl<A, F, R> MessageHandler<A, (private::Sync,)> for F
 where
-    F: FnOnce() + Send + Sync + Clone + 'static,
+    F: FnOnce() -> R + Send + Sync + Clone + 'static,
+    R: IntoAck,
     A: Adapter,
 {
-    fn call(&self, _: Arc<Socket<A>>, _: Value, _: Vec<Bytes>, _: Option<i64>) {
-        (self.clone())();
+    fn call(
+        &self,
+        s: Arc<Socket<A>>,
+        _: RawPayload,
+        _: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause> {
+        let res = (self.clone())();
+        if let Some(ack_id) = ack_id {
+            send_ack(s, ack_id, res);
+        }
+        Ok(None)
     }
 }
 
@@ -242,36 +406,34 @@ macro_rules! impl_async_handler {
         [$($ty:ident),*], $last:ident
     ) => {
         #[allow(non_snake_case, unused)]
-        impl<A, F, M, $($ty,)* $last, Fut> MessageHandler<A, (private::Async, M, $($ty,)* $last,)> for F
+        impl<A, F, M, $($ty,)* $last, Fut, R> MessageHandler<A, (private::Async, M, $($ty,)* $last,)> for F
         where
             F: FnOnce($($ty,)* $last,) -> Fut + Send + Sync + Clone + 'static,
-            Fut: Future<Output = ()> + Send + 'static,
+            Fut: Future<Output = R> + Send + 'static,
+            R: IntoAck + Send + 'static,
             A: Adapter,
             $( $ty: FromMessageParts<A> + Send, )*
             $last: FromMessage<A, M> + Send,
         {
-            fn call(&self, s: Arc<Socket<A>>, mut v: Value, mut p: Vec<Bytes>, ack_id: Option<i64>) {
+            fn call(&self, s: Arc<Socket<A>>, mut v: RawPayload, mut p: Vec<Bytes>, ack_id: Option<i64>) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause> {
+                let handles_ack = $( <$ty as FromMessageParts<A>>::HANDLES_ACK || )* <$last as FromMessage<A, M>>::HANDLES_ACK;
+                let ack = ack_id.filter(|_| !handles_ack).map(|ack_id| (s.clone(), ack_id));
                 $(
-                    let $ty = match $ty::from_message_parts(&s, &mut v, &mut p, &ack_id) {
-                        Ok(v) => v,
-                        Err(_e) => {
-                            #[cfg(feature = "tracing")]
-                            tracing::error!("Error while extracting data: {}", _e);
-                            return;
-                        },
-                    };
+                    let $ty = $ty::from_message_parts(&s, &mut v, &mut p, &ack_id)
+                        .map_err(|e| ErrorCause::Extract(Box::new(e)))?;
                 )*
-                let last = match $last::from_message(s, v, p, ack_id) {
-                    Ok(v) => v,
-                    Err(_e) => {
-                        #[cfg(feature = "tracing")]
-                        tracing::error!("Error while extracting data: {}", _e);
-                        return;
-                    },
-                };
+                let last = $last::from_message(s, v, p, ack_id)
+                    .map_err(|e| ErrorCause::Extract(Box::new(e)))?;
 
                 let fut = (self.clone())($($ty,)* last);
-                tokio::spawn(fut);
+                Ok(Some(match ack {
+                    Some((s, ack_id)) => Box::pin(async move { send_ack(s, ack_id, fut.await) }),
+                    None => Box::pin(async move { fut.await; }),
+                }))
+            }
+
+            fn is_bin_stream(&self) -> bool {
+                <$last as FromMessage<A, M>>::BIN_STREAM
             }
         }
     };
@@ -281,26 +443,33 @@ macro_rules! impl_handler {
         [$($ty:ident),*], $last:ident
     ) => {
         #[allow(non_snake_case, unused)]
-        impl<A, F, M, $($ty,)* $last> MessageHandler<A, (private::Sync, M, $($ty,)* $last,)> for F
+        impl<A, F, M, $($ty,)* $last, R> MessageHandler<A, (private::Sync, M, $($ty,)* $last,)> for F
         where
-            F: FnOnce($($ty,)* $last,) + Send + Sync + Clone + 'static,
+            F: FnOnce($($ty,)* $last,) -> R + Send + Sync + Clone + 'static,
+            R: IntoAck,
             A: Adapter,
             $( $ty: FromMessageParts<A> + Send, )*
             $last: FromMessage<A, M> + Send,
         {
-            fn call(&self, s: Arc<Socket<A>>, mut v: Value, mut p: Vec<Bytes>, ack_id: Option<i64>) {
+            fn call(&self, s: Arc<Socket<A>>, mut v: RawPayload, mut p: Vec<Bytes>, ack_id: Option<i64>) -> Result<Option<BoxFuture<'static, ()>>, ErrorCause> {
+                let handles_ack = $( <$ty as FromMessageParts<A>>::HANDLES_ACK || )* <$last as FromMessage<A, M>>::HANDLES_ACK;
+                let ack = ack_id.filter(|_| !handles_ack).map(|ack_id| (s.clone(), ack_id));
                 $(
-                    let $ty = match $ty::from_message_parts(&s, &mut v, &mut p, &ack_id) {
-                        Ok(v) => v,
-                        Err(_) => return,
-                    };
+                    let $ty = $ty::from_message_parts(&s, &mut v, &mut p, &ack_id)
+                        .map_err(|e| ErrorCause::Extract(Box::new(e)))?;
                 )*
-                let last = match $last::from_message(s, v, p, ack_id) {
-                    Ok(v) => v,
-                    Err(_) => return,
-                };
+                let last = $last::from_message(s, v, p, ack_id)
+                    .map_err(|e| ErrorCause::Extract(Box::new(e)))?;
+
+                let res = (self.clone())($($ty,)* last);
+                if let Some((s, ack_id)) = ack {
+                    send_ack(s, ack_id, res);
+                }
+                Ok(None)
+            }
 
-                (self.clone())($($ty,)* last);
+            fn is_bin_stream(&self) -> bool {
+                <$last as FromMessage<A, M>>::BIN_STREAM
             }
         }
     };
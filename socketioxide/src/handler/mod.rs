@@ -3,15 +3,19 @@
 //! All handlers can be async or not.
 pub mod connect;
 pub mod disconnect;
+pub mod error;
 pub mod extract;
 pub mod message;
+pub mod service;
 
-pub(crate) use connect::BoxedConnectHandler;
+pub(crate) use connect::{BoxedConnectHandler, BoxedConnectMiddleware};
 pub use connect::{ConnectHandler, ConnectMiddleware, FromConnectParts};
 pub(crate) use disconnect::BoxedDisconnectHandler;
 pub use disconnect::{DisconnectHandler, FromDisconnectParts};
+pub use error::ErrorHandler;
 pub(crate) use message::BoxedMessageHandler;
-pub use message::{FromMessage, FromMessageParts, MessageHandler};
+pub use message::{FromMessage, FromMessageParts, IntoAck, MessageHandler};
+pub use service::{EventRequest, EventService};
 /// A struct used to erase the type of a [`ConnectHandler`] or [`MessageHandler`] so it can be stored in a map
 pub(crate) struct MakeErasedHandler<H, A, T> {
     handler: H,
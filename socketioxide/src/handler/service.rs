@@ -0,0 +1,354 @@
+//! [`EventService`] and [`EventRequest`] types, used to wrap the [`MessageHandler`](super::MessageHandler)s
+//! with [`tower`] layers such as timeouts, concurrency limits or tracing spans.
+//!
+//! Each event received by a socket is dispatched as an [`EventRequest`] to an [`EventService`]
+//! which calls the handler registered for this event.
+//! Layers can be applied at three levels:
+//! * globally for every socket with [`SocketIo::event_layer`](crate::SocketIo::event_layer),
+//! * for every event of a socket with [`Socket::event_layer`](crate::socket::Socket::event_layer),
+//! * for a single event with [`Socket::on_with_layer`](crate::socket::Socket::on_with_layer).
+//!
+//! The socket layers wrap the global layers, which wrap the event layers.
+//! The response future of the service resolves when the handler (sync or async) has completed.
+//!
+//! When a socket has no layer, its handlers are called directly when the event is received.
+//! Otherwise the event is dispatched in a new task, so the order of execution of
+//! sync handlers is no longer guaranteed, unless a sequential [`DispatchMode`](crate::DispatchMode) is set.
+//!
+//! The errors returned by the layers are reported to the [`ErrorHandler`](super::ErrorHandler)
+//! with the [`ErrorCause::Service`] cause.
+//! The extractor errors are still reported directly and do not go through the layers.
+//!
+//! ## Example
+//! ```rust
+//! # use socketioxide::{SocketIo, extract::*};
+//! # use std::time::Duration;
+//! # use tower::ServiceBuilder;
+//! # #[derive(Clone)]
+//! # struct LogLayer;
+//! # impl<S> tower::Layer<S> for LogLayer {
+//! #     type Service = S;
+//! #     fn layer(&self, inner: S) -> S { inner }
+//! # }
+//! let (_, io) = SocketIo::new_svc();
+//! // Applied to all the events of all the sockets
+//! io.event_layer(LogLayer);
+//!
+//! io.ns("/", |s: SocketRef| {
+//!     // Applied to all the events of this socket
+//!     s.event_layer(ServiceBuilder::new().layer(LogLayer));
+//!     // Applied only to the "slow" event
+//!     s.on_with_layer("slow", |Data::<String>(data)| async move {
+//!         tokio::time::sleep(Duration::from_secs(1)).await;
+//!     }, LogLayer);
+//! });
+//! ```
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::future::{poll_fn, BoxFuture};
+use tower::{BoxError, Layer, Service};
+
+use crate::{
+    adapter::{Adapter, LocalAdapter},
+    extract::BinStream,
+    packet::RawPayload,
+    socket::Socket,
+};
+
+use super::error::ErrorCause;
+
+/// An event received by a socket and dispatched to an [`EventService`].
+pub struct EventRequest<A: Adapter = LocalAdapter> {
+    socket: Arc<Socket<A>>,
+    event: Cow<'static, str>,
+    data: RawPayload,
+    bin: Vec<Bytes>,
+    ack_id: Option<i64>,
+    bin_stream: Option<BinStream>,
+}
+
+impl<A: Adapter> EventRequest<A> {
+    pub(crate) fn new(
+        socket: Arc<Socket<A>>,
+        event: impl Into<Cow<'static, str>>,
+        data: RawPayload,
+        bin: Vec<Bytes>,
+        ack_id: Option<i64>,
+    ) -> Self {
+        Self {
+            socket,
+            event: event.into(),
+            data,
+            bin,
+            ack_id,
+            bin_stream: None,
+        }
+    }
+
+    pub(crate) fn with_bin_stream(mut self, stream: BinStream) -> Self {
+        self.bin_stream = Some(stream);
+        self
+    }
+
+    pub(crate) fn into_parts(
+        self,
+    ) -> (
+        Cow<'static, str>,
+        RawPayload,
+        Vec<Bytes>,
+        Option<i64>,
+        Option<BinStream>,
+    ) {
+        (
+            self.event,
+            self.data,
+            self.bin,
+            self.ack_id,
+            self.bin_stream,
+        )
+    }
+
+    /// The socket that received the event
+    pub fn socket(&self) -> &Socket<A> {
+        &self.socket
+    }
+
+    /// The name of the event
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    /// The raw payload of the event
+    pub fn data(&self) -> &RawPayload {
+        &self.data
+    }
+
+    /// The binary attachments of the event. They are empty if they are streamed with a [`BinStream`].
+    pub fn bin(&self) -> &[Bytes] {
+        &self.bin
+    }
+
+    /// The ack id of the event, if the client expects an acknowledgement
+    pub fn ack_id(&self) -> Option<i64> {
+        self.ack_id
+    }
+}
+
+impl<A: Adapter> Debug for EventRequest<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRequest")
+            .field("sid", &self.socket.id)
+            .field("event", &self.event)
+            .field("ack_id", &self.ack_id)
+            .finish()
+    }
+}
+
+/// A [`tower::Service`] that calls the [`MessageHandler`](super::MessageHandler) registered for an [`EventRequest`],
+/// possibly through the layers applied to it.
+///
+/// It is given to the [`Layer`]s registered with the `event_layer` methods.
+pub struct EventService<A: Adapter = LocalAdapter> {
+    inner: Inner<A>,
+}
+
+enum Inner<A: Adapter> {
+    /// Dispatches to the layers of the event, then to its handler
+    Socket,
+    /// Calls the handler of the event
+    Handler,
+    /// A layered service
+    Layered(Arc<dyn ErasedEventService<A>>),
+}
+
+impl<A: Adapter> EventService<A> {
+    /// The service used by default for all the events of a socket
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Inner::Socket,
+        }
+    }
+
+    /// The service wrapped by the layers of a single event
+    pub(crate) fn handler() -> Self {
+        Self {
+            inner: Inner::Handler,
+        }
+    }
+
+    /// Wraps this service with the given layer
+    pub(crate) fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<Self>,
+        L::Service: Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+        <L::Service as Service<EventRequest<A>>>::Error: Into<BoxError>,
+        <L::Service as Service<EventRequest<A>>>::Future: Send + 'static,
+    {
+        let svc = Mutex::new(layer.layer(self));
+        Self {
+            inner: Inner::Layered(Arc::new(svc)),
+        }
+    }
+
+    /// Dispatches an event received by a socket.
+    ///
+    /// Without layers, the handler is called right away and async handlers are spawned.
+    /// Otherwise the request goes through the layers in a new task.
+    /// With a sequential [`DispatchMode`](crate::DispatchMode), the whole processing of the event is queued.
+    pub(crate) fn dispatch(&self, req: EventRequest<A>) {
+        let socket = req.socket.clone();
+        if !socket.is_concurrent_dispatch() {
+            let event = req.event.clone();
+            socket.spawn_handler(&event, self.process(req));
+            return;
+        }
+        match &self.inner {
+            Inner::Socket => match socket.event_service(&req.event) {
+                Some(svc) => svc.dispatch(req),
+                None => call_handler(req),
+            },
+            Inner::Handler => call_handler(req),
+            Inner::Layered(svc) => {
+                let event = req.event.clone();
+                let fut = svc.call(socket.clone(), event.clone(), req.ack_id, req);
+                socket.spawn_handler(&event, fut);
+            }
+        }
+    }
+
+    /// Returns a future processing the whole event when polled, handler included
+    fn process(&self, req: EventRequest<A>) -> BoxFuture<'static, ()> {
+        let svc = self.clone();
+        Box::pin(async move {
+            let socket = req.socket.clone();
+            match &svc.inner {
+                Inner::Socket => match socket.event_service(&req.event) {
+                    Some(svc) => svc.process(req).await,
+                    None => call_handler_now(req).await,
+                },
+                Inner::Handler => call_handler_now(req).await,
+                Inner::Layered(svc) => {
+                    let event = req.event.clone();
+                    svc.call(socket, event, req.ack_id, req).await
+                }
+            }
+        })
+    }
+}
+
+/// Calls the handler of the request and spawns it if it is async
+fn call_handler<A: Adapter>(req: EventRequest<A>) {
+    let (socket, event) = (req.socket.clone(), req.event.clone());
+    if let Some(fut) = socket.clone().call_handler(req) {
+        socket.spawn_handler(&event, fut);
+    }
+}
+
+/// Calls the handler of the request and waits for it to complete
+async fn call_handler_now<A: Adapter>(req: EventRequest<A>) {
+    if let Some(fut) = req.socket.clone().call_handler(req) {
+        fut.await;
+    }
+}
+
+impl<A: Adapter> Clone for EventService<A> {
+    fn clone(&self) -> Self {
+        let inner = match &self.inner {
+            Inner::Socket => Inner::Socket,
+            Inner::Handler => Inner::Handler,
+            Inner::Layered(svc) => Inner::Layered(svc.clone()),
+        };
+        Self { inner }
+    }
+}
+
+impl<A: Adapter> Debug for EventService<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = match &self.inner {
+            Inner::Socket => "Socket",
+            Inner::Handler => "Handler",
+            Inner::Layered(_) => "Layered",
+        };
+        f.debug_tuple("EventService").field(&inner).finish()
+    }
+}
+
+impl<A: Adapter> Service<EventRequest<A>> for EventService<A> {
+    type Response = ();
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<(), BoxError>>;
+
+    #[inline(always)]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The readiness of the layered services is awaited when they are called
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: EventRequest<A>) -> Self::Future {
+        match &self.inner {
+            Inner::Socket => match req.socket.event_service(&req.event) {
+                Some(mut svc) => svc.call(req),
+                None => call_handler_fut(req),
+            },
+            Inner::Handler => call_handler_fut(req),
+            Inner::Layered(svc) => svc.ready_call(req),
+        }
+    }
+}
+
+/// Calls the handler of the request and returns a future that resolves when it has completed
+fn call_handler_fut<A: Adapter>(req: EventRequest<A>) -> BoxFuture<'static, Result<(), BoxError>> {
+    let fut = req.socket.clone().call_handler(req);
+    Box::pin(async move {
+        if let Some(fut) = fut {
+            fut.await;
+        }
+        Ok(())
+    })
+}
+
+/// A type erased layered service
+trait ErasedEventService<A: Adapter>: Send + Sync + 'static {
+    fn ready_call(&self, req: EventRequest<A>) -> BoxFuture<'static, Result<(), BoxError>>;
+
+    /// Calls the service and reports its error to the socket error handler
+    fn call(
+        &self,
+        socket: Arc<Socket<A>>,
+        event: Cow<'static, str>,
+        ack_id: Option<i64>,
+        req: EventRequest<A>,
+    ) -> BoxFuture<'static, ()> {
+        let fut = self.ready_call(req);
+        Box::pin(async move {
+            if let Err(e) = fut.await {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?socket.id, ?event, "event service error: {}", e);
+                socket.report_error(Some(&event), ack_id, ErrorCause::Service(e));
+            }
+        })
+    }
+}
+
+impl<A, S> ErasedEventService<A> for Mutex<S>
+where
+    A: Adapter,
+    S: Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    fn ready_call(&self, req: EventRequest<A>) -> BoxFuture<'static, Result<(), BoxError>> {
+        // The service is cloned so that it can be driven to readiness without holding the lock
+        let mut svc = self.lock().unwrap().clone();
+        Box::pin(async move {
+            poll_fn(|cx| svc.poll_ready(cx)).await.map_err(Into::into)?;
+            svc.call(req).await.map_err(Into::into)
+        })
+    }
+}
@@ -0,0 +1,93 @@
+//! ### Raw frame [`Interceptor`]
+//!
+//! An [`Interceptor`] set with [`SocketIoBuilder::interceptor`](crate::SocketIoBuilder::interceptor)
+//! sees the raw engine.io frames exchanged with every client:
+//! * the text and binary frames received, before they are decoded as socket.io packets,
+//! * the text and binary frames emitted, after the socket.io packets are encoded.
+//!
+//! The text hooks can forward the frame unchanged, replace it or drop it by returning `None`.
+//! The binary hooks can only forward or replace the attachments: dropping one of them would leave its packet
+//! with a missing attachment, which the receiver would wait for forever.
+//! It can be used to debug the protocol, to capture the traffic or to experiment with a custom framing
+//! (e.g. handling frames that are not socket.io packets before they are rejected by the decoder).
+//!
+//! The engine.io control packets (ping, pong, noop...) are handled by the engine.io layer and are not intercepted.
+//!
+//! The hooks are called on the hot path of every frame, they should be fast and must not block.
+//!
+//! ## Example
+//! ```
+//! # use socketioxide::{SocketIo, interceptor::{Interceptor, Str}, socket::Sid};
+//! struct Logger;
+//! impl Interceptor for Logger {
+//!     fn on_message(&self, sid: Sid, msg: Str) -> Option<Str> {
+//!         println!("[{sid}] <- {msg}");
+//!         Some(msg)
+//!     }
+//!     fn on_emit(&self, sid: Sid, msg: Str) -> Option<Str> {
+//!         println!("[{sid}] -> {msg}");
+//!         Some(msg)
+//!     }
+//! }
+//!
+//! let (_, io) = SocketIo::builder().interceptor(Logger).build_svc();
+//! ```
+use std::fmt::Debug;
+
+use bytes::Bytes;
+use engineioxide::sid::Sid;
+
+pub use engineioxide::Str;
+
+/// Hooks called with the raw frames received from and emitted to the clients.
+///
+/// Every method has a default implementation that forwards the frame unchanged.
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called with each text frame received from a client, before it is decoded.
+    /// Returning `None` drops the frame.
+    fn on_message(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        Some(msg)
+    }
+
+    /// Called with each binary frame received from a client, before it is applied to its packet.
+    /// The frame can be replaced but not dropped, so that its packet still gets all its attachments.
+    fn on_binary(&self, _sid: Sid, data: Bytes) -> Bytes {
+        data
+    }
+
+    /// Called with each text frame emitted to a client.
+    /// Returning `None` drops the frame along with its binary attachments.
+    fn on_emit(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        Some(msg)
+    }
+
+    /// Called with each binary attachment emitted to a client, after the text frame it belongs to.
+    /// The attachment can be replaced but not dropped, so that the client still gets all the attachments of the packet.
+    fn on_emit_binary(&self, _sid: Sid, data: Bytes) -> Bytes {
+        data
+    }
+}
+
+impl Debug for dyn Interceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Interceptor")
+    }
+}
+
+/// Applies the outgoing hooks of the interceptor, if any, to an encoded packet
+pub(crate) fn intercept_emit(
+    interceptor: Option<&dyn Interceptor>,
+    sid: Sid,
+    msg: Str,
+    bin: Vec<Bytes>,
+) -> Option<(Str, Vec<Bytes>)> {
+    let Some(interceptor) = interceptor else {
+        return Some((msg, bin));
+    };
+    let msg = interceptor.on_emit(sid, msg)?;
+    let bin = bin
+        .into_iter()
+        .map(|data| interceptor.on_emit_binary(sid, data))
+        .collect();
+    Some((msg, bin))
+}
@@ -2,22 +2,27 @@ use std::{borrow::Cow, sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use engineioxide::{
-    config::{EngineIoConfig, EngineIoConfigBuilder},
+    config::{CookieConfig, CorsConfig, EngineIoConfig, EngineIoConfigBuilder},
     service::NotFoundService,
     sid::Sid,
     TransportType,
 };
+use http::{request::Parts, HeaderMap};
 
 use crate::{
     ack::AckStream,
     adapter::{Adapter, LocalAdapter, Room},
     client::Client,
     extract::SocketRef,
-    handler::ConnectHandler,
+    handler::{
+        BoxedConnectMiddleware, ConnectHandler, ConnectMiddleware, ErrorHandler, EventRequest,
+        EventService, MakeErasedHandler,
+    },
+    interceptor::Interceptor,
     layer::SocketIoLayer,
     operators::{BroadcastOperators, RoomParam},
     service::SocketIoService,
-    BroadcastError, DisconnectError,
+    BroadcastError, DisconnectError, DispatchMode,
 };
 
 /// Configuration for Socket.IO & Engine.IO
@@ -35,6 +40,45 @@ pub struct SocketIoConfig {
     ///
     /// Defaults to 45 seconds.
     pub connect_timeout: Duration,
+
+    /// The maximum number of bytes of binary attachments that can be streamed to a
+    /// [`BinStream`](crate::extract::BinStream) extractor for a single event.
+    ///
+    /// Unlike `max_payload`, it applies to the whole set of attachments of an event rather than to a single request.
+    ///
+    /// Defaults to 10 MB.
+    pub max_bin_stream_size: usize,
+
+    /// The [`ErrorHandler`] called with the errors of the sockets whose namespace has no handler of its own.
+    ///
+    /// Defaults to `None`.
+    pub on_error: Option<Arc<dyn ErrorHandler>>,
+
+    /// The order in which the async message handlers of each socket are executed.
+    ///
+    /// Defaults to [`DispatchMode::Concurrent`].
+    pub dispatch_mode: DispatchMode,
+
+    /// The maximum number of message handlers of a socket that can be running or queued at the same time.
+    /// When it is reached, the server stops reading from the transport of the socket until a handler completes.
+    ///
+    /// Defaults to `None` (no limit).
+    pub max_in_flight_handlers: Option<usize>,
+
+    /// The [`Interceptor`] called with the raw frames received from and emitted to the clients.
+    ///
+    /// Defaults to `None`.
+    pub interceptor: Option<Arc<dyn Interceptor>>,
+
+    /// The [`HeadersHook`] called before answering a handshake request (polling or websocket).
+    ///
+    /// Defaults to `None`.
+    pub on_initial_headers: Option<Arc<dyn HeadersHook>>,
+
+    /// The [`HeadersHook`] called before answering any socket.io http request.
+    ///
+    /// Defaults to `None`.
+    pub on_headers: Option<Arc<dyn HeadersHook>>,
 }
 
 impl Default for SocketIoConfig {
@@ -46,16 +90,48 @@ impl Default for SocketIoConfig {
             },
             ack_timeout: Duration::from_secs(5),
             connect_timeout: Duration::from_secs(45),
+            max_bin_stream_size: 10 * 1024 * 1024,
+            on_error: None,
+            dispatch_mode: DispatchMode::default(),
+            max_in_flight_handlers: None,
+            interceptor: None,
+            on_initial_headers: None,
+            on_headers: None,
         }
     }
 }
 
+/// A hook adding headers to the response of a socket.io http request, see [`SocketIoBuilder::on_headers`].
+///
+/// It is implemented for closures taking the response headers and the request parts.
+pub trait HeadersHook: Send + Sync + 'static {
+    /// Adds headers to the response of the given request
+    fn call(&self, headers: &mut HeaderMap, req: &Parts);
+}
+
+impl<F> HeadersHook for F
+where
+    F: Fn(&mut HeaderMap, &Parts) + Send + Sync + 'static,
+{
+    #[inline(always)]
+    fn call(&self, headers: &mut HeaderMap, req: &Parts) {
+        self(headers, req)
+    }
+}
+
+impl std::fmt::Debug for dyn HeadersHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HeadersHook")
+    }
+}
+
 /// A builder to create a [`SocketIo`] instance.
 /// It contains everything to configure the socket.io server with a [`SocketIoConfig`].
 /// It can be used to build either a Tower [`Layer`](tower::layer::Layer) or a [`Service`](tower::Service).
 pub struct SocketIoBuilder<A: Adapter = LocalAdapter> {
     config: SocketIoConfig,
     engine_config_builder: EngineIoConfigBuilder,
+    middlewares: Vec<BoxedConnectMiddleware<A>>,
     adapter: std::marker::PhantomData<A>,
 }
 
@@ -65,6 +141,7 @@ impl<A: Adapter> SocketIoBuilder<A> {
         Self {
             config: SocketIoConfig::default(),
             engine_config_builder: EngineIoConfigBuilder::new().req_path("/socket.io".to_string()),
+            middlewares: Vec::new(),
             adapter: std::marker::PhantomData,
         }
     }
@@ -96,6 +173,38 @@ impl<A: Adapter> SocketIoBuilder<A> {
         self
     }
 
+    /// The [`HeartbeatStrategy`](engineioxide::heartbeat::HeartbeatStrategy) used to compute the delay
+    /// between two pings sent to a client.
+    ///
+    /// Defaults to [`FixedInterval`](engineioxide::heartbeat::FixedInterval), a ping every `ping_interval`.
+    #[inline]
+    pub fn heartbeat(mut self, strategy: impl engineioxide::heartbeat::HeartbeatStrategy) -> Self {
+        self.engine_config_builder = self.engine_config_builder.heartbeat(strategy);
+        self
+    }
+
+    /// The amount of time the server will wait for a client to complete a transport upgrade
+    /// before cancelling it. The client then keeps using the polling transport.
+    ///
+    /// Defaults to 10 seconds.
+    #[inline]
+    pub fn upgrade_timeout(mut self, upgrade_timeout: Duration) -> Self {
+        self.engine_config_builder = self.engine_config_builder.upgrade_timeout(upgrade_timeout);
+        self
+    }
+
+    /// The maximum amount of time the heartbeat timeout of a client can be extended while reading
+    /// from its transport is paused, e.g. because of [`max_in_flight_handlers`](Self::max_in_flight_handlers)
+    /// or a [`BinStream`](crate::extract::BinStream) that is not consumed.
+    /// Once it is reached the connection is closed with a heartbeat timeout.
+    ///
+    /// Defaults to 60 seconds.
+    #[inline]
+    pub fn max_read_pause(mut self, max_read_pause: Duration) -> Self {
+        self.engine_config_builder = self.engine_config_builder.max_read_pause(max_read_pause);
+        self
+    }
+
     /// The maximum number of packets that can be buffered per connection before being emitted to the client.
     /// If the buffer if full the `emit()` method will return an error
     ///
@@ -119,6 +228,7 @@ impl<A: Adapter> SocketIoBuilder<A> {
     /// Allowed transports on this server
     ///
     /// The `transports` array should have a size of 1 or 2
+    /// (or 3 with the `webtransport` feature)
     ///
     /// Defaults to :
     /// `[TransportType::Polling, TransportType::Websocket]`
@@ -128,6 +238,37 @@ impl<A: Adapter> SocketIoBuilder<A> {
         self
     }
 
+    /// The CORS policy applied to socket.io requests.
+    /// If it is set, preflight `OPTIONS` requests are answered directly
+    /// and requests from a non-allowed origin are rejected, websocket upgrades included.
+    ///
+    /// Defaults to `None` (no CORS handling).
+    ///
+    /// ```
+    /// # use socketioxide::{SocketIo, CorsConfig};
+    /// let (layer, io) = SocketIo::builder()
+    ///     .cors(CorsConfig::new().allow_origin("https://example.com"))
+    ///     .build_layer();
+    /// ```
+    ///
+    /// # Panics
+    /// If credentials are allowed without a list of allowed origins.
+    #[inline]
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        self.engine_config_builder = self.engine_config_builder.cors(cors);
+        self
+    }
+
+    /// A sticky-session cookie set on the handshake response (polling or websocket), with the session id as value.
+    /// It can be used by a load balancer to route every request of a session to the same server.
+    ///
+    /// Defaults to `None` (no cookie).
+    #[inline]
+    pub fn cookie(mut self, cookie: CookieConfig) -> Self {
+        self.engine_config_builder = self.engine_config_builder.cookie(cookie);
+        self
+    }
+
     /// The amount of time the server will wait for an acknowledgement from the client before closing the connection.
     ///
     /// Defaults to 5 seconds.
@@ -146,6 +287,106 @@ impl<A: Adapter> SocketIoBuilder<A> {
         self
     }
 
+    /// The maximum number of bytes of binary attachments that can be streamed to a
+    /// [`BinStream`](crate::extract::BinStream) extractor for a single event.
+    ///
+    /// Unlike `max_payload`, it applies to the whole set of attachments of an event rather than to a single request.
+    ///
+    /// Defaults to 10 MB.
+    #[inline]
+    pub fn max_bin_stream_size(mut self, max_bin_stream_size: usize) -> Self {
+        self.config.max_bin_stream_size = max_bin_stream_size;
+        self
+    }
+
+    /// The hook called with the errors that cannot be returned to the user code,
+    /// such as extractor failures. It can be overridden per namespace with [`SocketIo::ns_on_error`].
+    ///
+    /// See the [`error`](crate::handler::error) module for more details.
+    ///
+    /// Defaults to `None`, the errors are only logged with the `tracing` feature.
+    #[inline]
+    pub fn on_error(mut self, handler: impl ErrorHandler) -> Self {
+        self.config.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    /// The order in which the async message handlers of each socket are executed:
+    /// concurrently, one at a time per socket or one at a time per event name.
+    ///
+    /// See [`DispatchMode`] for more details.
+    /// It can be overridden per namespace with [`SocketIo::ns_dispatch_mode`].
+    ///
+    /// Defaults to [`DispatchMode::Concurrent`].
+    #[inline]
+    pub fn dispatch_mode(mut self, dispatch_mode: DispatchMode) -> Self {
+        self.config.dispatch_mode = dispatch_mode;
+        self
+    }
+
+    /// The maximum number of message handlers of a socket that can be running or queued at the same time.
+    /// When it is reached, the server stops reading from the transport of the socket until a handler completes,
+    /// applying backpressure on the client.
+    ///
+    /// Reading is not paused while a handler of the socket awaits an acknowledgement,
+    /// so that it can be received. The heartbeat timeout of the client is suspended while reading is paused,
+    /// up to [`max_read_pause`](Self::max_read_pause).
+    ///
+    /// Defaults to no limit.
+    ///
+    /// # Panics
+    /// If `max` is 0.
+    #[inline]
+    pub fn max_in_flight_handlers(mut self, max: usize) -> Self {
+        assert!(max > 0, "max_in_flight_handlers must be greater than 0");
+        self.config.max_in_flight_handlers = Some(max);
+        self
+    }
+
+    /// An [`Interceptor`] that sees the raw frames received from each client before they are decoded,
+    /// and the frames emitted to each client after they are encoded. It can also replace or drop them.
+    ///
+    /// See the [`interceptor`](crate::interceptor) module for more details.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.config.interceptor = Some(Arc::new(interceptor));
+        self
+    }
+
+    /// A hook called before answering a handshake request (polling or websocket).
+    /// The headers it sets are added to the handshake response, e.g. to set custom headers or cookies.
+    ///
+    /// It is called before the [`on_headers`](SocketIoBuilder::on_headers) hook.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// ```
+    /// # use socketioxide::SocketIo;
+    /// let (layer, io) = SocketIo::builder()
+    ///     .on_initial_headers(|headers: &mut http::HeaderMap, _: &http::request::Parts| {
+    ///         headers.insert("x-server", http::HeaderValue::from_static("socketioxide"));
+    ///     })
+    ///     .build_layer();
+    /// ```
+    #[inline]
+    pub fn on_initial_headers(mut self, hook: impl HeadersHook) -> Self {
+        self.config.on_initial_headers = Some(Arc::new(hook));
+        self
+    }
+
+    /// A hook called before answering any socket.io http request
+    /// (handshake, polling, post and websocket upgrade).
+    /// The headers it sets are added to the response.
+    ///
+    /// Defaults to `None`.
+    #[inline]
+    pub fn on_headers(mut self, hook: impl HeadersHook) -> Self {
+        self.config.on_headers = Some(Arc::new(hook));
+        self
+    }
+
     /// Sets a custom [`SocketIoConfig`] created previously for this [`SocketIoBuilder`]
     #[inline]
     pub fn with_config(mut self, config: SocketIoConfig) -> Self {
@@ -153,11 +394,46 @@ impl<A: Adapter> SocketIoBuilder<A> {
         self
     }
 
+    /// Registers a [`ConnectMiddleware`] called for every namespace connection,
+    /// before the middlewares of the namespace set with [`ConnectHandler::with`].
+    ///
+    /// Unlike [`SocketIo::use_middleware`], the middleware is in place before the first connection can arrive.
+    /// Because a middleware is bound to an [`Adapter`], it must be registered after [`with_adapter`](Self::with_adapter).
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, ConnectError, extract::{SocketRef, Data}};
+    /// fn jwt(Data(token): Data<String>) -> Result<(), ConnectError> {
+    ///     token.starts_with("user:").then_some(()).ok_or(ConnectError::new("invalid token"))
+    /// }
+    ///
+    /// let (_, io) = SocketIo::builder().use_middleware(jwt).build_svc();
+    /// io.ns("/", |s: SocketRef| {});
+    /// ```
+    pub fn use_middleware<M, T>(mut self, middleware: M) -> Self
+    where
+        M: ConnectMiddleware<A, T>,
+        T: Send + Sync + 'static,
+    {
+        self.middlewares
+            .push(MakeErasedHandler::new_middleware_boxed(middleware));
+        self
+    }
+
     /// Sets a custom [`Adapter`] for this [`SocketIoBuilder`]
+    ///
+    /// # Panics
+    /// If middlewares were registered with [`use_middleware`](Self::use_middleware) before,
+    /// because they are bound to the previous adapter.
     pub fn with_adapter<B: Adapter>(self) -> SocketIoBuilder<B> {
+        assert!(
+            self.middlewares.is_empty(),
+            "the global middlewares must be registered after the adapter is set"
+        );
         SocketIoBuilder {
             config: self.config,
             engine_config_builder: self.engine_config_builder,
+            middlewares: Vec::new(),
             adapter: std::marker::PhantomData,
         }
     }
@@ -180,6 +456,7 @@ impl<A: Adapter> SocketIoBuilder<A> {
         self.config.engine_config = self.engine_config_builder.build();
 
         let (layer, client) = SocketIoLayer::from_config(Arc::new(self.config));
+        client.extend_middlewares(self.middlewares);
         (layer, SocketIo(client))
     }
 
@@ -187,21 +464,26 @@ impl<A: Adapter> SocketIoBuilder<A> {
     ///
     /// This service will be a _standalone_ service that return a 404 error for every non-socket.io request
     /// It can be used as a hyper service
-    pub fn build_svc(mut self) -> (SocketIoService<NotFoundService>, SocketIo) {
+    pub fn build_svc(mut self) -> (SocketIoService<NotFoundService, A>, SocketIo<A>) {
         self.config.engine_config = self.engine_config_builder.build();
 
         let (svc, client) =
             SocketIoService::with_config_inner(NotFoundService, Arc::new(self.config));
+        client.extend_middlewares(self.middlewares);
         (svc, SocketIo(client))
     }
 
     /// Builds a [`SocketIoService`] and a [`SocketIo`] instance with an inner service
     ///
     /// It can be used as a hyper service
-    pub fn build_with_inner_svc<S: Clone>(mut self, svc: S) -> (SocketIoService<S>, SocketIo) {
+    pub fn build_with_inner_svc<S: Clone>(
+        mut self,
+        svc: S,
+    ) -> (SocketIoService<S, A>, SocketIo<A>) {
         self.config.engine_config = self.engine_config_builder.build();
 
         let (svc, client) = SocketIoService::with_config_inner(svc, Arc::new(self.config));
+        client.extend_middlewares(self.middlewares);
         (svc, SocketIo(client))
     }
 }
@@ -342,6 +624,131 @@ impl<A: Adapter> SocketIo<A> {
         self.0.add_ns(path.into(), callback);
     }
 
+    /// Registers a [`ConnectMiddleware`] called for every namespace connection,
+    /// before the middlewares of the namespace set with [`ConnectHandler::with`].
+    /// Global middlewares are called in their registration order.
+    /// Use [`SocketIoBuilder::use_middleware`] to register them before the first connection can arrive.
+    ///
+    /// It supports the same extractors as namespace middlewares and can also
+    /// return a value retrieved with the [`MiddlewareValue`](crate::extract::MiddlewareValue) extractor.
+    ///
+    /// See the [`connect`](crate::handler::connect) module doc for more details on middlewares.
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, ConnectError, extract::{SocketRef, Data, MiddlewareValue}};
+    /// #[derive(Clone)]
+    /// struct UserId(String);
+    ///
+    /// fn jwt(Data(token): Data<String>) -> Result<UserId, ConnectError> {
+    ///     // Validate the token for all namespaces
+    ///     token.strip_prefix("user:").map(|id| UserId(id.into())).ok_or(ConnectError::new("invalid token"))
+    /// }
+    ///
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.use_middleware(jwt);
+    /// io.ns("/", |s: SocketRef, MiddlewareValue(id): MiddlewareValue<UserId>| {
+    ///     println!("user {} connected on /", id.0);
+    /// });
+    /// io.ns("/admin", |s: SocketRef, MiddlewareValue(id): MiddlewareValue<UserId>| {
+    ///     println!("user {} connected on /admin", id.0);
+    /// });
+    /// ```
+    #[inline]
+    pub fn use_middleware<M, T>(&self, middleware: M)
+    where
+        M: ConnectMiddleware<A, T>,
+        T: Send + Sync + 'static,
+    {
+        self.0.add_middleware(middleware);
+    }
+
+    /// Wraps the dispatch of the events of all the sockets with a [`tower`] [`Layer`](tower::Layer),
+    /// for example to apply a timeout or a tracing span to every [`MessageHandler`](crate::handler::MessageHandler).
+    ///
+    /// It only applies to the sockets connected after this call.
+    /// It can be called multiple times, the last layer set is the outermost one.
+    ///
+    /// See the [`service`](crate::handler::service) module doc for more details.
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// // Every handler taking more than 10 seconds is cancelled and reported to the error handler
+    /// io.event_layer(tower::timeout::TimeoutLayer::new(Duration::from_secs(10)));
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("test", |Data::<String>(data)| async move {
+    ///         println!("Received a test message {:?}", data);
+    ///     });
+    /// });
+    /// ```
+    #[inline]
+    pub fn event_layer<L>(&self, layer: L)
+    where
+        L: tower::Layer<EventService<A>>,
+        L::Service: tower::Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+        <L::Service as tower::Service<EventRequest<A>>>::Error: Into<tower::BoxError>,
+        <L::Service as tower::Service<EventRequest<A>>>::Future: Send + 'static,
+    {
+        self.0.add_event_layer(layer);
+    }
+
+    /// Sets the hook called with the errors of the sockets connected to the namespace with the given path.
+    /// It replaces the global hook set with [`SocketIoBuilder::on_error`] for this namespace.
+    ///
+    /// Returns `false` if the namespace does not exist.
+    ///
+    /// See the [`error`](crate::handler::error) module for more details.
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::SocketRef, handler::error::{ErrorReply, HandlerError}};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/chat", |socket: SocketRef| {});
+    /// io.ns_on_error("/chat", |err: &HandlerError| {
+    ///     let data = serde_json::json!({ "event": err.event, "error": err.cause.to_string() });
+    ///     Some(ErrorReply::Emit("chat_error".into(), data))
+    /// });
+    /// ```
+    #[inline]
+    pub fn ns_on_error(&self, path: &str, handler: impl ErrorHandler) -> bool {
+        match self.0.get_ns(path) {
+            Some(ns) => {
+                ns.set_error_handler(Arc::new(handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the [`DispatchMode`] of the sockets connected to the namespace with the given path.
+    /// It overrides the global mode set with [`SocketIoBuilder::dispatch_mode`] for this namespace.
+    ///
+    /// It only applies to the sockets connected after this call.
+    /// Returns `false` if the namespace does not exist.
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, DispatchMode, extract::SocketRef};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {});
+    /// // The chat messages must be processed in order
+    /// io.ns("/chat", |socket: SocketRef| {});
+    /// io.ns_dispatch_mode("/chat", DispatchMode::Sequential);
+    /// ```
+    #[inline]
+    pub fn ns_dispatch_mode(&self, path: &str, mode: DispatchMode) -> bool {
+        match self.0.get_ns(path) {
+            Some(ns) => {
+                ns.set_dispatch_mode(mode);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Deletes the namespace with the given path.
     ///
     /// This will disconnect all sockets connected to this
@@ -363,6 +770,36 @@ impl<A: Adapter> SocketIo<A> {
         self.0.close().await;
     }
 
+    /// Gracefully closes all the connections like [`SocketIo::close`],
+    /// then waits for all the async handlers still running to complete.
+    ///
+    /// The [`CancellationToken`](crate::extract::CancellationToken) of each socket is cancelled when it is closed,
+    /// so the long running handlers can stop early. Otherwise this may wait forever,
+    /// it can be bounded with [`tokio::time::timeout`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// # async fn doc() {
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("save", |token: CancellationToken| async move {
+    ///         tokio::select! {
+    ///             _ = token.cancelled() => println!("save interrupted"),
+    ///             _ = tokio::time::sleep(Duration::from_secs(1)) => println!("saved"),
+    ///         }
+    ///     });
+    /// });
+    ///
+    /// tokio::time::timeout(Duration::from_secs(5), io.close_and_wait()).await.ok();
+    /// # }
+    /// ```
+    pub async fn close_and_wait(&self) {
+        self.0.close().await;
+        self.0.wait_handlers().await;
+    }
+
     // Chaining operators fns
 
     /// Selects a specific namespace to perform operations on
@@ -473,6 +910,55 @@ impl<A: Adapter> SocketIo<A> {
         self.get_default_op().except(rooms)
     }
 
+    /// Selects the sockets with the given ids on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().to_sockets(sids)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    ///
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::SocketRef};
+    /// # use socketioxide::socket::Sid;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     println!("Socket connected on / namespace with id: {}", socket.id);
+    /// });
+    ///
+    /// // Later in your code you can send a message to specific sockets
+    /// // without making them join a room
+    /// let sids: Vec<Sid> = vec![];
+    /// io.to_sockets(sids).emit("hello", "world").ok();
+    #[inline]
+    pub fn to_sockets(&self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        self.get_default_op().to_sockets(sids)
+    }
+
+    /// Filters out the sockets with the given ids on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().except_sockets(sids)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    ///
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::SocketRef};
+    /// # use socketioxide::socket::Sid;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     println!("Socket connected on / namespace with id: {}", socket.id);
+    /// });
+    ///
+    /// // Later in your code you can send a message to all sockets except some of them
+    /// let muted: Vec<Sid> = vec![];
+    /// io.except_sockets(muted).emit("hello", "world").ok();
+    #[inline]
+    pub fn except_sockets(&self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        self.get_default_op().except_sockets(sids)
+    }
+
     /// Broadcasts to all sockets only connected on this node (when using multiple nodes).
     /// When using the default in-memory adapter, this operator is a no-op.
     ///
@@ -721,6 +1207,32 @@ impl<A: Adapter> SocketIo<A> {
         self.get_default_op().disconnect()
     }
 
+    /// Disconnects all sockets selected with the previous operators with a custom reason.
+    ///
+    /// Alias for `io.of("/").unwrap().disconnect_with(code, message)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    ///
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::SocketRef};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     println!("Socket connected on / namespace with id: {}", socket.id);
+    /// });
+    ///
+    /// // Later in your code you can disconnect all sockets in the root namespace
+    /// io.disconnect_with(4003, "server maintenance");
+    #[inline]
+    pub fn disconnect_with(
+        &self,
+        code: u16,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Result<(), Vec<DisconnectError>> {
+        self.get_default_op().disconnect_with(code, message)
+    }
+
     /// Makes all sockets selected with the previous operators join the given room(s).
     ///
     /// Alias for `io.of("/").unwrap().join(rooms)`
@@ -764,6 +1276,91 @@ impl<A: Adapter> SocketIo<A> {
         self.get_default_op().rooms()
     }
 
+    /// Gets the number of sockets in the given room on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().room_len(room)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    ///
+    /// ### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::SocketRef};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| socket.join("lobby").unwrap());
+    ///
+    /// // Later in your code you can get the size of a room without fetching its sockets
+    /// let count = io.room_len("lobby").unwrap();
+    #[inline]
+    pub fn room_len(&self, room: impl Into<Room>) -> Result<usize, A::Error> {
+        self.get_default_op().room_len(room)
+    }
+
+    /// Returns true if at least one socket is in the given room on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().room_exists(room)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    #[inline]
+    pub fn room_exists(&self, room: impl Into<Room>) -> Result<bool, A::Error> {
+        self.get_default_op().room_exists(room)
+    }
+
+    /// Gets all the non-empty rooms whose name starts with the given prefix on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().rooms_with_prefix(prefix)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    #[inline]
+    pub fn rooms_with_prefix(&self, prefix: &str) -> Result<Vec<Room>, A::Error> {
+        self.get_default_op().rooms_with_prefix(prefix)
+    }
+
+    /// Gets a clone of the metadata of type `T` attached to the given room on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().room_meta(room)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    #[inline]
+    pub fn room_meta<T>(&self, room: impl Into<Room>) -> Result<Option<T>, A::Error>
+    where
+        T: Clone + serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.get_default_op().room_meta(room)
+    }
+
+    /// Attaches metadata of type `T` to the given room on the root namespace.
+    /// It returns `false` if the room is empty.
+    ///
+    /// Alias for `io.of("/").unwrap().set_room_meta(room, value)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    #[inline]
+    pub fn set_room_meta<T>(&self, room: impl Into<Room>, value: T) -> Result<bool, A::Error>
+    where
+        T: Clone + serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.get_default_op().set_room_meta(room, value)
+    }
+
+    /// Removes the metadata of type `T` from the given room on the root namespace.
+    ///
+    /// Alias for `io.of("/").unwrap().remove_room_meta(room)`
+    ///
+    /// ## Panics
+    /// If the **default namespace "/" is not found** this fn will panic!
+    #[inline]
+    pub fn remove_room_meta<T>(&self, room: impl Into<Room>) -> Result<Option<T>, A::Error>
+    where
+        T: Clone + serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.get_default_op().remove_room_meta(room)
+    }
+
     /// Makes all sockets selected with the previous operators leave the given room(s).
     ///
     /// Alias for `io.of("/").unwrap().join(rooms)`
@@ -869,7 +1466,14 @@ mod tests {
         let config = SocketIoConfig::default().into();
         io.0.get_ns("/")
             .unwrap()
-            .connect(sid, socket, None, config)
+            .connect(
+                socket,
+                None,
+                config,
+                Vec::new(),
+                EventService::new(),
+                Default::default(),
+            )
             .await
             .ok();
 
@@ -133,11 +133,14 @@
 //! They are greatly inspired by the axum handlers.
 //!
 //! If they are async, a new task will be spawned for each incoming connection/message so it doesn't block the event management task.
+//! These tasks keep running after the socket is disconnected: they can watch the [`CancellationToken`](extract::CancellationToken)
+//! of the socket to stop early, and [`SocketIo::close_and_wait`] waits for all of them when shutting down.
 //!
 //! * Check the [`handler::connect`] module doc for more details on the connect handler and connect middlewares.
 //! * Check the [`handler::message`] module doc for more details on the message handler.
 //! * Check the [`handler::disconnect`] module doc for more details on the disconnect handler.
 //! * Check the [`handler::extract`] module doc for more details on the extractors.
+//! * Check the [`handler::service`] module doc for more details on wrapping the message handlers with [`tower`] layers.
 //!
 //! ## Extractors
 //! Handlers params are called extractors and are used to extract data from the incoming connection/message. They are inspired by the axum extractors.
@@ -158,7 +161,9 @@
 //! * [`ProtocolVersion`]: extracts the protocol version of the socket
 //! * [`TransportType`]: extracts the transport type of the socket
 //! * [`DisconnectReason`](crate::socket::DisconnectReason): extracts the reason of the disconnection
+//! * [`CancellationToken`](extract::CancellationToken): extracts a token cancelled when the socket is disconnected
 //! * [`State`](extract::State): extracts a reference to a state previously set with [`SocketIoBuilder::with_state`](crate::io::SocketIoBuilder).
+//! * [`MiddlewareValue`](extract::MiddlewareValue): extracts a value returned by a previous connect middleware.
 //! ### Extractor order
 //! Extractors are run in the order of their declaration in the handler signature. If an extractor returns an error, the handler won't be called and a `tracing::error!` call will be emitted if the `tracing` feature is enabled.
 //!
@@ -177,12 +182,16 @@
 //! ## Middlewares
 //! When providing a [`ConnectHandler`](handler::ConnectHandler) for a namespace you can add any number of
 //! [`ConnectMiddleware`](handler::ConnectMiddleware) in front of it. It is useful to add authentication or logging middlewares.
+//! Middlewares shared by all the namespaces can be registered once with [`SocketIo::use_middleware`].
 //!
-//! A middleware *must* return a `Result<(), E> where E: Display`.
-//! * If the result is `Ok(())`, the next middleware is called or if there is no more middleware,
+//! A middleware *must* return a `Result<T, E> where E: Display`.
+//! * If the result is `Ok`, the next middleware is called or if there is no more middleware,
 //! the socket is connected and the [`ConnectHandler`](handler::ConnectHandler) is called.
+//!   The returned value can be retrieved by the next middlewares and the handler with the
+//!   [`MiddlewareValue`](extract::MiddlewareValue) extractor.
 //! * If the result is an error, the namespace connection will be refused and the error will be returned with a
 //! [`connect_error` event and a `message`](https://socket.io/docs/v4/middlewares/#handling-middleware-error) field with the error.
+//!   A [`ConnectError`] can be returned to also send a `data` field.
 //!
 //! <div class="warning">
 //!     Because the socket is not yet connected to the namespace,
@@ -229,6 +238,9 @@
 //! You can send an ack response with an optional binary payload with the [`AckSender::send`](extract::AckSender) method.
 //! If the client doesn't send an ack response, the [`AckSender::send`](extract::AckSender) method will do nothing.
 //!
+//! Handlers can also return a value implementing [`IntoAck`](handler::IntoAck), like a `Result<T, E>`,
+//! which is automatically sent as the ack response.
+//!
 //! #### Client acknowledgements
 //! If you want to emit/broadcast a message and await for a/many client(s) acknowledgment(s) you can use:
 //! * [`SocketRef::emit_with_ack`] for a single client
@@ -271,6 +283,7 @@
 //! * `tracing`: enable logging with [`tracing`] calls
 //! * `extensions`: enable per-socket state with the [`extensions`] module
 //! * `state`: enable global state management
+//! * `webtransport`: enable the engine.io WebTransport transport, see [`SocketIoService::serve_webtransport`](service::SocketIoService::serve_webtransport)
 //!
 pub mod adapter;
 
@@ -282,18 +295,29 @@ mod state;
 
 pub mod ack;
 pub mod handler;
+pub mod interceptor;
 pub mod layer;
 pub mod operators;
 pub mod packet;
 pub mod service;
 pub mod socket;
 
-pub use engineioxide::TransportType;
-pub use errors::{AckError, AdapterError, BroadcastError, DisconnectError, SendError, SocketError};
+pub use dispatch::DispatchMode;
+pub use engineioxide::{
+    config::{CookieConfig, CorsConfig},
+    stats::SocketStats,
+    TransportType,
+};
+pub use errors::{
+    AckError, AdapterError, BinStreamError, BroadcastError, ConnectError, DisconnectError,
+    SendError, SocketError,
+};
 pub use handler::extract;
-pub use io::{SocketIo, SocketIoBuilder, SocketIoConfig};
+pub use io::{HeadersHook, SocketIo, SocketIoBuilder, SocketIoConfig};
 
+mod bin_stream;
 mod client;
+mod dispatch;
 mod errors;
 mod io;
 mod ns;
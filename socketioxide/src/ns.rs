@@ -6,20 +6,28 @@ use std::{
 
 use crate::{
     adapter::Adapter,
+    dispatch::DispatchMode,
     errors::{ConnectFail, Error},
-    handler::{BoxedConnectHandler, ConnectHandler, MakeErasedHandler},
+    handler::{
+        BoxedConnectHandler, BoxedConnectMiddleware, ConnectHandler, ErrorHandler, EventService,
+        MakeErasedHandler,
+    },
     packet::{Packet, PacketData},
     socket::{DisconnectReason, Socket},
     SocketIoConfig,
 };
 use crate::{client::SocketData, errors::AdapterError};
 use engineioxide::sid::Sid;
+use tokio_util::task::TaskTracker;
 
 pub struct Namespace<A: Adapter> {
     pub path: Cow<'static, str>,
     pub(crate) adapter: A,
     handler: BoxedConnectHandler<A>,
     sockets: RwLock<HashMap<Sid, Arc<Socket<A>>>>,
+    error_handler: RwLock<Option<Arc<dyn ErrorHandler>>>,
+    /// Overrides the global [`DispatchMode`] for the sockets of this namespace
+    dispatch_mode: RwLock<Option<DispatchMode>>,
 }
 
 impl<A: Adapter> Namespace<A> {
@@ -32,31 +40,47 @@ impl<A: Adapter> Namespace<A> {
             path,
             handler: MakeErasedHandler::new_ns_boxed(handler),
             sockets: HashMap::new().into(),
+            error_handler: RwLock::new(None),
+            dispatch_mode: RwLock::new(None),
             adapter: A::new(ns.clone()),
         })
     }
 
     /// Connects a socket to a namespace.
     ///
-    /// Middlewares are first called to check if the connection is allowed.
+    /// The global middlewares and then the namespace middlewares are first called to check if the connection is allowed.
     /// * If the handler returns an error, a connect_error packet is sent to the client.
     /// * If the handler returns Ok, a connect packet is sent to the client
     /// and the handler is called.
     pub(crate) async fn connect(
         self: Arc<Self>,
-        sid: Sid,
         esocket: Arc<engineioxide::Socket<SocketData>>,
         auth: Option<String>,
         config: Arc<SocketIoConfig>,
+        middlewares: Vec<BoxedConnectMiddleware<A>>,
+        event_service: EventService<A>,
+        tasks: TaskTracker,
     ) -> Result<(), ConnectFail> {
-        let socket: Arc<Socket<A>> = Socket::new(sid, self.clone(), esocket.clone(), config).into();
-
-        if let Err(e) = self.handler.call_middleware(socket.clone(), &auth).await {
+        let sid = esocket.id;
+        let socket: Arc<Socket<A>> =
+            Socket::new(sid, self.clone(), esocket.clone(), config, tasks).into();
+        socket.set_event_service(event_service);
+
+        let res = async {
+            for middleware in &middlewares {
+                middleware.call(socket.clone(), &auth).await?;
+            }
+            self.handler.call_middleware(socket.clone(), &auth).await
+        };
+        if let Err(e) = res.await {
             #[cfg(feature = "tracing")]
             tracing::trace!(ns = self.path.as_ref(), ?socket.id, "emitting connect_error packet");
 
-            let data = e.to_string();
-            if let Err(_e) = socket.send(Packet::connect_error(&self.path, &data)) {
+            let packet = match e.data() {
+                Some(data) => Packet::connect_error_with_data(&self.path, e.message(), data),
+                None => Packet::connect_error(&self.path, e.message()),
+            };
+            if let Err(_e) = socket.send(packet) {
                 #[cfg(feature = "tracing")]
                 tracing::debug!("error sending connect_error packet: {:?}, closing conn", _e);
                 esocket.close(engineioxide::DisconnectReason::PacketParsingError);
@@ -78,7 +102,9 @@ impl<A: Adapter> Namespace<A> {
         }
 
         socket.set_connected(true);
-        self.handler.call(socket, auth);
+        self.handler.call(socket.clone(), auth);
+        // The middleware values are only available to the middlewares and the connect handler
+        socket.clear_middleware_values();
 
         Ok(())
     }
@@ -94,6 +120,22 @@ impl<A: Adapter> Namespace<A> {
             .map_err(|err| AdapterError(Box::new(err)))
     }
 
+    pub fn set_error_handler(&self, handler: Arc<dyn ErrorHandler>) {
+        self.error_handler.write().unwrap().replace(handler);
+    }
+
+    pub fn error_handler(&self) -> Option<Arc<dyn ErrorHandler>> {
+        self.error_handler.read().unwrap().clone()
+    }
+
+    pub fn set_dispatch_mode(&self, mode: DispatchMode) {
+        self.dispatch_mode.write().unwrap().replace(mode);
+    }
+
+    pub fn dispatch_mode(&self) -> Option<DispatchMode> {
+        *self.dispatch_mode.read().unwrap()
+    }
+
     pub fn has(&self, sid: Sid) -> bool {
         self.sockets.read().unwrap().values().any(|s| s.id == sid)
     }
@@ -139,11 +181,8 @@ impl<A: Adapter> Namespace<A> {
             future::join_all(sockets.values().map(|s| s.close_underlying_transport())).await;
         } else {
             for s in sockets.into_values() {
-                let _sid = s.id;
-                let _err = s.close(reason);
-                #[cfg(feature = "tracing")]
-                if let Err(err) = _err {
-                    tracing::debug!(?_sid, ?err, "error closing socket");
+                if let Err(err) = s.clone().close(reason.clone()) {
+                    s.report_error(None, None, err.into());
                 }
             }
         }
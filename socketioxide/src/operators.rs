@@ -11,22 +11,25 @@ use std::{sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use engineioxide::sid::Sid;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::ack::{AckInnerStream, AckStream};
 use crate::adapter::LocalAdapter;
-use crate::errors::{BroadcastError, DisconnectError};
+use crate::errors::{AdapterError, BroadcastError, DisconnectError, SocketError};
 use crate::extract::SocketRef;
 use crate::socket::Socket;
 use crate::SendError;
 use crate::{
     adapter::{Adapter, BroadcastFlags, BroadcastOptions, Room},
     ns::Namespace,
-    packet::Packet,
+    packet::EncodedPacket,
 };
 
 /// A trait for types that can be used as a room parameter.
 ///
 /// [`String`], [`Vec<String>`], [`Vec<&str>`], [`&'static str`](str) and const arrays are implemented by default.
+/// Integer rooms can be given as [`u64`], [`Vec<u64>`] or `[u64; N]`
+/// and tagged rooms as `(&'static str, u64)` or `(String, u64)`, see [`Room`].
 pub trait RoomParam: 'static {
     /// The type of the iterator returned by `into_room_iter`.
     type IntoIter: Iterator<Item = Room>;
@@ -42,25 +45,32 @@ impl RoomParam for Room {
         std::iter::once(self)
     }
 }
+impl RoomParam for Cow<'static, str> {
+    type IntoIter = std::iter::Once<Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        std::iter::once(Room::Str(self))
+    }
+}
 impl RoomParam for String {
     type IntoIter = std::iter::Once<Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        std::iter::once(Cow::Owned(self))
+        std::iter::once(Room::from(self))
     }
 }
 impl RoomParam for Vec<String> {
     type IntoIter = std::iter::Map<std::vec::IntoIter<String>, fn(String) -> Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        self.into_iter().map(Cow::Owned)
+        self.into_iter().map(Room::from)
     }
 }
 impl RoomParam for Vec<&'static str> {
     type IntoIter = std::iter::Map<std::vec::IntoIter<&'static str>, fn(&'static str) -> Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        self.into_iter().map(Cow::Borrowed)
+        self.into_iter().map(Room::from)
     }
 }
 
@@ -75,7 +85,7 @@ impl RoomParam for &'static str {
     type IntoIter = std::iter::Once<Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        std::iter::once(Cow::Borrowed(self))
+        std::iter::once(Room::from(self))
     }
 }
 impl<const COUNT: usize> RoomParam for [&'static str; COUNT] {
@@ -84,21 +94,64 @@ impl<const COUNT: usize> RoomParam for [&'static str; COUNT] {
 
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        self.into_iter().map(Cow::Borrowed)
+        self.into_iter().map(Room::from)
     }
 }
 impl<const COUNT: usize> RoomParam for [String; COUNT] {
     type IntoIter = std::iter::Map<std::array::IntoIter<String, COUNT>, fn(String) -> Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        self.into_iter().map(Cow::Owned)
+        self.into_iter().map(Room::from)
     }
 }
 impl RoomParam for Sid {
     type IntoIter = std::iter::Once<Room>;
     #[inline(always)]
     fn into_room_iter(self) -> Self::IntoIter {
-        std::iter::once(Cow::Owned(self.to_string()))
+        std::iter::once(Room::from(self))
+    }
+}
+impl RoomParam for u64 {
+    type IntoIter = std::iter::Once<Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        std::iter::once(Room::Int(self))
+    }
+}
+impl RoomParam for (&'static str, u64) {
+    type IntoIter = std::iter::Once<Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        std::iter::once(Room::from(self))
+    }
+}
+impl RoomParam for (String, u64) {
+    type IntoIter = std::iter::Once<Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        std::iter::once(Room::from(self))
+    }
+}
+impl RoomParam for Vec<u64> {
+    type IntoIter = std::iter::Map<std::vec::IntoIter<u64>, fn(u64) -> Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        self.into_iter().map(Room::Int)
+    }
+}
+impl<const COUNT: usize> RoomParam for [u64; COUNT] {
+    type IntoIter = std::iter::Map<std::array::IntoIter<u64, COUNT>, fn(u64) -> Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        self.into_iter().map(Room::Int)
+    }
+}
+impl RoomParam for Vec<(&'static str, u64)> {
+    type IntoIter =
+        std::iter::Map<std::vec::IntoIter<(&'static str, u64)>, fn((&'static str, u64)) -> Room>;
+    #[inline(always)]
+    fn into_room_iter(self) -> Self::IntoIter {
+        self.into_iter().map(Room::from)
     }
 }
 
@@ -212,6 +265,20 @@ impl<'a, A: Adapter> ConfOperators<'a, A> {
         BroadcastOperators::from(self).except(rooms)
     }
 
+    /// Selects the sockets with the given ids.
+    ///
+    /// Contrary to the `to()` operator, the current socket is not excluded if its id is given.
+    /// See [`BroadcastOperators::to_sockets`].
+    pub fn to_sockets(self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        BroadcastOperators::from(self).to_sockets(sids)
+    }
+
+    /// Filters out the sockets with the given ids from the sockets selected with the previous operators.
+    /// See [`BroadcastOperators::except_sockets`].
+    pub fn except_sockets(self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        BroadcastOperators::from(self).except_sockets(sids)
+    }
+
     /// Broadcasts to all sockets only connected on this node (when using multiple nodes).
     /// When using the default in-memory adapter, this operator is a no-op.
     /// #### Example
@@ -310,6 +377,7 @@ impl<A: Adapter> ConfOperators<'_, A> {
     /// If you provide array-like data (tuple, vec, arrays), it will be considered as multiple arguments.
     /// Therefore if you want to send an array as the _first_ argument of the payload,
     /// you need to wrap it in an array or a tuple.
+    /// The [`Args`](crate::packet::Args) wrapper can also be used to always send each element as an argument.
     ///
     /// ## Errors
     /// * When encoding the data into JSON a [`SendError::Serialize`] may be returned.
@@ -346,8 +414,6 @@ impl<A: Adapter> ConfOperators<'_, A> {
         event: impl Into<Cow<'static, str>>,
         data: T,
     ) -> Result<(), SendError<T>> {
-        use crate::errors::SocketError;
-        use crate::socket::PermitExt;
         if !self.socket.connected() {
             return Err(SendError::Socket(SocketError::Closed(data)));
         }
@@ -365,6 +431,40 @@ impl<A: Adapter> ConfOperators<'_, A> {
         Ok(())
     }
 
+    /// Emits a message to the client and apply the previous operators on the message,
+    /// waiting at most `timeout` for space in the socket buffer if it is full.
+    ///
+    /// See [`Socket::emit_async`] for more details.
+    ///
+    /// ## Errors
+    /// The errors are the same as for [`ConfOperators::emit`],
+    /// except that a [`SendError::Socket(SocketError::InternalChannelFull)`] is only returned
+    /// if the buffer is still full after the timeout.
+    ///
+    /// [`SendError::Socket(SocketError::InternalChannelFull)`]: crate::SocketError::InternalChannelFull
+    pub async fn emit_async<T: serde::Serialize>(
+        mut self,
+        event: impl Into<Cow<'static, str>>,
+        data: T,
+        timeout: Duration,
+    ) -> Result<(), SendError<T>> {
+        if !self.socket.connected() {
+            return Err(SendError::Socket(SocketError::Closed(data)));
+        }
+        let permit = match self.socket.reserve_async(timeout).await {
+            Ok(permit) => permit,
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("sending error during emit message: {e:?}");
+                return Err(e.with_value(data).into());
+            }
+        };
+        let packet = self.get_packet(event, data)?;
+        permit.send(packet);
+
+        Ok(())
+    }
+
     /// Emits a message to the client and wait for acknowledgement.
     ///
     /// The acknowledgement has a timeout specified in the config (5s by default)
@@ -421,7 +521,6 @@ impl<A: Adapter> ConfOperators<'_, A> {
         event: impl Into<Cow<'static, str>>,
         data: T,
     ) -> Result<AckStream<V>, SendError<T>> {
-        use crate::errors::SocketError;
         if !self.socket.connected() {
             return Err(SendError::Socket(SocketError::Closed(data)));
         }
@@ -435,7 +534,7 @@ impl<A: Adapter> ConfOperators<'_, A> {
         };
         let timeout = self.timeout.unwrap_or(self.socket.config.ack_timeout);
         let packet = self.get_packet(event, data)?;
-        let rx = self.socket.send_with_ack_permit(packet, permit);
+        let rx = self.socket.send_with_ack_permit(&packet, permit);
         let stream = AckInnerStream::send(rx, timeout, self.socket.id);
         Ok(AckStream::<V>::from(stream))
     }
@@ -482,16 +581,9 @@ impl<A: Adapter> ConfOperators<'_, A> {
         &mut self,
         event: impl Into<Cow<'static, str>>,
         data: impl serde::Serialize,
-    ) -> Result<Packet<'static>, serde_json::Error> {
-        let ns = self.socket.ns.path.clone();
-        let data = serde_json::to_value(data)?;
-        let packet = if self.binary.is_empty() {
-            Packet::event(ns, event.into(), data)
-        } else {
-            let binary = std::mem::take(&mut self.binary);
-            Packet::bin_event(ns, event.into(), data, binary)
-        };
-        Ok(packet)
+    ) -> Result<EncodedPacket, serde_json::Error> {
+        let binary = std::mem::take(&mut self.binary);
+        EncodedPacket::event(&self.socket.ns.path, &event.into(), &data, binary)
     }
 }
 
@@ -590,6 +682,47 @@ impl<A: Adapter> BroadcastOperators<A> {
         self.broadcast()
     }
 
+    /// Selects the sockets with the given ids, in addition to the sockets selected with the previous operators.
+    ///
+    /// The current socket is selected if its id is given, unless the `to()`, `except()` or `broadcast()`
+    /// operators are also used.
+    /// #### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use socketioxide::socket::Sid;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("invite", |socket: SocketRef, Data::<Vec<Sid>>(sids)| async move {
+    ///         // Sent to the invited sockets and to the other sockets in room1
+    ///         socket.to("room1").to_sockets(sids).emit("invited", socket.id).ok();
+    ///     });
+    /// });
+    pub fn to_sockets(mut self, sids: impl IntoIterator<Item = Sid>) -> Self {
+        self.opts
+            .sids
+            .get_or_insert_with(Default::default)
+            .extend(sids);
+        self
+    }
+
+    /// Filters out the sockets with the given ids from the sockets selected with the previous operators.
+    /// #### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use socketioxide::socket::Sid;
+    /// # use serde_json::Value;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("message", |socket: SocketRef, Data::<(Vec<Sid>, Value)>((muted, data))| async move {
+    ///         // Sent to all sockets except the current one and the muted ones
+    ///         socket.broadcast().except_sockets(muted).emit("message", data).ok();
+    ///     });
+    /// });
+    pub fn except_sockets(mut self, sids: impl IntoIterator<Item = Sid>) -> Self {
+        self.opts.except_sids.extend(sids);
+        self.broadcast()
+    }
+
     /// Broadcasts to all sockets only connected on this node (when using multiple nodes).
     /// When using the default in-memory adapter, this operator is a no-op.
     /// #### Example
@@ -690,6 +823,7 @@ impl<A: Adapter> BroadcastOperators<A> {
     /// If you provide array-like data (tuple, vec, arrays), it will be considered as multiple arguments.
     /// Therefore if you want to send an array as the _first_ argument of the payload,
     /// you need to wrap it in an array or a tuple.
+    /// The [`Args`](crate::packet::Args) wrapper can also be used to always send each element as an argument.
     ///
     /// ## Errors
     /// * When encoding the data into JSON a [`BroadcastError::Serialize`] may be returned.
@@ -737,6 +871,66 @@ impl<A: Adapter> BroadcastOperators<A> {
         Ok(())
     }
 
+    /// Emits a message to all sockets selected with the previous operators,
+    /// waiting at most `timeout` for space in the buffer of each socket if it is full.
+    ///
+    /// The sockets are awaited concurrently, so a slow client does not delay the others.
+    /// Only the sockets of this server are awaited: with a remote adapter,
+    /// the other servers are not reached, use [`BroadcastOperators::emit`] for them.
+    ///
+    /// See [`Socket::emit_async`](crate::socket::Socket::emit_async) for more details.
+    ///
+    /// ## Errors
+    /// The errors are the same as for [`BroadcastOperators::emit`],
+    /// except that a [`BroadcastError::Socket(SocketError::InternalChannelFull)`] is only returned
+    /// for a socket if its buffer is still full after the timeout.
+    ///
+    /// > **Note**: If a error is returned because of a specific socket, the message will still be sent to all other sockets.
+    ///
+    /// [`BroadcastError::Socket(SocketError::InternalChannelFull)`]: crate::SocketError::InternalChannelFull
+    ///
+    /// #### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| async move {
+    ///     for i in 0..1000 {
+    ///         socket.within("room1").emit_async("tick", i, Duration::from_secs(1)).await.ok();
+    ///     }
+    /// });
+    /// ```
+    pub async fn emit_async<T: serde::Serialize>(
+        mut self,
+        event: impl Into<Cow<'static, str>>,
+        data: T,
+        timeout: Duration,
+    ) -> Result<(), BroadcastError> {
+        let packet = self.get_packet(event, data)?;
+        let sockets = self
+            .ns
+            .adapter
+            .fetch_sockets(self.opts)
+            .map_err(Into::<AdapterError>::into)?;
+        let sends = sockets.iter().map(|socket| async {
+            let permit = socket.reserve_async(timeout).await?;
+            permit.send(packet.clone());
+            Ok(())
+        });
+        let errors: Vec<SocketError<()>> = futures_util::future::join_all(sends)
+            .await
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("broadcast error: {errors:?}");
+            Err(errors.into())
+        }
+    }
+
     /// Emits a message to all sockets selected with the previous operators and
     /// waits for the acknowledgement(s).
     ///
@@ -846,6 +1040,30 @@ impl<A: Adapter> BroadcastOperators<A> {
         self.ns.adapter.disconnect_socket(self.opts)
     }
 
+    /// Disconnects all sockets selected with the previous operators with a custom reason.
+    ///
+    /// See [`Socket::disconnect_with`](crate::socket::Socket::disconnect_with) for more details.
+    ///
+    /// ### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///   socket.on("login", |socket: SocketRef| async move {
+    ///     // Disconnect the other sessions of the user
+    ///     socket.to("user:1").disconnect_with(4002, "duplicate login").unwrap();
+    ///   });
+    /// });
+    pub fn disconnect_with(
+        self,
+        code: u16,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Result<(), Vec<DisconnectError>> {
+        self.ns
+            .adapter
+            .disconnect_socket_with(self.opts, code, message.into())
+    }
+
     /// Makes all sockets selected with the previous operators join the given room(s).
     ///
     /// ### Example
@@ -883,6 +1101,87 @@ impl<A: Adapter> BroadcastOperators<A> {
         self.ns.adapter.rooms()
     }
 
+    /// Gets the number of sockets in the given room, without fetching the sockets themselves.
+    ///
+    /// ### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///   socket.on("test", |socket: SocketRef| async move {
+    ///     let count = socket.broadcast().room_len("room1").unwrap();
+    ///     println!("room1 has {} members", count);
+    ///   });
+    /// });
+    pub fn room_len(&self, room: impl Into<Room>) -> Result<usize, A::Error> {
+        self.ns.adapter.room_len(&room.into())
+    }
+
+    /// Returns true if at least one socket is in the given room.
+    pub fn room_exists(&self, room: impl Into<Room>) -> Result<bool, A::Error> {
+        self.ns.adapter.room_exists(&room.into())
+    }
+
+    /// Gets all the non-empty rooms whose name starts with the given prefix.
+    ///
+    /// ### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///   socket.on("games", |socket: SocketRef| async move {
+    ///     let games = socket.broadcast().rooms_with_prefix("game:").unwrap();
+    ///     socket.emit("games", games).ok();
+    ///   });
+    /// });
+    pub fn rooms_with_prefix(&self, prefix: &str) -> Result<Vec<Room>, A::Error> {
+        self.ns.adapter.rooms_with_prefix(prefix)
+    }
+
+    /// Gets a clone of the metadata of type `T` attached to the given room.
+    ///
+    /// ### Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    /// struct Topic(String);
+    ///
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///   socket.join("room1").unwrap();
+    ///   socket.broadcast().set_room_meta("room1", Topic("rust".into())).unwrap();
+    ///   socket.on("topic", |socket: SocketRef| async move {
+    ///     if let Some(Topic(topic)) = socket.broadcast().room_meta::<Topic>("room1").unwrap() {
+    ///       socket.emit("topic", topic).ok();
+    ///     }
+    ///   });
+    /// });
+    pub fn room_meta<T>(&self, room: impl Into<Room>) -> Result<Option<T>, A::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.ns.adapter.room_meta(&room.into())
+    }
+
+    /// Attaches metadata of type `T` to the given room.
+    ///
+    /// It returns `false` if the room is empty. The metadata is automatically removed
+    /// when the last socket leaves the room.
+    pub fn set_room_meta<T>(&self, room: impl Into<Room>, value: T) -> Result<bool, A::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.ns.adapter.set_room_meta(&room.into(), value)
+    }
+
+    /// Removes the metadata of type `T` from the given room and returns it.
+    pub fn remove_room_meta<T>(&self, room: impl Into<Room>) -> Result<Option<T>, A::Error>
+    where
+        T: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.ns.adapter.remove_room_meta(&room.into())
+    }
+
     /// Gets a [`SocketRef`] by the specified [`Sid`].
     pub fn get_socket(&self, sid: Sid) -> Option<SocketRef<A>> {
         self.ns.get_socket(sid).map(SocketRef::from).ok()
@@ -893,15 +1192,8 @@ impl<A: Adapter> BroadcastOperators<A> {
         &mut self,
         event: impl Into<Cow<'static, str>>,
         data: impl serde::Serialize,
-    ) -> Result<Packet<'static>, serde_json::Error> {
-        let ns = self.ns.path.clone();
-        let data = serde_json::to_value(data)?;
-        let packet = if self.binary.is_empty() {
-            Packet::event(ns, event.into(), data)
-        } else {
-            let binary = std::mem::take(&mut self.binary);
-            Packet::bin_event(ns, event.into(), data, binary)
-        };
-        Ok(packet)
+    ) -> Result<EncodedPacket, serde_json::Error> {
+        let binary = std::mem::take(&mut self.binary);
+        EncodedPacket::event(&self.ns.path, &event.into(), &data, binary)
     }
 }
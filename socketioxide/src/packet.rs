@@ -5,8 +5,12 @@ use std::borrow::Cow;
 
 use crate::ProtocolVersion;
 use bytes::Bytes;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde::{
+    de::{self, value::SeqDeserializer, DeserializeOwned, IgnoredAny},
+    ser::{self, Impossible},
+    Deserialize, Serialize, Serializer,
+};
+use serde_json::{json, value::RawValue, Value};
 
 use crate::errors::Error;
 use engineioxide::{sid::Sid, Str};
@@ -70,6 +74,15 @@ impl<'a> Packet<'a> {
 }
 
 impl<'a> Packet<'a> {
+    /// Create a connect error packet for the given namespace with a message and additional data
+    pub fn connect_error_with_data(ns: &'a str, message: &str, data: &Value) -> Self {
+        let packet = serde_json::json!({ "message": message, "data": data }).to_string();
+        Self {
+            inner: PacketData::ConnectError(packet),
+            ns: Cow::Borrowed(ns),
+        }
+    }
+
     /// Create a connect error packet for the given namespace with a message
     pub fn connect_error(ns: &'a str, message: &str) -> Self {
         let message = serde_json::to_string(message).unwrap();
@@ -82,8 +95,11 @@ impl<'a> Packet<'a> {
 
     /// Create an event packet for the given namespace
     pub fn event(ns: impl Into<Cow<'a, str>>, e: impl Into<Cow<'a, str>>, data: Value) -> Self {
+        let e = e.into();
+        // Value serialization cannot fail
+        let payload = RawPayload::event(&e, &data, 0).unwrap();
         Self {
-            inner: PacketData::Event(e.into(), data, None),
+            inner: PacketData::Event(e, payload, None),
             ns: ns.into(),
         }
     }
@@ -97,9 +113,16 @@ impl<'a> Packet<'a> {
     ) -> Self {
         debug_assert!(!bin.is_empty());
 
-        let packet = BinaryPacket::outgoing(data, bin);
+        let e = e.into();
+        // Value serialization cannot fail
+        let payload = RawPayload::event(&e, &data, bin.len()).unwrap();
+        let packet = BinaryPacket {
+            data: payload,
+            payload_count: bin.len(),
+            bin,
+        };
         Self {
-            inner: PacketData::BinaryEvent(e.into(), packet, None),
+            inner: PacketData::BinaryEvent(e, packet, None),
             ns: ns.into(),
         }
     }
@@ -126,6 +149,7 @@ impl<'a> Packet<'a> {
     /// This is used to pre-allocate a buffer for the packet
     ///
     /// #### Disclaimer: The size does not include serialized `Value` size
+    /// (event payloads are already serialized and therefore included)
     fn get_size_hint(&self) -> usize {
         use PacketData::*;
         const PACKET_INDEX_SIZE: usize = 1;
@@ -137,15 +161,18 @@ impl<'a> Packet<'a> {
             Connect(Some(data)) => data.len(),
             Connect(None) => 0,
             Disconnect => 0,
-            Event(_, _, Some(ack)) => {
-                ack.checked_ilog10().unwrap_or(0) as usize + ACK_PUNCTUATION_SIZE
+            Event(_, data, Some(ack)) => {
+                data.len() + ack.checked_ilog10().unwrap_or(0) as usize + ACK_PUNCTUATION_SIZE
             }
-            Event(_, _, None) => 0,
+            Event(_, data, None) => data.len(),
             BinaryEvent(_, bin, None) => {
-                bin.payload_count.checked_ilog10().unwrap_or(0) as usize + BINARY_PUNCTUATION_SIZE
+                bin.data.len()
+                    + bin.payload_count.checked_ilog10().unwrap_or(0) as usize
+                    + BINARY_PUNCTUATION_SIZE
             }
             BinaryEvent(_, bin, Some(ack)) => {
-                ack.checked_ilog10().unwrap_or(0) as usize
+                bin.data.len()
+                    + ack.checked_ilog10().unwrap_or(0) as usize
                     + bin.payload_count.checked_ilog10().unwrap_or(0) as usize
                     + ACK_PUNCTUATION_SIZE
                     + BINARY_PUNCTUATION_SIZE
@@ -187,26 +214,26 @@ pub enum PacketData<'a> {
     /// Disconnect packet, used to disconnect from a namespace
     Disconnect,
     /// Event packet with optional ack id, to request an ack from the other side
-    Event(Cow<'a, str>, Value, Option<i64>),
+    Event(Cow<'a, str>, RawPayload, Option<i64>),
     /// Event ack packet, to acknowledge an event
     EventAck(Value, i64),
     /// Connect error packet, sent when the namespace is invalid
     ConnectError(String),
     /// Binary event packet with optional ack id, to request an ack from the other side
-    BinaryEvent(Cow<'a, str>, BinaryPacket, Option<i64>),
+    BinaryEvent(Cow<'a, str>, BinaryPacket<RawPayload>, Option<i64>),
     /// Binary ack packet, to acknowledge an event with binary data
     BinaryAck(BinaryPacket, i64),
 }
 
 /// Binary packet used when sending binary data
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BinaryPacket {
+pub struct BinaryPacket<D = Value> {
     /// Data related to the packet
-    pub data: Value,
+    pub data: D,
     /// Binary payload
     pub bin: Vec<Bytes>,
     /// The number of expected payloads (used when receiving data)
-    payload_count: usize,
+    pub(crate) payload_count: usize,
 }
 
 impl<'a> PacketData<'a> {
@@ -224,6 +251,7 @@ impl<'a> PacketData<'a> {
 
     /// Set the ack id for the packet
     /// It will only set the ack id for the packets that support it
+    #[cfg(test)]
     pub(crate) fn set_ack_id(&mut self, ack_id: i64) {
         match self {
             PacketData::Event(_, _, ack) | PacketData::BinaryEvent(_, _, ack) => {
@@ -232,14 +260,6 @@ impl<'a> PacketData<'a> {
             _ => {}
         };
     }
-
-    /// Check if the packet is a binary packet (either binary event or binary ack)
-    pub(crate) fn is_binary(&self) -> bool {
-        matches!(
-            self,
-            PacketData::BinaryEvent(_, _, _) | PacketData::BinaryAck(_, _)
-        )
-    }
 }
 
 impl BinaryPacket {
@@ -291,7 +311,21 @@ impl BinaryPacket {
             payload_count,
         }
     }
+}
+
+impl BinaryPacket<RawPayload> {
+    /// Create a binary packet from an incoming event payload,
+    /// the payload count is the one announced in the packet header
+    fn incoming_event(data: RawPayload, payload_count: usize) -> Self {
+        Self {
+            data,
+            bin: Vec::new(),
+            payload_count,
+        }
+    }
+}
 
+impl<D> BinaryPacket<D> {
     /// Add a payload to the binary packet, when all payloads are added,
     /// the packet is complete and can be further processed
     pub fn add_payload<B: Into<Bytes>>(&mut self, payload: B) {
@@ -303,100 +337,478 @@ impl BinaryPacket {
     }
 }
 
-impl<'a> From<Packet<'a>> for String {
-    fn from(mut packet: Packet<'a>) -> String {
-        use PacketData::*;
+/// The payload of an event packet, kept as the raw JSON array it was received as:
+/// ```text
+/// ["<event name>", ...<arguments>]
+/// ```
+///
+/// Only the event name is parsed when a packet is decoded. The arguments are deserialized
+/// straight from the received string, and only when an extractor asks for them.
+/// Cloning a [`RawPayload`] only increments a reference counter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPayload {
+    data: Str,
+}
 
-        // Serialize the data if there is any
-        // pre-serializing allows to preallocate the buffer
-        let data = match &mut packet.inner {
-            Event(e, data, _) | BinaryEvent(e, BinaryPacket { data, .. }, _) => {
-                // Expand the packet if it is an array with data -> ["event", ...data]
-                let packet = match data {
-                    Value::Array(ref mut v) if !v.is_empty() => {
-                        v.insert(0, Value::String((*e).to_string()));
-                        serde_json::to_string(&v)
-                    }
-                    Value::Array(_) => serde_json::to_string::<(_, [(); 0])>(&(e, [])),
-                    _ => serde_json::to_string(&(e, data)),
-                }
-                .unwrap();
-                Some(packet)
-            }
-            EventAck(data, _) | BinaryAck(BinaryPacket { data, .. }, _) => {
-                // Enforce that the packet is an array -> [data]
-                let packet = match data {
-                    Value::Array(_) => serde_json::to_string(&data),
-                    Value::Null => Ok("[]".to_string()),
-                    _ => serde_json::to_string(&[data]),
-                }
-                .unwrap();
-                Some(packet)
-            }
-            _ => None,
-        };
+impl RawPayload {
+    /// Serialize an event payload, a placeholder is appended for each binary attachment
+    fn event<T: Serialize + ?Sized>(
+        event: &str,
+        data: &T,
+        bin_count: usize,
+    ) -> Result<Self, serde_json::Error> {
+        let mut buf = Vec::with_capacity(64 + event.len());
+        write_payload(&mut buf, Some(event), data, bin_count)?;
+        // SAFETY: serde_json output is always valid utf8
+        let data = unsafe { String::from_utf8_unchecked(buf) };
+        Ok(Self { data: data.into() })
+    }
 
-        let capacity = packet.get_size_hint() + data.as_ref().map(|d| d.len()).unwrap_or(0);
-        let mut res = String::with_capacity(capacity);
-        res.push(packet.inner.index());
+    /// The raw JSON array of the event, including the event name
+    pub fn as_str(&self) -> &str {
+        &self.data
+    }
 
-        // Add the ns if it is not the default one and the packet is not binary
-        // In case of bin packet, we should first add the payload count before ns
-        let push_nsp = |res: &mut String| {
-            if !packet.ns.is_empty() && packet.ns != "/" {
-                if !packet.ns.starts_with('/') {
-                    res.push('/');
-                }
-                res.push_str(&packet.ns);
-                res.push(',');
-            }
-        };
+    /// The length of the raw JSON array of the event
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check if the raw JSON array of the event is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
 
-        if !packet.inner.is_binary() {
-            push_nsp(&mut res);
+    /// Split the event into its arguments, without deserializing them.
+    /// The event name and the binary placeholders are not part of the arguments.
+    pub fn args(&self) -> Result<Vec<&RawValue>, serde_json::Error> {
+        let mut args: Vec<&RawValue> = serde_json::from_str(&self.data)?;
+        if !args.is_empty() {
+            args.remove(0);
         }
+        args.retain(|arg| !is_placeholder(arg));
+        Ok(args)
+    }
 
-        let mut itoa_buf = itoa::Buffer::new();
+    /// Deserialize the arguments of the event.
+    ///
+    /// If there is only one argument it is deserialized to `T`,
+    /// otherwise all the arguments are deserialized as an array.
+    /// The returned value may borrow data from the payload (e.g. `&str` or `&RawValue`).
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T, serde_json::Error> {
+        match self.args()?.as_slice() {
+            [arg] => T::deserialize(*arg),
+            args => T::deserialize(SeqDeserializer::<_, serde_json::Error>::new(
+                args.iter().copied(),
+            )),
+        }
+    }
 
-        match packet.inner {
-            PacketData::Connect(Some(data)) => res.push_str(&data),
-            PacketData::Disconnect | PacketData::Connect(None) => (),
-            PacketData::Event(_, _, ack) => {
-                if let Some(ack) = ack {
-                    res.push_str(itoa_buf.format(ack));
-                }
+    /// Deserialize all the arguments of the event as a sequence (e.g. a tuple),
+    /// even if there is only one argument.
+    pub fn deserialize_args<'de, T: Deserialize<'de>>(&'de self) -> Result<T, serde_json::Error> {
+        let args = self.args()?;
+        T::deserialize(SeqDeserializer::<_, serde_json::Error>::new(
+            args.into_iter(),
+        ))
+    }
 
-                res.push_str(&data.unwrap())
+    /// Deserialize the argument of the event at the given position.
+    pub fn deserialize_arg<'de, T: Deserialize<'de>>(
+        &'de self,
+        index: usize,
+    ) -> Result<T, serde_json::Error> {
+        let args = self.args()?;
+        match args.get(index) {
+            Some(arg) => T::deserialize(*arg),
+            None => {
+                let expected = format!("at least {} arguments", index + 1);
+                Err(de::Error::invalid_length(args.len(), &expected.as_str()))
             }
-            PacketData::EventAck(_, ack) => {
-                res.push_str(itoa_buf.format(ack));
-                res.push_str(&data.unwrap())
-            }
-            PacketData::ConnectError(data) => res.push_str(&data),
-            PacketData::BinaryEvent(_, bin, ack) => {
-                res.push_str(itoa_buf.format(bin.payload_count));
-                res.push('-');
+        }
+    }
+}
 
-                push_nsp(&mut res);
+/// Arguments of an event or an acknowledgement.
+///
+/// By default, array-like data (tuple, vec, arrays) given to `emit` or to [`AckSender::send`] is
+/// spread into multiple arguments, except if it is empty. Wrapping the data in [`Args`] makes it explicit:
+/// each element of the sequence is **always** sent as a separate argument.
+/// It allows to send a single array argument or no argument at all.
+///
+/// The wrapped data must be serialized as a sequence, otherwise a serialization error is returned.
+/// ```
+/// # use socketioxide::{SocketIo, extract::*, packet::Args};
+/// let (_, io) = SocketIo::new_svc();
+/// io.ns("/", |socket: SocketRef| {
+///     // Client side: `socket.on("move", (x, y, meta) => {})`
+///     socket.emit("move", Args((1, 2, "meta"))).ok();
+///     // Client side: `socket.on("list", (list) => {})`
+///     socket.emit("list", Args(([1, 2, 3],))).ok();
+///     // Client side: `socket.on("ping", () => {})`
+///     socket.emit("ping", Args(())).ok();
+///
+///     socket.on("move", |ack: AckSender| {
+///         ack.send(Args(("ok", 2))).ok();
+///     });
+/// });
+/// ```
+///
+/// [`AckSender::send`]: crate::extract::AckSender#method.send
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Args<T>(pub T);
 
-                if let Some(ack) = ack {
-                    res.push_str(itoa_buf.format(ack));
-                }
+/// The name used to recognize [`Args`] when serializing a payload
+const ARGS_TOKEN: &str = "$socketioxide::packet::Args";
+
+impl<T: Serialize> Serialize for Args<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(ARGS_TOKEN, &self.0)
+    }
+}
+
+/// A [`Serializer`] that only checks if a value is wrapped in [`Args`], without serializing it
+struct ArgsProbe;
 
-                res.push_str(&data.unwrap())
+#[derive(Debug)]
+struct NotArgs;
+impl std::fmt::Display for NotArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("not args")
+    }
+}
+impl std::error::Error for NotArgs {}
+impl ser::Error for NotArgs {
+    fn custom<T: std::fmt::Display>(_: T) -> Self {
+        NotArgs
+    }
+}
+
+macro_rules! not_args {
+    ($($name:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(
+            fn $name(self, $(_: $arg),*) -> Result<$ret, NotArgs> {
+                Err(NotArgs)
             }
-            PacketData::BinaryAck(packet, ack) => {
-                res.push_str(itoa_buf.format(packet.payload_count));
-                res.push('-');
+        )*
+    };
+}
+
+impl Serializer for ArgsProbe {
+    type Ok = ();
+    type Error = NotArgs;
+    type SerializeSeq = Impossible<(), NotArgs>;
+    type SerializeTuple = Impossible<(), NotArgs>;
+    type SerializeTupleStruct = Impossible<(), NotArgs>;
+    type SerializeTupleVariant = Impossible<(), NotArgs>;
+    type SerializeMap = Impossible<(), NotArgs>;
+    type SerializeStruct = Impossible<(), NotArgs>;
+    type SerializeStructVariant = Impossible<(), NotArgs>;
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _: &T,
+    ) -> Result<(), NotArgs> {
+        (name == ARGS_TOKEN).then_some(()).ok_or(NotArgs)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<(), NotArgs> {
+        Err(NotArgs)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), NotArgs> {
+        Err(NotArgs)
+    }
+
+    not_args! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+/// Check if an argument is a binary placeholder: `{"_placeholder":true,"num":0}`
+fn is_placeholder(arg: &RawValue) -> bool {
+    #[derive(Deserialize)]
+    struct Placeholder {
+        #[serde(rename = "_placeholder")]
+        _placeholder: IgnoredAny,
+    }
+    let arg = arg.get();
+    arg.starts_with('{')
+        && arg.contains("\"_placeholder\"")
+        && serde_json::from_str::<Placeholder>(arg).is_ok()
+}
+
+/// Write the packet header to the buffer:
+/// ```text
+/// <packet type>[<# of binary attachments>-][<namespace>,]
+/// ```
+fn write_header(buf: &mut Vec<u8>, index: char, ns: &str, bin_count: Option<usize>) {
+    buf.push(index as u8);
+    // In case of bin packet, we should first add the payload count before ns
+    if let Some(count) = bin_count {
+        buf.extend_from_slice(itoa::Buffer::new().format(count).as_bytes());
+        buf.push(b'-');
+    }
+    // Add the ns if it is not the default one
+    if !ns.is_empty() && ns != "/" {
+        if !ns.starts_with('/') {
+            buf.push(b'/');
+        }
+        buf.extend_from_slice(ns.as_bytes());
+        buf.push(b',');
+    }
+}
+
+/// Serialize the payload of an event or an ack directly to the buffer, without any intermediate [`Value`]:
+/// * Event payloads are expanded after the event name if they are non-empty arrays -> `["event", ...data]`,
+///   otherwise they are sent as a single argument -> `["event", data]`.
+/// * Ack payloads are always arrays -> `[data]`, a `null` ack payload without binary is sent as `[]`.
+/// * Payloads wrapped in [`Args`] are always expanded -> `["event", ...args]` or `[...args]`.
+///
+/// A placeholder is appended for each binary attachment.
+fn write_payload<T: Serialize + ?Sized>(
+    buf: &mut Vec<u8>,
+    event: Option<&str>,
+    data: &T,
+    bin_count: usize,
+) -> Result<(), serde_json::Error> {
+    buf.push(b'[');
+    if let Some(event) = event {
+        serde_json::to_writer(&mut *buf, event)?;
+        buf.push(b',');
+    }
+    let data_start = buf.len();
+    let is_args = data.serialize(ArgsProbe).is_ok();
+    serde_json::to_writer(&mut *buf, data)?;
+
+    let data = &buf[data_start..];
+    let is_empty_event = event.is_some() && bin_count == 0 && data == b"[]";
+    if is_args && data == b"null" {
+        // `Args(())` is sent without any argument
+        buf.truncate(data_start);
+    } else if is_args && data[0] != b'[' {
+        return Err(ser::Error::custom(
+            "Args data should be serialized as a sequence",
+        ));
+    } else if data[0] == b'[' && (is_args || !is_empty_event) {
+        // Remove the array brackets to spread its elements
+        buf.pop();
+        buf.remove(data_start);
+    } else if event.is_none() && bin_count == 0 && data == b"null" {
+        buf.truncate(data_start);
+    }
+    // An event without arguments should not have a separator after its name
+    if event.is_some() && buf.len() == data_start {
+        buf.pop();
+    }
+
+    let mut itoa_buf = itoa::Buffer::new();
+    for i in 0..bin_count {
+        if !matches!(buf.last(), Some(b'[' | b',')) {
+            buf.push(b',');
+        }
+        buf.extend_from_slice(br#"{"_placeholder":true,"num":"#);
+        buf.extend_from_slice(itoa_buf.format(i).as_bytes());
+        buf.push(b'}');
+    }
+    buf.push(b']');
+    Ok(())
+}
+
+/// A packet that has already been serialized.
+///
+/// Broadcasting a packet serializes it only once, the resulting [`EncodedPacket`]
+/// is then shared between all the recipients: cloning it only increments reference counters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPacket {
+    data: Str,
+    bin: Vec<Bytes>,
+    /// The position where an ack id can be inserted, only set for event packets without ack id
+    ack_index: Option<usize>,
+}
+
+impl EncodedPacket {
+    /// Serialize an event packet for the given namespace directly from its data
+    pub fn event<T: Serialize + ?Sized>(
+        ns: &str,
+        event: &str,
+        data: &T,
+        bin: Vec<Bytes>,
+    ) -> Result<Self, serde_json::Error> {
+        let (index, bin_count) = match bin.len() {
+            0 => ('2', None),
+            count => ('5', Some(count)),
+        };
+        let mut buf = Vec::with_capacity(64 + ns.len() + event.len());
+        write_header(&mut buf, index, ns, bin_count);
+        let ack_index = Some(buf.len());
+        write_payload(&mut buf, Some(event), data, bin.len())?;
+        Ok(Self::new(buf, bin, ack_index))
+    }
+
+    /// Serialize an ack packet for the given namespace directly from its data
+    pub fn ack<T: Serialize + ?Sized>(
+        ns: &str,
+        data: &T,
+        bin: Vec<Bytes>,
+        ack: i64,
+    ) -> Result<Self, serde_json::Error> {
+        let (index, bin_count) = match bin.len() {
+            0 => ('3', None),
+            count => ('6', Some(count)),
+        };
+        let mut buf = Vec::with_capacity(64 + ns.len());
+        write_header(&mut buf, index, ns, bin_count);
+        buf.extend_from_slice(itoa::Buffer::new().format(ack).as_bytes());
+        write_payload(&mut buf, None, data, bin.len())?;
+        Ok(Self::new(buf, bin, None))
+    }
 
-                push_nsp(&mut res);
+    fn new(buf: Vec<u8>, bin: Vec<Bytes>, ack_index: Option<usize>) -> Self {
+        // SAFETY: the buffer only contains valid utf8 strings and serde_json output which is always valid utf8
+        let data = unsafe { String::from_utf8_unchecked(buf) };
+        Self {
+            data: data.into(),
+            bin,
+            ack_index,
+        }
+    }
+
+    /// The serialized packet
+    pub fn data(&self) -> &Str {
+        &self.data
+    }
+
+    /// The binary attachments of the packet
+    pub fn bin(&self) -> &[Bytes] {
+        &self.bin
+    }
+
+    /// Split the packet into its serialized data and its binary attachments
+    pub fn into_parts(self) -> (Str, Vec<Bytes>) {
+        (self.data, self.bin)
+    }
+
+    /// Copy the packet with the given ack id.
+    /// The payload is not serialized again, the ack id is only inserted before it.
+    ///
+    /// Only event packets without ack id can be sent with an ack id, other packets are returned unchanged.
+    pub(crate) fn with_ack(&self, ack: i64) -> Self {
+        let Some(index) = self.ack_index else {
+            return self.clone();
+        };
+        let mut itoa_buf = itoa::Buffer::new();
+        let ack = itoa_buf.format(ack);
+        let mut data = String::with_capacity(self.data.len() + ack.len());
+        data.push_str(&self.data[..index]);
+        data.push_str(ack);
+        data.push_str(&self.data[index..]);
+        Self {
+            data: data.into(),
+            bin: self.bin.clone(),
+            ack_index: None,
+        }
+    }
+}
 
-                res.push_str(itoa_buf.format(ack));
-                res.push_str(&data.unwrap())
+impl<'a> From<Packet<'a>> for EncodedPacket {
+    fn from(packet: Packet<'a>) -> Self {
+        use PacketData::*;
+        let mut buf = Vec::with_capacity(packet.get_size_hint() + 64);
+        let index = packet.inner.index();
+        let ns = &packet.ns;
+        // Value serialization cannot fail.
+        // The placeholders of binary packets are already part of their data.
+        let (bin, ack_index) = match packet.inner {
+            Connect(data) => {
+                write_header(&mut buf, index, ns, None);
+                if let Some(data) = data {
+                    buf.extend_from_slice(data.as_bytes());
+                }
+                (Vec::new(), None)
+            }
+            Disconnect => {
+                write_header(&mut buf, index, ns, None);
+                (Vec::new(), None)
+            }
+            ConnectError(data) => {
+                write_header(&mut buf, index, ns, None);
+                buf.extend_from_slice(data.as_bytes());
+                (Vec::new(), None)
+            }
+            // The event name is already part of the raw payload
+            Event(_, data, ack) => {
+                write_header(&mut buf, index, ns, None);
+                let ack_index = write_ack(&mut buf, ack);
+                buf.extend_from_slice(data.as_str().as_bytes());
+                (Vec::new(), ack_index)
+            }
+            BinaryEvent(_, bin, ack) => {
+                write_header(&mut buf, index, ns, Some(bin.payload_count));
+                let ack_index = write_ack(&mut buf, ack);
+                buf.extend_from_slice(bin.data.as_str().as_bytes());
+                (bin.bin, ack_index)
+            }
+            EventAck(data, ack) => {
+                write_header(&mut buf, index, ns, None);
+                write_ack(&mut buf, Some(ack));
+                write_payload(&mut buf, None, &data, 0).unwrap();
+                (Vec::new(), None)
+            }
+            BinaryAck(bin, ack) => {
+                write_header(&mut buf, index, ns, Some(bin.payload_count));
+                write_ack(&mut buf, Some(ack));
+                write_payload(&mut buf, None, &bin.data, 0).unwrap();
+                (bin.bin, None)
             }
         };
+        Self::new(buf, bin, ack_index)
+    }
+}
 
-        res
+/// Write the ack id if there is one, otherwise return the position where it could be inserted
+fn write_ack(buf: &mut Vec<u8>, ack: Option<i64>) -> Option<usize> {
+    match ack {
+        Some(ack) => {
+            buf.extend_from_slice(itoa::Buffer::new().format(ack).as_bytes());
+            None
+        }
+        None => Some(buf.len()),
+    }
+}
+
+impl<'a> From<Packet<'a>> for String {
+    fn from(packet: Packet<'a>) -> String {
+        EncodedPacket::from(packet).data.into()
     }
 }
 
@@ -404,22 +816,20 @@ impl<'a> From<Packet<'a>> for String {
 /// ```text
 /// ["<event name>", ...<JSON-stringified payload without binary>]
 /// ```
-fn deserialize_event_packet(data: &str) -> Result<(String, Value), Error> {
+/// Only the event name is parsed, the payload is kept as is in a [`RawPayload`].
+fn deserialize_event_packet(data: Str) -> Result<(String, RawPayload), Error> {
     #[cfg(feature = "tracing")]
     tracing::debug!("Deserializing event packet: {:?}", data);
-    let packet = match serde_json::from_str::<Value>(data)? {
-        Value::Array(packet) => packet,
-        _ => return Err(Error::InvalidEventName),
-    };
-
-    let event = packet
-        .first()
-        .ok_or(Error::InvalidEventName)?
-        .as_str()
+    let name = data
+        .strip_prefix('[')
         .ok_or(Error::InvalidEventName)?
-        .to_string();
-    let payload = Value::from_iter(packet.into_iter().skip(1));
-    Ok((event, payload))
+        .trim_start();
+    let event = serde_json::Deserializer::from_str(name)
+        .into_iter::<String>()
+        .next()
+        .and_then(Result::ok)
+        .ok_or(Error::InvalidEventName)?;
+    Ok((event, RawPayload { data }))
 }
 
 fn deserialize_packet<T: DeserializeOwned>(data: &str) -> Result<Option<T>, serde_json::Error> {
@@ -452,10 +862,12 @@ impl<'a> TryFrom<Str> for Packet<'a> {
             .ok_or(Error::InvalidPacketType)?;
 
         // Move the cursor to skip the payload count if it is a binary packet
+        let mut payload_count = 0;
         if index == b'5' || index == b'6' {
             while chars.get(i) != Some(&b'-') {
                 i += 1;
             }
+            payload_count = value[1..i].parse().map_err(|_| Error::InvalidPacketType)?;
             i += 1;
         }
 
@@ -490,12 +902,13 @@ impl<'a> TryFrom<Str> for Packet<'a> {
             }
         };
 
-        let data = &value[i..];
+        let payload = value.slice(i..);
+        let data = payload.as_str();
         let inner = match index {
             b'0' => PacketData::Connect((!data.is_empty()).then(|| data.to_string())),
             b'1' => PacketData::Disconnect,
             b'2' => {
-                let (event, payload) = deserialize_event_packet(data)?;
+                let (event, payload) = deserialize_event_packet(payload)?;
                 PacketData::Event(event.into(), payload, ack)
             }
             b'3' => {
@@ -503,8 +916,9 @@ impl<'a> TryFrom<Str> for Packet<'a> {
                 PacketData::EventAck(packet, ack.ok_or(Error::InvalidPacketType)?)
             }
             b'5' => {
-                let (event, payload) = deserialize_event_packet(data)?;
-                PacketData::BinaryEvent(event.into(), BinaryPacket::incoming(payload), ack)
+                let (event, payload) = deserialize_event_packet(payload)?;
+                let packet = BinaryPacket::incoming_event(payload, payload_count);
+                PacketData::BinaryEvent(event.into(), packet, ack)
             }
             b'6' => {
                 let packet = deserialize_packet(data)?.ok_or(Error::InvalidPacketType)?;
@@ -713,6 +1127,11 @@ mod test {
             .try_into()
             .unwrap();
         assert_eq!(packet, payload);
+
+        let data = json!({ "code": 401 });
+        let payload = format!("4{}", json!({ "message": "Unauthorized", "data": data }));
+        let packet: String = Packet::connect_error_with_data("/", "Unauthorized", &data).into();
+        assert_eq!(packet, payload);
     }
 
     // BinaryEvent(String, BinaryPacket, Option<i64>),
@@ -779,7 +1198,7 @@ mod test {
                 "event".into(),
                 BinaryPacket {
                     bin: vec![Bytes::from_static(&[1])],
-                    data: json!([{"data": "value™"}]),
+                    data: RawPayload::event("event", &json!([{"data": "value™"}]), 1).unwrap(),
                     payload_count: 1,
                 },
                 ack,
@@ -892,6 +1311,175 @@ mod test {
         assert_eq!(packet, comparison_packet(54, "/admin™"));
     }
 
+    #[test]
+    fn encoded_packet_event() {
+        #[derive(Serialize)]
+        struct Data {
+            data: &'static str,
+        }
+        let data = Data { data: "value™" };
+        let cases = [
+            (json!({ "data": "value™" }), vec![]),
+            (json!([{ "data": "value™" }, 1, "str"]), vec![]),
+            (json!([]), vec![]),
+            (json!(null), vec![]),
+            (json!({ "data": "value™" }), vec![Bytes::from_static(&[1])]),
+            (json!([1, 2]), vec![Bytes::new(), Bytes::new()]),
+            (json!([]), vec![Bytes::new()]),
+        ];
+        for ns in ["/", "/admin™"] {
+            for (value, bin) in cases.clone() {
+                let packet = EncodedPacket::event(ns, "event", &value, bin.clone()).unwrap();
+                let comparison = if bin.is_empty() {
+                    Packet::event(ns, "event", value)
+                } else {
+                    Packet::bin_event(ns, "event", value, bin.clone())
+                };
+                assert_eq!(packet, EncodedPacket::from(comparison.clone()));
+                assert_eq!(packet.bin(), &bin);
+
+                let mut comparison = comparison;
+                comparison.inner.set_ack_id(254);
+                assert_eq!(packet.with_ack(254), EncodedPacket::from(comparison));
+            }
+            // Serialize a struct without any intermediate value
+            let packet = EncodedPacket::event(ns, "event", &data, vec![]).unwrap();
+            let comparison = Packet::event(ns, "event", json!({ "data": "value™" }));
+            assert_eq!(packet, EncodedPacket::from(comparison));
+        }
+    }
+
+    #[test]
+    fn encoded_packet_ack() {
+        let cases = [
+            (json!({ "data": "value™" }), vec![]),
+            (json!([{ "data": "value™" }, 1]), vec![]),
+            (json!(null), vec![]),
+            (json!({ "data": "value™" }), vec![Bytes::from_static(&[1])]),
+            (json!(null), vec![Bytes::from_static(&[1])]),
+        ];
+        for ns in ["/", "/admin™"] {
+            for (value, bin) in cases.clone() {
+                let packet = EncodedPacket::ack(ns, &value, bin.clone(), 54).unwrap();
+                let comparison = if bin.is_empty() {
+                    Packet::ack(ns, value, 54)
+                } else {
+                    Packet::bin_ack(ns, value, bin, 54)
+                };
+                assert_eq!(packet, EncodedPacket::from(comparison));
+                // Ack packets can't be sent with another ack id
+                assert_eq!(packet.with_ack(1), packet);
+            }
+        }
+    }
+
+    #[test]
+    fn raw_payload_args() {
+        let packet = Packet::try_from(
+            r#"51-["event",{"data":"value™"},{"_placeholder":true,"num":0},2]"#.to_string(),
+        )
+        .unwrap();
+        let PacketData::BinaryEvent(e, bin, _) = packet.inner else {
+            panic!("expected a binary event");
+        };
+        assert_eq!(e, "event");
+        assert_eq!(bin.payload_count, 1);
+
+        let args = bin.data.args().unwrap();
+        let args: Vec<&str> = args.iter().map(|arg| arg.get()).collect();
+        assert_eq!(args, [r#"{"data":"value™"}"#, "2"]);
+
+        let data: (Value, u8) = bin.data.deserialize().unwrap();
+        assert_eq!(data, (json!({ "data": "value™" }), 2));
+    }
+
+    #[test]
+    fn raw_payload_deserialize() {
+        let payload = RawPayload::event("event", &json!(["value™"]), 0).unwrap();
+        // A single argument is deserialized without its enclosing array
+        assert_eq!(payload.deserialize::<&str>().unwrap(), "value™");
+        assert_eq!(payload.deserialize::<String>().unwrap(), "value™");
+
+        let payload = RawPayload::event("event", &json!([1, 2, 3]), 0).unwrap();
+        assert_eq!(payload.deserialize::<[u8; 3]>().unwrap(), [1, 2, 3]);
+        assert!(payload.deserialize::<u8>().is_err());
+
+        // An empty array is sent as a single argument
+        let payload = RawPayload::event("event", &json!([]), 0).unwrap();
+        assert_eq!(payload.args().unwrap().len(), 1);
+        assert!(payload.deserialize::<Vec<u8>>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn raw_payload_deserialize_args() {
+        let payload = RawPayload::event("event", &json!([[1, 2]]), 0).unwrap();
+        assert_eq!(payload.deserialize::<Vec<u8>>().unwrap(), [1, 2]);
+        assert_eq!(
+            payload.deserialize_args::<(Vec<u8>,)>().unwrap(),
+            (vec![1, 2],)
+        );
+        assert_eq!(payload.deserialize_arg::<Vec<u8>>(0).unwrap(), [1, 2]);
+        assert!(payload.deserialize_arg::<Vec<u8>>(1).is_err());
+
+        let payload = RawPayload::event("event", &json!(["a", 2]), 0).unwrap();
+        assert_eq!(payload.deserialize_args::<(&str, u8)>().unwrap(), ("a", 2));
+        assert_eq!(payload.deserialize_arg::<u8>(1).unwrap(), 2);
+    }
+
+    #[test]
+    fn encoded_packet_args() {
+        let packet = EncodedPacket::event("/", "event", &Args((1, 2, "meta")), vec![]).unwrap();
+        assert_eq!(packet.data().as_str(), r#"2["event",1,2,"meta"]"#);
+
+        // A single array argument is not spread
+        let packet = EncodedPacket::event("/", "event", &Args(([1, 2],)), vec![]).unwrap();
+        assert_eq!(packet.data().as_str(), r#"2["event",[1,2]]"#);
+
+        let packet = EncodedPacket::event("/", "event", &Args(()), vec![]).unwrap();
+        assert_eq!(packet.data().as_str(), r#"2["event"]"#);
+        let packet = EncodedPacket::event("/", "event", &Args([0u8; 0]), vec![]).unwrap();
+        assert_eq!(packet.data().as_str(), r#"2["event"]"#);
+
+        let bin = vec![Bytes::from_static(&[1])];
+        let packet = EncodedPacket::event("/", "event", &Args(()), bin.clone()).unwrap();
+        assert_eq!(
+            packet.data().as_str(),
+            r#"51-["event",{"_placeholder":true,"num":0}]"#
+        );
+
+        let packet = EncodedPacket::ack("/", &Args(("ok", 2)), vec![], 3).unwrap();
+        assert_eq!(packet.data().as_str(), r#"33["ok",2]"#);
+        let packet = EncodedPacket::ack("/", &Args(()), vec![], 3).unwrap();
+        assert_eq!(packet.data().as_str(), "33[]");
+        let packet = EncodedPacket::ack("/", &Args(([1, 2],)), bin, 3).unwrap();
+        assert_eq!(
+            packet.data().as_str(),
+            r#"61-3[[1,2],{"_placeholder":true,"num":0}]"#
+        );
+
+        // Args should always be a sequence
+        assert!(EncodedPacket::event("/", "event", &Args("data"), vec![]).is_err());
+        assert!(EncodedPacket::ack("/", &Args(1), vec![], 3).is_err());
+    }
+
+    #[test]
+    fn packet_decode_event_name_only() {
+        // The payload is only parsed when it is extracted
+        let packet = Packet::try_from(r#"2["event",{"data":}]"#.to_string()).unwrap();
+        let PacketData::Event(e, payload, _) = packet.inner else {
+            panic!("expected an event");
+        };
+        assert_eq!(e, "event");
+        assert!(payload.args().is_err());
+
+        let packet = Packet::try_from(r#"2["ev\"ent™", 1]"#.to_string()).unwrap();
+        assert!(matches!(packet.inner, PacketData::Event(e, _, _) if e == "ev\"ent™"));
+
+        assert!(Packet::try_from(r#"2[1,"event"]"#.to_string()).is_err());
+        assert!(Packet::try_from(r#"2{"event":1}"#.to_string()).is_err());
+        assert!(Packet::try_from(r#"2[]"#.to_string()).is_err());
+    }
+
     #[test]
     fn packet_size_hint() {
         let sid = Sid::new();
@@ -912,10 +1500,10 @@ mod test {
         assert_eq!(packet.get_size_hint(), 8);
 
         let packet = Packet::event("/", "event", json!({ "data": "value™" }));
-        assert_eq!(packet.get_size_hint(), 1);
+        assert_eq!(packet.get_size_hint(), 30);
 
         let packet = Packet::event("/admin", "event", json!({ "data": "value™" }));
-        assert_eq!(packet.get_size_hint(), 8);
+        assert_eq!(packet.get_size_hint(), 37);
 
         let packet = Packet::ack("/", json!("data"), 54);
         assert_eq!(packet.get_size_hint(), 3);
@@ -929,7 +1517,7 @@ mod test {
             json!({ "data": "value™" }),
             vec![Bytes::from_static(&[1])],
         );
-        assert_eq!(packet.get_size_hint(), 3);
+        assert_eq!(packet.get_size_hint(), 62);
 
         let packet = Packet::bin_event(
             "/admin",
@@ -937,7 +1525,7 @@ mod test {
             json!({ "data": "value™" }),
             vec![Bytes::from_static(&[1])],
         );
-        assert_eq!(packet.get_size_hint(), 10);
+        assert_eq!(packet.get_size_hint(), 69);
 
         let packet = Packet::bin_ack("/", json!("data"), vec![Bytes::from_static(&[1])], 54);
         assert_eq!(packet.get_size_hint(), 5);
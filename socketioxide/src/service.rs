@@ -116,6 +116,45 @@ impl<A: Adapter, S: Clone> SocketIoService<S, A> {
         self.engine_svc.into_make_service()
     }
 
+    /// Serves the WebTransport sessions of an HTTP/3 connection accepted with [`quinn`].
+    ///
+    /// The QUIC endpoint must negotiate the `h3` ALPN protocol.
+    /// The `WebTransport` transport must be enabled with [`SocketIoBuilder::transports`](crate::SocketIoBuilder::transports).
+    /// See [`EngineIoService::serve_webtransport`] for more details.
+    ///
+    /// The returned future resolves when the connection is closed.
+    #[cfg(feature = "webtransport")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+    #[inline(always)]
+    pub fn serve_webtransport(
+        &self,
+        conn: quinn::Connection,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        self.engine_svc.serve_webtransport(conn)
+    }
+
+    /// Handles a WebTransport session opened with an HTTP/3 `CONNECT` request.
+    ///
+    /// With an HTTP/3 stack other than `quinn`, see [`SocketIoService::serve_webtransport`] otherwise:
+    /// once your HTTP/3 stack has accepted the session,
+    /// give the request parts and the first bidirectional stream opened by the client to this fn.
+    /// The `WebTransport` transport must be enabled with [`SocketIoBuilder::transports`](crate::SocketIoBuilder::transports).
+    ///
+    /// The returned future resolves when the stream is closed.
+    #[cfg(feature = "webtransport")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+    #[inline(always)]
+    pub fn webtransport_session<T>(
+        &self,
+        req: http::request::Parts,
+        stream: T,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static
+    where
+        T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
+    {
+        self.engine_svc.webtransport_session(req, stream)
+    }
+
     /// Creates a new [`EngineIoService`] with a custom inner service and a custom config.
     pub(crate) fn with_config_inner(
         inner: S,
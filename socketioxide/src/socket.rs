@@ -13,10 +13,13 @@ use std::{
 };
 
 use bytes::Bytes;
-use engineioxide::socket::{DisconnectReason as EIoDisconnectReason, Permit};
+use engineioxide::socket::{DisconnectReason as EIoDisconnectReason, Permit as EIoPermit};
+use futures_util::future::BoxFuture;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use tokio::sync::oneshot::{self, Receiver};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tower::{BoxError, Layer, Service};
 
 #[cfg(feature = "extensions")]
 use crate::extensions::Extensions;
@@ -26,25 +29,37 @@ use crate::{
     adapter::{Adapter, LocalAdapter, Room},
     errors::{DisconnectError, Error, SendError},
     handler::{
-        BoxedDisconnectHandler, BoxedMessageHandler, DisconnectHandler, MakeErasedHandler,
-        MessageHandler,
+        error::{ErrorCause, ErrorReply, HandlerError},
+        BoxedDisconnectHandler, BoxedMessageHandler, DisconnectHandler, EventRequest, EventService,
+        MakeErasedHandler, MessageHandler,
     },
     ns::Namespace,
     operators::{BroadcastOperators, ConfOperators, RoomParam},
-    packet::{BinaryPacket, Packet, PacketData},
+    packet::{BinaryPacket, EncodedPacket, Packet, PacketData, RawPayload},
     AckError, SocketIoConfig,
 };
 use crate::{
+    bin_stream::{self, BinStreamTx},
     client::SocketData,
+    dispatch::{AckGuard, Dispatcher},
     errors::{AdapterError, SocketError},
+    extract::BinStream,
+    interceptor::{intercept_emit, Interceptor},
 };
 
 pub use engineioxide::sid::Sid;
 
+/// The event sent to the client before the disconnect packet when a socket is disconnected
+/// with [`Socket::disconnect_with`] or [`BroadcastOperators::disconnect_with`].
+pub const DISCONNECT_REASON_EVENT: &str = "disconnect_reason";
+
 /// All the possible reasons for a [`Socket`] to be disconnected from a namespace.
 ///
 /// It can be used as an extractor in the [`on_disconnect`](crate::handler::disconnect) handler.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// **Breaking change**: it is not `Copy` anymore because the [`DisconnectReason::ServerNSDisconnectWith`]
+/// variant holds the message of the reason, it must be cloned explicitly.
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum DisconnectReason {
     /// The client gracefully closed the connection
     TransportClose,
@@ -67,6 +82,15 @@ pub enum DisconnectReason {
     /// The socket was forcefully disconnected from the namespace with [`Socket::disconnect`] or with [`SocketIo::delete_ns`](crate::io::SocketIo::delete_ns)
     ServerNSDisconnect,
 
+    /// The socket was forcefully disconnected from the namespace with [`Socket::disconnect_with`]
+    /// or [`BroadcastOperators::disconnect_with`], with a custom reason also sent to the client
+    ServerNSDisconnectWith {
+        /// The application defined reason code
+        code: u16,
+        /// A human readable message describing the reason
+        message: Cow<'static, str>,
+    },
+
     /// The server is being closed
     ClosingServer,
 }
@@ -74,6 +98,12 @@ pub enum DisconnectReason {
 impl std::fmt::Display for DisconnectReason {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use DisconnectReason::*;
+        if let ServerNSDisconnectWith { code, message } = self {
+            return write!(
+                f,
+                "socket was disconnected from the namespace ({code}): {message}"
+            );
+        }
         let str: &'static str = match self {
             TransportClose => "client gracefully closed the connection",
             MultipleHttpPollingError => "client sent multiple polling requests at the same time",
@@ -81,7 +111,9 @@ impl std::fmt::Display for DisconnectReason {
             TransportError => "The connection was abruptly closed",
             HeartbeatTimeout => "client did not send a PONG packet in time",
             ClientNSDisconnect => "client has manually disconnected the socket from the namespace",
-            ServerNSDisconnect => "socket was forcefully disconnected from the namespace",
+            ServerNSDisconnect | ServerNSDisconnectWith { .. } => {
+                "socket was forcefully disconnected from the namespace"
+            }
             ClosingServer => "server is being closed",
         };
         f.write_str(str)
@@ -102,28 +134,53 @@ impl From<EIoDisconnectReason> for DisconnectReason {
     }
 }
 
-pub(crate) trait PermitExt<'a> {
-    fn send(self, packet: Packet<'_>);
+/// A permit to emit a message to the client of a socket, reserved with [`Socket::reserve_async`].
+///
+/// It holds a slot in the socket buffer, so the message cannot fail because the buffer is full.
+/// The slot is released if the permit is dropped without being used.
+/// The message goes through the [`Interceptor`] of the server if there is one.
+pub struct Permit<'a> {
+    inner: EIoPermit<'a>,
+    sid: Sid,
+    ns: &'a str,
+    interceptor: Option<&'a dyn Interceptor>,
 }
-impl<'a> PermitExt<'a> for Permit<'a> {
-    fn send(self, mut packet: Packet<'_>) {
-        let bin_payloads = match packet.inner {
-            PacketData::BinaryEvent(_, ref mut bin, _) | PacketData::BinaryAck(ref mut bin, _) => {
-                Some(std::mem::take(&mut bin.bin))
-            }
-            _ => None,
-        };
-
-        let msg = packet.into();
+impl Permit<'_> {
+    /// Emits a message to the client with the reserved slot.
+    ///
+    /// See [`Socket::emit`] for more details about the data format.
+    ///
+    /// ## Errors
+    /// If the data cannot be serialized to JSON, a [`serde_json::Error`] is returned
+    /// and the slot is released.
+    pub fn emit<T: Serialize>(
+        self,
+        event: impl Into<Cow<'static, str>>,
+        data: T,
+    ) -> Result<(), serde_json::Error> {
+        let packet = EncodedPacket::event(self.ns, &event.into(), &data, Vec::new())?;
+        self.send(packet);
+        Ok(())
+    }
 
-        if let Some(bin_payloads) = bin_payloads {
-            self.emit_many(msg, bin_payloads);
+    pub(crate) fn send(self, packet: impl Into<EncodedPacket>) {
+        let (msg, bin_payloads) = packet.into().into_parts();
+        let Some((msg, bin_payloads)) =
+            intercept_emit(self.interceptor, self.sid, msg, bin_payloads)
+        else {
+            return;
+        };
+        if bin_payloads.is_empty() {
+            self.inner.emit(msg);
         } else {
-            self.emit(msg);
+            self.inner.emit_many(msg, bin_payloads);
         }
     }
 }
 
+/// The sender of a pending acknowledgement and the guard held while it is awaited
+type PendingAck = (oneshot::Sender<AckResult<Value>>, Option<AckGuard>);
+
 /// A Socket represents a client connected to a namespace.
 /// It is used to send and receive messages from the client, join and leave rooms, etc.
 /// The socket struct itself should not be used directly, but through a [`SocketRef`](crate::extract::SocketRef).
@@ -131,10 +188,21 @@ pub struct Socket<A: Adapter = LocalAdapter> {
     pub(crate) config: Arc<SocketIoConfig>,
     pub(crate) ns: Arc<Namespace<A>>,
     message_handlers: RwLock<HashMap<Cow<'static, str>, BoxedMessageHandler<A>>>,
+    /// The service through which the events are dispatched to the handlers
+    event_service: RwLock<EventService<A>>,
+    /// The services wrapping the handlers of single events
+    event_layers: RwLock<HashMap<Cow<'static, str>, EventService<A>>>,
+    /// Executes the handlers according to the dispatch mode
+    dispatcher: Dispatcher,
     disconnect_handler: Mutex<Option<BoxedDisconnectHandler<A>>>,
-    ack_message: Mutex<HashMap<i64, oneshot::Sender<AckResult<Value>>>>,
+    /// The pending acknowledgements, with a guard keeping the transport read while they are awaited
+    ack_message: Mutex<HashMap<i64, PendingAck>>,
     ack_counter: AtomicI64,
     connected: AtomicBool,
+    /// The stream of attachments handed to a [`BinStream`] extractor during a handler call
+    bin_stream: Mutex<Option<BinStream>>,
+    /// The values returned by the connect middlewares, retrieved with the [`MiddlewareValue`](crate::extract::MiddlewareValue) extractor
+    middleware_values: Mutex<http::Extensions>,
     /// The socket id
     pub id: Sid,
 
@@ -155,14 +223,26 @@ impl<A: Adapter> Socket<A> {
         ns: Arc<Namespace<A>>,
         esocket: Arc<engineioxide::Socket<SocketData>>,
         config: Arc<SocketIoConfig>,
+        tasks: TaskTracker,
     ) -> Self {
+        let dispatch_mode = ns.dispatch_mode().unwrap_or(config.dispatch_mode);
         Self {
             ns,
             message_handlers: RwLock::new(HashMap::new()),
+            event_service: RwLock::new(EventService::new()),
+            event_layers: RwLock::new(HashMap::new()),
+            dispatcher: Dispatcher::new(
+                dispatch_mode,
+                config.max_in_flight_handlers,
+                esocket.clone(),
+                tasks,
+            ),
             disconnect_handler: Mutex::new(None),
             ack_message: Mutex::new(HashMap::new()),
             ack_counter: AtomicI64::new(0),
             connected: AtomicBool::new(false),
+            bin_stream: Mutex::new(None),
+            middleware_values: Mutex::new(http::Extensions::new()),
             id: sid,
             #[cfg(feature = "extensions")]
             extensions: Extensions::new(),
@@ -228,10 +308,80 @@ impl<A: Adapter> Socket<A> {
         H: MessageHandler<A, T>,
         T: Send + Sync + 'static,
     {
+        let event = event.into();
+        self.event_layers.write().unwrap().remove(&event);
+        self.message_handlers
+            .write()
+            .unwrap()
+            .insert(event, MakeErasedHandler::new_message_boxed(handler));
+    }
+
+    /// ### Registers a [`MessageHandler`] for the given event, wrapped with a [`tower`] [`Layer`].
+    ///
+    /// The layer only applies to this event. It is wrapped by the layers set with [`Socket::event_layer`]
+    /// and [`SocketIo::event_layer`](crate::SocketIo::event_layer).
+    /// Registering a new handler for this event with [`Socket::on`] removes the layer.
+    ///
+    /// See the [`service`](crate::handler::service) module doc for more details.
+    ///
+    /// #### Example with a timeout:
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     let timeout = tower::timeout::TimeoutLayer::new(Duration::from_secs(5));
+    ///     socket.on_with_layer("compute", |Data::<u64>(n)| async move {
+    ///         tokio::time::sleep(Duration::from_millis(n)).await;
+    ///     }, timeout);
+    /// });
+    /// ```
+    pub fn on_with_layer<H, T, L>(&self, event: impl Into<Cow<'static, str>>, handler: H, layer: L)
+    where
+        H: MessageHandler<A, T>,
+        T: Send + Sync + 'static,
+        L: Layer<EventService<A>>,
+        L::Service: Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+        <L::Service as Service<EventRequest<A>>>::Error: Into<BoxError>,
+        <L::Service as Service<EventRequest<A>>>::Future: Send + 'static,
+    {
+        let event = event.into();
         self.message_handlers
             .write()
             .unwrap()
-            .insert(event.into(), MakeErasedHandler::new_message_boxed(handler));
+            .insert(event.clone(), MakeErasedHandler::new_message_boxed(handler));
+        let svc = EventService::handler().layer(layer);
+        self.event_layers.write().unwrap().insert(event, svc);
+    }
+
+    /// ### Wraps the dispatch of all the events of this socket with a [`tower`] [`Layer`].
+    ///
+    /// It can be called multiple times, the last layer set is the outermost one.
+    /// The socket layers wrap the global layers set with [`SocketIo::event_layer`](crate::SocketIo::event_layer).
+    ///
+    /// See the [`service`](crate::handler::service) module doc for more details.
+    ///
+    /// #### Example with a concurrency limit:
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     // At most 4 handlers of this socket are running at the same time
+    ///     socket.event_layer(tower::limit::ConcurrencyLimitLayer::new(4));
+    ///     socket.on("test", |Data::<String>(data)| async move {
+    ///         println!("Received a test message {:?}", data);
+    ///     });
+    /// });
+    /// ```
+    pub fn event_layer<L>(&self, layer: L)
+    where
+        L: Layer<EventService<A>>,
+        L::Service: Service<EventRequest<A>, Response = ()> + Clone + Send + 'static,
+        <L::Service as Service<EventRequest<A>>>::Error: Into<BoxError>,
+        <L::Service as Service<EventRequest<A>>>::Future: Send + 'static,
+    {
+        let mut svc = self.event_service.write().unwrap();
+        *svc = svc.clone().layer(layer);
     }
 
     /// ## Registers a disconnect handler.
@@ -271,6 +421,7 @@ impl<A: Adapter> Socket<A> {
     /// If you provide array-like data (tuple, vec, arrays), it will be considered as multiple arguments.
     /// Therefore if you want to send an array as the _first_ argument of the payload,
     /// you need to wrap it in an array or a tuple.
+    /// The [`Args`](crate::packet::Args) wrapper can also be used to always send each element as an argument.
     ///
     /// ## Errors
     /// * When encoding the data into JSON a [`SendError::Serialize`] may be returned.
@@ -309,11 +460,57 @@ impl<A: Adapter> Socket<A> {
         event: impl Into<Cow<'static, str>>,
         data: T,
     ) -> Result<(), SendError<T>> {
+        let event = event.into();
         if !self.connected() {
             return Err(SendError::Socket(SocketError::Closed(data)));
         }
 
         let permit = match self.reserve() {
+            Ok(permit) => permit,
+            Err(e) => return Err(e.with_value(data).into()),
+        };
+
+        let packet = EncodedPacket::event(self.ns(), &event, &data, Vec::new())?;
+        permit.send(packet);
+        Ok(())
+    }
+
+    /// Emits a message to the client, waiting at most `timeout` for space in the socket buffer if it is full.
+    ///
+    /// Producers such as background tasks can use it to wait for a slow client instead of dropping messages.
+    /// To adapt their rate beforehand, they can check the remaining space with [`Socket::buffer_capacity`].
+    ///
+    /// ## Errors
+    /// The errors are the same as for [`Socket::emit`],
+    /// except that a [`SendError::Socket(SocketError::InternalChannelFull)`] is only returned
+    /// if the buffer is still full after the timeout.
+    ///
+    /// [`SendError::Socket(SocketError::InternalChannelFull)`]: crate::SocketError::InternalChannelFull
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| async move {
+    ///     for i in 0..1000 {
+    ///         if let Err(e) = socket.emit_async("tick", i, Duration::from_secs(1)).await {
+    ///             println!("client too slow: {e}");
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub async fn emit_async<T: Serialize>(
+        &self,
+        event: impl Into<Cow<'static, str>>,
+        data: T,
+        timeout: Duration,
+    ) -> Result<(), SendError<T>> {
+        if !self.connected() {
+            return Err(SendError::Socket(SocketError::Closed(data)));
+        }
+
+        let permit = match self.reserve_async(timeout).await {
             Ok(permit) => permit,
             Err(e) => {
                 #[cfg(feature = "tracing")]
@@ -322,12 +519,21 @@ impl<A: Adapter> Socket<A> {
             }
         };
 
-        let ns = self.ns();
-        let data = serde_json::to_value(data)?;
-        permit.send(Packet::event(ns, event.into(), data));
+        let packet = EncodedPacket::event(self.ns(), &event.into(), &data, Vec::new())?;
+        permit.send(packet);
         Ok(())
     }
 
+    /// Returns the number of messages that can still be buffered for this socket before its buffer is full.
+    ///
+    /// Compared to the [`SocketIoBuilder::max_buffer_size`] option,
+    /// it gives a watermark that producers can use to adapt their rate to the client.
+    ///
+    /// [`SocketIoBuilder::max_buffer_size`]: crate::SocketIoBuilder#method.max_buffer_size
+    pub fn buffer_capacity(&self) -> usize {
+        self.esocket.buffer_capacity()
+    }
+
     /// Emits a message to the client and wait for acknowledgement.
     ///
     /// The acknowledgement has a timeout specified in the config (5s by default)
@@ -395,9 +601,8 @@ impl<A: Adapter> Socket<A> {
                 return Err(e.with_value(data).into());
             }
         };
-        let data = serde_json::to_value(data)?;
-        let packet = Packet::event(self.ns(), event.into(), data);
-        let rx = self.send_with_ack_permit(packet, permit);
+        let packet = EncodedPacket::event(self.ns(), &event.into(), &data, Vec::new())?;
+        let rx = self.send_with_ack_permit(&packet, permit);
         let stream = AckInnerStream::send(rx, self.config.ack_timeout, self.id);
         Ok(AckStream::<V>::from(stream))
     }
@@ -523,6 +728,40 @@ impl<A: Adapter> Socket<A> {
         BroadcastOperators::from_sock(self.ns.clone(), self.id).except(rooms)
     }
 
+    /// Selects the clients with the given ids.
+    ///
+    /// Contrary to the `to()` operator, the current socket is not excluded if its id is given.
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use socketioxide::socket::Sid;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("whisper", |socket: SocketRef, Data::<(Vec<Sid>, String)>((sids, msg))| async move {
+    ///         socket.to_sockets(sids).emit("whisper", msg).ok();
+    ///     });
+    /// });
+    pub fn to_sockets(&self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        BroadcastOperators::from_sock(self.ns.clone(), self.id).to_sockets(sids)
+    }
+
+    /// Filters out the clients with the given ids from the clients selected with the previous operators.
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use socketioxide::socket::Sid;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("message", |socket: SocketRef, Data::<(Vec<Sid>, String)>((muted, msg))| async move {
+    ///         // This message will be broadcast to all clients in the Namespace
+    ///         // except for the muted ones and the current socket
+    ///         socket.except_sockets(muted).emit("message", msg).ok();
+    ///     });
+    /// });
+    pub fn except_sockets(&self, sids: impl IntoIterator<Item = Sid>) -> BroadcastOperators<A> {
+        BroadcastOperators::from_sock(self.ns.clone(), self.id).except_sockets(sids)
+    }
+
     /// Broadcasts to all clients only connected on this node (when using multiple nodes).
     /// When using the default in-memory [`LocalAdapter`], this operator is a no-op.
     /// # Example
@@ -618,7 +857,9 @@ impl<A: Adapter> Socket<A> {
     ///
     /// It will also call the disconnect handler if it is set.
     pub fn disconnect(self: Arc<Self>) -> Result<(), DisconnectError> {
-        let res = self.send(Packet::disconnect(&self.ns.path));
+        let res = self
+            .reserve_control()
+            .map(|permit| permit.send(Packet::disconnect(&self.ns.path)));
         if let Err(SocketError::InternalChannelFull(_)) = res {
             return Err(DisconnectError::InternalChannelFull);
         }
@@ -627,6 +868,51 @@ impl<A: Adapter> Socket<A> {
         Ok(())
     }
 
+    /// Disconnects the socket from the current namespace with a custom reason.
+    ///
+    /// Before the disconnect packet, a [`DISCONNECT_REASON_EVENT`] event is sent to the client
+    /// with a `{ "code": code, "message": message }` object, so that it can be shown to the user.
+    ///
+    /// The disconnect handler is called with a [`DisconnectReason::ServerNSDisconnectWith`] reason.
+    ///
+    /// # Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("ban", |socket: SocketRef| {
+    ///         socket.disconnect_with(4001, "kicked by admin").ok();
+    ///     });
+    /// });
+    /// ```
+    pub fn disconnect_with(
+        self: Arc<Self>,
+        code: u16,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Result<(), DisconnectError> {
+        let message = message.into();
+        let data = serde_json::json!({ "code": code, "message": message });
+        // Value serialization cannot fail
+        let reason =
+            EncodedPacket::event(self.ns(), DISCONNECT_REASON_EVENT, &data, Vec::new()).unwrap();
+
+        // Both permits are reserved first so that the reason is never sent without the disconnect packet.
+        // They go through the control lane to be sent after the packets emitted before, even if the buffer is full.
+        let permits = self
+            .reserve_control()
+            .and_then(|reason_permit| Ok((reason_permit, self.reserve_control()?)));
+        let res = permits.map(|(reason_permit, permit)| {
+            reason_permit.send(reason);
+            permit.send(Packet::disconnect(&self.ns.path));
+        });
+        if let Err(SocketError::InternalChannelFull(_)) = res {
+            return Err(DisconnectError::InternalChannelFull);
+        }
+
+        self.close(DisconnectReason::ServerNSDisconnectWith { code, message })?;
+        Ok(())
+    }
+
     /// Closes the engine.io connection if it is not already closed.
     /// Return a future that resolves when the underlying transport is closed.
     pub(crate) async fn close_underlying_transport(&self) {
@@ -642,6 +928,18 @@ impl<A: Adapter> Socket<A> {
         self.connected.store(connected, Ordering::SeqCst);
     }
 
+    pub(crate) fn insert_middleware_value<T: Clone + Send + Sync + 'static>(&self, value: T) {
+        self.middleware_values.lock().unwrap().insert(value);
+    }
+
+    pub(crate) fn middleware_value<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.middleware_values.lock().unwrap().get::<T>().cloned()
+    }
+
+    pub(crate) fn clear_middleware_values(&self) {
+        self.middleware_values.lock().unwrap().clear();
+    }
+
     /// Gets the current namespace path.
     #[inline]
     pub fn ns(&self) -> &str {
@@ -649,10 +947,54 @@ impl<A: Adapter> Socket<A> {
     }
 
     pub(crate) fn reserve(&self) -> Result<Permit<'_>, SocketError<()>> {
-        Ok(self.esocket.reserve()?)
+        Ok(self.permit(self.esocket.reserve()?))
     }
 
-    pub(crate) fn send(&self, packet: Packet<'_>) -> Result<(), SocketError<()>> {
+    /// Reserves a slot in the control lane of the socket buffer, for the packets that must not be lost
+    /// when the buffer is full of emitted messages (acknowledgements and disconnections).
+    /// They are still sent after the messages emitted before.
+    pub(crate) fn reserve_control(&self) -> Result<Permit<'_>, SocketError<()>> {
+        Ok(self.permit(self.esocket.reserve_control()?))
+    }
+
+    /// Reserves a slot in the socket buffer to emit a message later,
+    /// waiting at most `timeout` for space if the buffer is full.
+    ///
+    /// Unlike [`Socket::emit_async`], the message is only built once the slot is reserved,
+    /// e.g. a producer can read the next chunk of a file only when the client can receive it.
+    ///
+    /// ## Errors
+    /// * If the socket is closed, a [`SocketError::Closed`] is returned.
+    /// * If the buffer is still full after the timeout, a [`SocketError::InternalChannelFull`] is returned.
+    ///
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| async move {
+    ///     for i in 0..1000 {
+    ///         let Ok(permit) = socket.reserve_async(Duration::from_secs(1)).await else {
+    ///             break;
+    ///         };
+    ///         permit.emit("tick", i).ok();
+    ///     }
+    /// });
+    /// ```
+    pub async fn reserve_async(&self, timeout: Duration) -> Result<Permit<'_>, SocketError<()>> {
+        Ok(self.permit(self.esocket.reserve_async(timeout).await?))
+    }
+
+    fn permit<'a>(&'a self, inner: EIoPermit<'a>) -> Permit<'a> {
+        Permit {
+            inner,
+            sid: self.id,
+            ns: self.ns(),
+            interceptor: self.config.interceptor.as_deref(),
+        }
+    }
+
+    pub(crate) fn send(&self, packet: impl Into<EncodedPacket>) -> Result<(), SocketError<()>> {
         let permit = self.reserve()?;
         permit.send(packet);
         Ok(())
@@ -660,26 +1002,26 @@ impl<A: Adapter> Socket<A> {
 
     pub(crate) fn send_with_ack_permit(
         &self,
-        mut packet: Packet<'_>,
+        packet: &EncodedPacket,
         permit: Permit<'_>,
     ) -> Receiver<AckResult<Value>> {
         let (tx, rx) = oneshot::channel();
 
         let ack = self.ack_counter.fetch_add(1, Ordering::SeqCst) + 1;
-        packet.inner.set_ack_id(ack);
-        permit.send(packet);
-        self.ack_message.lock().unwrap().insert(ack, tx);
+        permit.send(packet.with_ack(ack));
+        let guard = self.dispatcher.await_ack();
+        self.ack_message.lock().unwrap().insert(ack, (tx, guard));
         rx
     }
 
-    pub(crate) fn send_with_ack(&self, mut packet: Packet<'_>) -> Receiver<AckResult<Value>> {
+    pub(crate) fn send_with_ack(&self, packet: &EncodedPacket) -> Receiver<AckResult<Value>> {
         let (tx, rx) = oneshot::channel();
 
         let ack = self.ack_counter.fetch_add(1, Ordering::SeqCst) + 1;
-        packet.inner.set_ack_id(ack);
-        match self.send(packet) {
+        match self.send(packet.with_ack(ack)) {
             Ok(()) => {
-                self.ack_message.lock().unwrap().insert(ack, tx);
+                let guard = self.dispatcher.await_ack();
+                self.ack_message.lock().unwrap().insert(ack, (tx, guard));
             }
             Err(e) => {
                 tx.send(Err(AckError::Socket(e))).ok();
@@ -688,11 +1030,51 @@ impl<A: Adapter> Socket<A> {
         rx
     }
 
+    /// Reports an error to the [`ErrorHandler`](crate::handler::ErrorHandler) of the namespace, or to the global one,
+    /// and sends its reply to the client if any.
+    pub(crate) fn report_error(&self, event: Option<&str>, ack_id: Option<i64>, cause: ErrorCause) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?self.id, ?event, "{cause}");
+
+        let Some(handler) = self
+            .ns
+            .error_handler()
+            .or_else(|| self.config.on_error.clone())
+        else {
+            return;
+        };
+        let err = HandlerError {
+            sid: self.id,
+            ns: self.ns.path.clone(),
+            event: event.map(str::to_owned),
+            ack_id,
+            cause,
+        };
+        // Value serialization cannot fail
+        let res = match handler.call(&err) {
+            Some(ErrorReply::Ack(data)) => match ack_id {
+                Some(ack_id) => self.reserve_control().map(|permit| {
+                    permit.send(EncodedPacket::ack(self.ns(), &data, vec![], ack_id).unwrap())
+                }),
+                None => Ok(()),
+            },
+            Some(ErrorReply::Emit(e, data)) => {
+                self.send(EncodedPacket::event(self.ns(), &e, &data, vec![]).unwrap())
+            }
+            None => Ok(()),
+        };
+        if let Err(_e) = res {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(?self.id, "error sending the error reply: {_e:?}");
+        }
+    }
+
     /// Called when the socket is gracefully disconnected from the server or the client
     ///
     /// It maybe also close when the underlying transport is closed or failed.
     pub(crate) fn close(self: Arc<Self>, reason: DisconnectReason) -> Result<(), AdapterError> {
         self.set_connected(false);
+        self.dispatcher.close();
 
         let handler = { self.disconnect_handler.lock().unwrap().take() };
         if let Some(handler) = handler {
@@ -713,9 +1095,12 @@ impl<A: Adapter> Socket<A> {
             PacketData::EventAck(data, ack_id) => self.recv_ack(data, ack_id),
             PacketData::BinaryEvent(e, packet, ack) => self.recv_bin_event(&e, packet, ack),
             PacketData::BinaryAck(packet, ack) => self.recv_bin_ack(packet, ack),
-            PacketData::Disconnect => self
-                .close(DisconnectReason::ClientNSDisconnect)
-                .map_err(Error::from),
+            PacketData::Disconnect => {
+                if let Err(err) = self.clone().close(DisconnectReason::ClientNSDisconnect) {
+                    self.report_error(None, None, err.into());
+                }
+                Ok(())
+            }
             _ => unreachable!(),
         }
     }
@@ -741,6 +1126,32 @@ impl<A: Adapter> Socket<A> {
         self.esocket.transport_type()
     }
 
+    /// Gets the last round trip time measured between a ping sent to the client and its pong.
+    ///
+    /// It is `None` until the first pong is received, or if the client uses the engine.io protocol v3,
+    /// where the pings are sent by the client.
+    pub fn rtt(&self) -> Option<std::time::Duration> {
+        self.esocket.rtt()
+    }
+
+    /// Gets a snapshot of the [`SocketStats`](crate::SocketStats) of the underlying engine.io connection:
+    /// latency, traffic, buffered packets and transport upgrades.
+    ///
+    /// It can also be accessed as an extractor:
+    /// ```
+    /// # use socketioxide::{SocketIo, SocketStats, extract::*};
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("ping", |socket: SocketRef, stats: SocketStats| {
+    ///         println!("rtt: {:?}, {} bytes received", stats.rtt, stats.bytes_received);
+    ///         assert_eq!(socket.stats().transport_switches, stats.transport_switches);
+    ///     });
+    /// });
+    /// ```
+    pub fn stats(&self) -> crate::SocketStats {
+        self.esocket.stats()
+    }
+
     /// Gets the socket.io [`ProtocolVersion`](crate::ProtocolVersion) used by the client to connect with this [`Socket`]
     ///
     /// It can also be accessed as an extractor:
@@ -756,9 +1167,41 @@ impl<A: Adapter> Socket<A> {
         self.esocket.protocol.into()
     }
 
-    fn recv_event(self: Arc<Self>, e: &str, data: Value, ack: Option<i64>) -> Result<(), Error> {
-        if let Some(handler) = self.message_handlers.read().unwrap().get(e) {
-            handler.call(self.clone(), data, vec![], ack);
+    /// Gets a [`CancellationToken`] that is cancelled when this [`Socket`] is disconnected.
+    ///
+    /// The async handlers keep running after the disconnection, they can use it to stop early.
+    /// It can also be accessed as an extractor:
+    /// ## Example
+    /// ```
+    /// # use socketioxide::{SocketIo, extract::*};
+    /// # use std::time::Duration;
+    /// let (_, io) = SocketIo::new_svc();
+    /// io.ns("/", |socket: SocketRef| {
+    ///     socket.on("watch", |socket: SocketRef, token: CancellationToken| async move {
+    ///         loop {
+    ///             tokio::select! {
+    ///                 _ = token.cancelled() => break,
+    ///                 _ = tokio::time::sleep(Duration::from_secs(1)) => {
+    ///                     socket.emit("tick", ()).ok();
+    ///                 }
+    ///             }
+    ///         }
+    ///     });
+    /// });
+    /// ```
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.dispatcher.cancellation_token().clone()
+    }
+
+    fn recv_event(
+        self: Arc<Self>,
+        e: &str,
+        data: RawPayload,
+        ack: Option<i64>,
+    ) -> Result<(), Error> {
+        if self.message_handlers.read().unwrap().contains_key(e) {
+            let req = EventRequest::new(self.clone(), e.to_string(), data, vec![], ack);
+            self.dispatch(req);
         }
         Ok(())
     }
@@ -766,17 +1209,127 @@ impl<A: Adapter> Socket<A> {
     fn recv_bin_event(
         self: Arc<Self>,
         e: &str,
-        packet: BinaryPacket,
+        packet: BinaryPacket<RawPayload>,
         ack: Option<i64>,
     ) -> Result<(), Error> {
-        if let Some(handler) = self.message_handlers.read().unwrap().get(e) {
-            handler.call(self.clone(), packet.data, packet.bin, ack);
+        if self.message_handlers.read().unwrap().contains_key(e) {
+            let req = EventRequest::new(self.clone(), e.to_string(), packet.data, packet.bin, ack);
+            self.dispatch(req);
         }
         Ok(())
     }
 
+    /// Dispatches an event through the [`EventService`] of the socket
+    fn dispatch(&self, req: EventRequest<A>) {
+        let svc = self.event_service.read().unwrap().clone();
+        svc.dispatch(req);
+    }
+
+    /// Executes a handler future of the given event according to the dispatch mode
+    pub(crate) fn spawn_handler(&self, e: &str, fut: BoxFuture<'static, ()>) {
+        if self.dispatcher.has_in_flight_limit() {
+            // The acknowledgements that timed out are no longer awaited and must not prevent the backpressure
+            self.ack_message
+                .lock()
+                .unwrap()
+                .retain(|_, (tx, _)| !tx.is_closed());
+        }
+        self.dispatcher.spawn(e, fut);
+    }
+
+    /// Spawns a task tracked by the server, outside of the dispatch mode
+    pub(crate) fn spawn_tracked<F>(&self, fut: F)
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.dispatcher.spawn_tracked(fut);
+    }
+
+    /// Returns true if the events can be processed as soon as they are received
+    pub(crate) fn is_concurrent_dispatch(&self) -> bool {
+        self.dispatcher.is_concurrent()
+    }
+
+    pub(crate) fn set_event_service(&self, svc: EventService<A>) {
+        *self.event_service.write().unwrap() = svc;
+    }
+
+    /// Gets the service wrapping the handler of the given event, if a layer was set for it
+    pub(crate) fn event_service(&self, e: &str) -> Option<EventService<A>> {
+        self.event_layers.read().unwrap().get(e).cloned()
+    }
+
+    /// Calls the handler registered for the event of the request.
+    ///
+    /// Extractor errors are reported to the error handler.
+    /// Returns the future of async handlers.
+    pub(crate) fn call_handler(
+        self: Arc<Self>,
+        req: EventRequest<A>,
+    ) -> Option<BoxFuture<'static, ()>> {
+        let (e, data, bin, ack, stream) = req.into_parts();
+        // The lock is released before reporting the errors, so that the error handler can update the handlers
+        let res = {
+            let handlers = self.message_handlers.read().unwrap();
+            let handler = handlers.get(e.as_ref())?;
+            let has_stream = stream.is_some();
+            if let Some(stream) = stream {
+                self.bin_stream.lock().unwrap().replace(stream);
+            }
+            let res = handler.call(self.clone(), data, bin, ack);
+            if has_stream {
+                self.bin_stream.lock().unwrap().take();
+            }
+            res
+        };
+        res.unwrap_or_else(|cause| {
+            self.report_error(Some(&e), ack, cause);
+            None
+        })
+    }
+
+    /// Returns true if the handler of the given event consumes its attachments with a [`BinStream`]
+    pub(crate) fn is_bin_stream_event(&self, e: &str) -> bool {
+        self.message_handlers
+            .read()
+            .unwrap()
+            .get(e)
+            .is_some_and(|handler| handler.is_bin_stream())
+    }
+
+    /// Calls the handler of a binary event before its attachments are received.
+    ///
+    /// Returns the sender half of the stream if attachments are still expected
+    pub(crate) fn recv_bin_stream(
+        self: Arc<Self>,
+        e: &str,
+        packet: BinaryPacket<RawPayload>,
+        ack: Option<i64>,
+    ) -> Option<BinStreamTx> {
+        let (tx, stream) = bin_stream::channel(
+            self.esocket.clone(),
+            packet.payload_count,
+            self.config.max_bin_stream_size,
+        );
+        if self.message_handlers.read().unwrap().contains_key(e) {
+            let req = EventRequest::new(self.clone(), e.to_string(), packet.data, vec![], ack);
+            self.dispatch(req.with_bin_stream(stream));
+        }
+        (packet.payload_count > 0).then_some(tx)
+    }
+
+    /// Takes the stream of attachments of the event being dispatched.
+    /// An empty stream is returned for events without attachments.
+    pub(crate) fn take_bin_stream(&self) -> BinStream {
+        self.bin_stream
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| bin_stream::channel(self.esocket.clone(), 0, 0).1)
+    }
+
     fn recv_ack(self: Arc<Self>, data: Value, ack: i64) -> Result<(), Error> {
-        if let Some(tx) = self.ack_message.lock().unwrap().remove(&ack) {
+        if let Some((tx, _)) = self.ack_message.lock().unwrap().remove(&ack) {
             let res = AckResponse {
                 data,
                 binary: vec![],
@@ -787,7 +1340,7 @@ impl<A: Adapter> Socket<A> {
     }
 
     fn recv_bin_ack(self: Arc<Self>, packet: BinaryPacket, ack: i64) -> Result<(), Error> {
-        if let Some(tx) = self.ack_message.lock().unwrap().remove(&ack) {
+        if let Some((tx, _)) = self.ack_message.lock().unwrap().remove(&ack) {
             let res = AckResponse {
                 data: packet.data,
                 binary: packet.bin,
@@ -824,6 +1377,7 @@ impl<A: Adapter> Socket<A> {
             ns,
             engineioxide::Socket::new_dummy(sid, close_fn),
             Arc::new(SocketIoConfig::default()),
+            TaskTracker::new(),
         );
         s.set_connected(true);
         s
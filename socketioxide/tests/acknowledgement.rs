@@ -3,7 +3,7 @@ mod utils;
 
 use engineioxide::Packet::*;
 use futures_util::StreamExt;
-use socketioxide::extract::SocketRef;
+use socketioxide::extract::{AckSender, Data, SocketRef};
 use socketioxide::packet::{Packet, PacketData};
 use socketioxide::SocketIo;
 use tokio::sync::mpsc;
@@ -121,3 +121,74 @@ pub async fn broadcast_with_ack() {
         }
     }
 }
+
+#[tokio::test]
+pub async fn ack_after_emits() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(8).build_svc();
+    let (tx, mut rx) = mpsc::channel(1);
+    io.ns("/", move |s: SocketRef| {
+        let tx = tx.clone();
+        s.on("test", move |s: SocketRef, ack: AckSender| {
+            for i in 0..5 {
+                assert_ok!(s.emit("data", i));
+            }
+            assert_ok!(tx.try_send(ack.send("ack")));
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+    assert_ok!(stx.send(Message("21[\"test\"]".into())).await);
+    assert_ok!(assert_some!(rx.recv().await));
+
+    // The ack is received after the packets emitted before it
+    let mut packets = Vec::new();
+    while let Ok(Some(msg)) = tokio::time::timeout(Duration::from_millis(50), srx.recv()).await {
+        packets.push(msg);
+    }
+    let mut expected: Vec<_> = (0..5)
+        .map(|i| Message(format!("2[\"data\",{i}]").into()))
+        .collect();
+    expected.push(Message("31[\"ack\"]".into()));
+    assert_eq!(packets, expected);
+}
+
+#[tokio::test]
+pub async fn handler_return_ack() {
+    let (_svc, io) = SocketIo::new_svc();
+    io.ns("/", |s: SocketRef| {
+        s.on("ok", |Data::<u32>(n)| Ok::<_, String>(n * 2));
+        s.on("err", || async move { Err::<(), _>("bad request") });
+        s.on("nothing", || {});
+        s.on("sender", |ack: AckSender| {
+            ack.send("foo").ok();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("21[\"ok\",21]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("31[42]".into()));
+
+    assert_ok!(stx.send(Message("22[\"err\"]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("32[{\"error\":\"bad request\"}]".into()));
+
+    assert_ok!(stx.send(Message("23[\"nothing\"]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(
+        msg,
+        Message("33[{\"error\":\"no acknowledgement returned by the handler\"}]".into())
+    );
+
+    assert_ok!(stx.send(Message("24[\"sender\"]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("34[\"foo\"]".into()));
+
+    // Without ack id, nothing is sent back
+    assert_ok!(stx.send(Message("2[\"ok\",1]".into())).await);
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_err!(srx.try_recv());
+}
@@ -0,0 +1,96 @@
+//! Tests for the broadcast operators selecting sockets by id
+mod utils;
+
+use engineioxide::Packet::*;
+use futures_util::StreamExt;
+use socketioxide::extract::SocketRef;
+use socketioxide::socket::Sid;
+use socketioxide::SocketIo;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+#[tokio::test]
+pub async fn to_and_except_sockets() {
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel(4);
+    io.ns("/", move |socket: SocketRef| {
+        assert_ok!(tx.try_send(socket.id));
+    });
+
+    let mut socks = Vec::new();
+    for _ in 0..3 {
+        let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+        assert_some!(srx.recv().await); // NS connect packet
+        let sid = assert_some!(rx.recv().await);
+        socks.push((sid, stx, srx));
+    }
+    let sids: Vec<_> = socks.iter().map(|(sid, _, _)| *sid).collect();
+
+    assert_ok!(io.to_sockets([sids[0], sids[2]]).emit("test", "foo"));
+    for i in [0, 2] {
+        let msg = assert_some!(socks[i].2.recv().await);
+        assert_eq!(msg, Message("2[\"test\",\"foo\"]".into()));
+    }
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_err!(socks[1].2.try_recv());
+
+    let res = io
+        .except_sockets([sids[0]])
+        .timeout(Duration::from_millis(500))
+        .emit_with_ack::<[String; 1]>("test", "bar");
+    let stream = assert_ok!(res);
+    for (_, stx, srx) in &mut socks[1..] {
+        let msg = assert_some!(srx.recv().await);
+        let Message(msg) = msg else {
+            panic!("unexpected packet: {msg:?}");
+        };
+        let ack_id = msg
+            .strip_prefix('2')
+            .and_then(|msg| msg.strip_suffix("[\"test\",\"bar\"]"))
+            .expect("event packet with an ack id");
+        assert_ok!(
+            stx.send(Message(format!("3{ack_id}[\"rab\"]").into()))
+                .await
+        );
+    }
+    let acks: Vec<_> = stream.collect().await;
+    assert_eq!(acks.len(), 2);
+    for (sid, ack) in acks {
+        assert_ne!(sid, sids[0]);
+        assert_eq!(assert_ok!(ack).data[0], "rab");
+    }
+    assert_err!(socks[0].2.try_recv());
+}
+
+#[tokio::test]
+pub async fn to_empty_sockets() {
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel(4);
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on("empty", move |socket: SocketRef| {
+            // An empty set of ids selects no socket, not even the current one
+            let sockets = assert_ok!(socket.to_sockets(Vec::<Sid>::new()).sockets());
+            assert_ok!(tx.try_send(sockets.len()));
+            assert_ok!(socket.to_sockets([]).emit("test", "foo"));
+        });
+    });
+
+    let mut socks = Vec::new();
+    for _ in 0..2 {
+        let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+        assert_some!(srx.recv().await); // NS connect packet
+        socks.push((stx, srx));
+    }
+
+    assert!(assert_ok!(io.to_sockets([]).sockets()).is_empty());
+    assert_ok!(io.to_sockets(Vec::<Sid>::new()).emit("test", "foo"));
+
+    assert_ok!(socks[0].0.send(Message("2[\"empty\"]".into())).await);
+    assert_eq!(assert_some!(rx.recv().await), 0);
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    for (_, srx) in &mut socks {
+        assert_err!(srx.try_recv());
+    }
+}
@@ -0,0 +1,78 @@
+//! Tests for the cancellation and the tracking of the async handlers
+mod fixture;
+mod utils;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use engineioxide::Packet::*;
+use fixture::{create_server, create_ws_connection};
+use futures_util::SinkExt;
+use socketioxide::extract::{CancellationToken, SocketRef};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+#[tokio::test]
+pub async fn cancelled_on_disconnect() {
+    let (_svc, io) = socketioxide::SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<&'static str>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on("watch", move |token: CancellationToken| async move {
+            tx.send("started").unwrap();
+            token.cancelled().await;
+            tx.send("cancelled").unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"watch\"]".into())).await);
+    assert_eq!(assert_some!(rx.recv().await), "started");
+    assert_err!(rx.try_recv());
+
+    assert_ok!(stx.send(Message("1".into())).await);
+    let msg = tokio::time::timeout(Duration::from_millis(200), rx.recv()).await;
+    assert_eq!(assert_some!(assert_ok!(msg)), "cancelled");
+}
+
+#[tokio::test]
+pub async fn close_and_wait_handlers() {
+    let io = create_server(3900).await;
+    let completed = Arc::new(AtomicBool::new(false));
+    let disconnected = Arc::new(AtomicBool::new(false));
+    let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+    io.ns("/", {
+        let completed = completed.clone();
+        let disconnected = disconnected.clone();
+        move |socket: SocketRef| {
+            let completed = completed.clone();
+            let tx = tx.clone();
+            socket.on("work", move || async move {
+                tx.send(()).unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                completed.store(true, Ordering::SeqCst);
+            });
+            let disconnected = disconnected.clone();
+            socket.on_disconnect(move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                disconnected.store(true, Ordering::SeqCst);
+            });
+        }
+    });
+
+    let mut ws = create_ws_connection(3900).await;
+    assert_ok!(ws.send(WsMessage::Text("42[\"work\"]".into())).await);
+    assert_some!(rx.recv().await);
+
+    let res = tokio::time::timeout(Duration::from_millis(500), io.close_and_wait()).await;
+    assert_ok!(res);
+    assert!(completed.load(Ordering::SeqCst));
+    assert!(disconnected.load(Ordering::SeqCst));
+}
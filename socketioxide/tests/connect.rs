@@ -2,8 +2,12 @@ mod utils;
 
 use bytes::Bytes;
 use engineioxide::Packet::*;
+use serde_json::json;
 use socketioxide::{
-    extract::SocketRef, handler::ConnectHandler, packet::Packet, SendError, SocketError, SocketIo,
+    extract::{MiddlewareValue, SocketRef},
+    handler::ConnectHandler,
+    packet::Packet,
+    ConnectError, SendError, SocketError, SocketIo,
 };
 use tokio::sync::mpsc;
 
@@ -111,6 +115,151 @@ pub async fn connect_middleware_error() {
     assert_err!(rx.try_recv());
 }
 
+#[tokio::test]
+pub async fn connect_middleware_error_with_data() {
+    let (_svc, io) = SocketIo::new_svc();
+    let middleware =
+        || Err::<(), _>(ConnectError::new("Unauthorized").with_data(json!({ "code": 401 })));
+    io.ns(
+        "/",
+        { || -> () { panic!("handler should not be called") } }.with(middleware),
+    );
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+
+    let p = assert_some!(srx.recv().await);
+    let expected = json!({ "message": "Unauthorized", "data": { "code": 401 } });
+    assert_eq!(p, Message(format!("4{expected}").into()));
+}
+
+#[tokio::test]
+pub async fn connect_middleware_value() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct User(String);
+
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel::<(User, usize)>(100);
+
+    let auth = |s: SocketRef| async move { Ok::<_, ConnectError>(User(s.id.to_string())) };
+    let tx1 = tx.clone();
+    let check = move |MiddlewareValue(user): MiddlewareValue<User>| {
+        tx1.try_send((user, 1)).unwrap();
+        Ok::<_, ConnectError>(())
+    };
+    let handler = move |s: SocketRef, MiddlewareValue(user): MiddlewareValue<User>| {
+        assert_eq!(user.0, s.id.to_string());
+        tx.try_send((user, 2)).unwrap();
+    };
+    io.ns("/", handler.with(check).with(auth));
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+    let p = assert_some!(srx.recv().await);
+    assert!(matches!(p, Message(s) if s.starts_with("0")));
+
+    let (user1, i) = timeout_rcv(&mut rx).await;
+    assert_eq!(i, 1);
+    let (user2, i) = timeout_rcv(&mut rx).await;
+    assert_eq!(i, 2);
+    assert_eq!(user1, user2);
+}
+
+#[tokio::test]
+pub async fn connect_middleware_value_not_found() {
+    #[derive(Clone)]
+    struct User;
+
+    let (_svc, io) = SocketIo::new_svc();
+    let middleware = |_: MiddlewareValue<User>| Ok::<_, ConnectError>(());
+    io.ns(
+        "/",
+        { || -> () { panic!("handler should not be called") } }.with(middleware),
+    );
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+
+    let p = assert_some!(srx.recv().await);
+    let expected = json!({ "message": "Middleware value not found" });
+    assert_eq!(p, Message(format!("4{expected}").into()));
+}
+
+#[tokio::test]
+pub async fn global_middleware() {
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel::<&'static str>(100);
+
+    let tx1 = tx.clone();
+    io.use_middleware(move |s: SocketRef| {
+        tx1.try_send("global").unwrap();
+        Ok::<_, ConnectError>(s.ns().to_string())
+    });
+    let tx1 = tx.clone();
+    let middleware = move |MiddlewareValue(ns): MiddlewareValue<String>| {
+        assert_eq!(ns, "/admin");
+        tx1.try_send("admin").unwrap();
+        Ok::<_, ConnectError>(())
+    };
+    let tx1 = tx.clone();
+    io.ns("/", move |MiddlewareValue(ns): MiddlewareValue<String>| {
+        assert_eq!(ns, "/");
+        tx1.try_send("handler /").unwrap();
+    });
+    io.ns(
+        "/admin",
+        { move || tx.try_send("handler /admin").unwrap() }.with(middleware),
+    );
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert!(matches!(assert_some!(srx.recv().await), Message(s) if s.starts_with("0")));
+    assert_eq!(timeout_rcv(&mut rx).await, "global");
+    assert_eq!(timeout_rcv(&mut rx).await, "handler /");
+
+    let (_, mut srx) = io.new_dummy_sock("/admin", ()).await;
+    assert!(matches!(assert_some!(srx.recv().await), Message(s) if s.starts_with("0")));
+    assert_eq!(timeout_rcv(&mut rx).await, "global");
+    assert_eq!(timeout_rcv(&mut rx).await, "admin");
+    assert_eq!(timeout_rcv(&mut rx).await, "handler /admin");
+    assert_err!(rx.try_recv());
+}
+
+#[tokio::test]
+pub async fn global_middleware_error() {
+    let (_svc, io) = SocketIo::new_svc();
+    io.use_middleware(|| Err::<(), _>(ConnectError::new("Forbidden")));
+    let middleware = || -> Result<(), ConnectError> { panic!("middleware should not be called") };
+    io.ns(
+        "/",
+        { || -> () { panic!("handler should not be called") } }.with(middleware),
+    );
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+    let p = assert_some!(srx.recv().await);
+    assert_eq!(p, Message("4{\"message\":\"Forbidden\"}".into()));
+}
+
+#[tokio::test]
+pub async fn builder_global_middleware() {
+    let (tx, mut rx) = mpsc::channel::<&'static str>(4);
+    let tx1 = tx.clone();
+    let (_svc, io) = SocketIo::builder()
+        .use_middleware(move || {
+            tx1.try_send("global 1")
+                .map_err(|e| ConnectError::new(e.to_string()))
+        })
+        .build_svc();
+    let tx2 = tx.clone();
+    io.use_middleware(move || {
+        tx2.try_send("global 2")
+            .map_err(|e| ConnectError::new(e.to_string()))
+    });
+    io.ns("/", move || tx.try_send("handler").unwrap());
+
+    let (_, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+    assert_eq!(timeout_rcv(&mut rx).await, "global 1");
+    assert_eq!(timeout_rcv(&mut rx).await, "global 2");
+    assert_eq!(timeout_rcv(&mut rx).await, "handler");
+}
+
 #[tokio::test]
 async fn remove_ns_from_connect_handler() {
     let (tx, mut rx) = tokio::sync::mpsc::channel::<()>(2);
@@ -7,6 +7,7 @@
 //!
 //! * Client namespace disconnect
 //! * Server namespace disconnect
+//! * Server namespace disconnect with a custom reason
 
 use std::time::Duration;
 
@@ -249,6 +250,55 @@ pub async fn server_ns_close() {
     assert_eq!(data, DisconnectReason::ServerNSDisconnect);
 }
 
+#[tokio::test]
+pub async fn server_ns_disconnect_with() {
+    let (tx, mut rx) = mpsc::channel::<DisconnectReason>(1);
+    let io = create_server(12354).await;
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on_disconnect(move |reason: DisconnectReason| tx.try_send(reason).unwrap());
+        socket.on("kick", |socket: SocketRef| {
+            for i in 0..3 {
+                socket.emit("bye", i).unwrap();
+            }
+            socket.disconnect_with(4001, "kicked by admin").unwrap();
+        });
+    });
+
+    let mut ws = create_ws_connection(12354).await;
+    ws.next().await; // engine.io open packet
+    ws.next().await; // socket.io open packet
+    ws.send(Message::Text(r#"42["kick"]"#.into()))
+        .await
+        .unwrap();
+    // The reason is sent after the messages emitted before it
+    for i in 0..3 {
+        let msg = ws.next().await.unwrap().unwrap();
+        assert_eq!(msg, Message::Text(format!(r#"42["bye",{i}]"#)));
+    }
+    let reason = ws.next().await.unwrap().unwrap();
+    assert_eq!(
+        reason,
+        Message::Text(
+            r#"42["disconnect_reason",{"code":4001,"message":"kicked by admin"}]"#.into()
+        )
+    );
+    let disconnect = ws.next().await.unwrap().unwrap();
+    assert_eq!(disconnect, Message::Text("41".into()));
+
+    let data = tokio::time::timeout(Duration::from_millis(20), rx.recv())
+        .await
+        .expect("timeout waiting for DisconnectReason::ServerNSDisconnectWith")
+        .unwrap();
+    assert_eq!(
+        data,
+        DisconnectReason::ServerNSDisconnectWith {
+            code: 4001,
+            message: "kicked by admin".into()
+        }
+    );
+}
+
 #[tokio::test]
 pub async fn server_ws_closing() {
     let io = create_server(12350).await;
@@ -310,3 +360,44 @@ pub async fn server_http_closing() {
         );
     }
 }
+
+#[tokio::test]
+pub async fn server_ns_disconnect_full_buffer() {
+    let (tx, mut rx) = mpsc::channel::<DisconnectReason>(1);
+    let io = create_server(12355).await;
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on_disconnect(move |reason: DisconnectReason| tx.try_send(reason).unwrap());
+        socket.on("kick", |socket: SocketRef| {
+            // Fill the buffer until it is full
+            while socket.emit("bye", ()).is_ok() {}
+            socket.disconnect().unwrap();
+        });
+    });
+
+    let mut ws = create_ws_connection(12355).await;
+    ws.next().await; // engine.io open packet
+    ws.next().await; // socket.io open packet
+    ws.send(Message::Text(r#"42["kick"]"#.into()))
+        .await
+        .unwrap();
+    // The disconnect packet is not lost and it is sent after all the messages emitted before it
+    let mut count = 0;
+    loop {
+        let msg = ws.next().await.unwrap().unwrap();
+        match msg {
+            Message::Text(msg) if msg == r#"42["bye",null]"# => count += 1,
+            msg => {
+                assert_eq!(msg, Message::Text("41".into()));
+                break;
+            }
+        }
+    }
+    assert!(count > 0);
+
+    let data = tokio::time::timeout(Duration::from_millis(20), rx.recv())
+        .await
+        .expect("timeout waiting for DisconnectReason::ServerNSDisconnect")
+        .unwrap();
+    assert_eq!(data, DisconnectReason::ServerNSDisconnect);
+}
@@ -0,0 +1,242 @@
+//! Tests for the dispatch modes and the in-flight limit of the message handlers
+mod fixture;
+mod utils;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use engineioxide::Packet::*;
+use fixture::{create_ws_connection, spawn_server};
+use futures_util::{SinkExt, StreamExt};
+use socketioxide::{
+    extract::{Data, SocketRef},
+    socket::DisconnectReason,
+    DispatchMode, SocketIo,
+};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+async fn recv_timeout<T>(rx: &mut mpsc::UnboundedReceiver<T>) -> T {
+    let res = tokio::time::timeout(Duration::from_millis(500), rx.recv()).await;
+    assert_some!(assert_ok!(res))
+}
+
+#[tokio::test]
+pub async fn sequential_dispatch() {
+    let (_svc, io) = SocketIo::builder()
+        .dispatch_mode(DispatchMode::Sequential)
+        .build_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx1 = tx.clone();
+        socket.on("slow", move |Data::<String>(data)| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            tx1.send(data).unwrap();
+        });
+        let tx2 = tx.clone();
+        socket.on("fast", move |Data::<String>(data)| {
+            tx2.send(data).unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"slow\",\"a\"]".into())).await);
+    assert_ok!(stx.send(Message("2[\"fast\",\"b\"]".into())).await);
+    assert_ok!(stx.send(Message("2[\"slow\",\"c\"]".into())).await);
+
+    assert_eq!(recv_timeout(&mut rx).await, "a");
+    assert_eq!(recv_timeout(&mut rx).await, "b");
+    assert_eq!(recv_timeout(&mut rx).await, "c");
+}
+
+#[tokio::test]
+pub async fn sequential_per_event_dispatch() {
+    let (_svc, io) = SocketIo::builder()
+        .dispatch_mode(DispatchMode::SequentialPerEvent)
+        .build_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx1 = tx.clone();
+        socket.on(
+            "slow",
+            move |Data::<(String, u64)>((data, ms))| async move {
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                tx1.send(data).unwrap();
+            },
+        );
+        let tx2 = tx.clone();
+        socket.on("fast", move |Data::<String>(data)| {
+            tx2.send(data).unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"slow\",\"a\",50]".into())).await);
+    assert_ok!(stx.send(Message("2[\"slow\",\"b\",0]".into())).await);
+    assert_ok!(stx.send(Message("2[\"fast\",\"c\"]".into())).await);
+
+    // The "fast" event does not wait for the "slow" queue, which stays ordered
+    assert_eq!(recv_timeout(&mut rx).await, "c");
+    assert_eq!(recv_timeout(&mut rx).await, "a");
+    assert_eq!(recv_timeout(&mut rx).await, "b");
+}
+
+#[tokio::test]
+pub async fn ns_dispatch_mode_override() {
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx1 = tx.clone();
+        socket.on("slow", move |Data::<String>(data)| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            tx1.send(data).unwrap();
+        });
+        let tx2 = tx.clone();
+        socket.on("fast", move |Data::<String>(data)| async move {
+            tx2.send(data).unwrap();
+        });
+    });
+    assert!(io.ns_dispatch_mode("/", DispatchMode::Sequential));
+    assert!(!io.ns_dispatch_mode("/unknown", DispatchMode::Sequential));
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"slow\",\"a\"]".into())).await);
+    assert_ok!(stx.send(Message("2[\"fast\",\"b\"]".into())).await);
+
+    assert_eq!(recv_timeout(&mut rx).await, "a");
+    assert_eq!(recv_timeout(&mut rx).await, "b");
+}
+
+#[tokio::test]
+pub async fn sequential_dispatch_panic() {
+    let (_svc, io) = SocketIo::builder()
+        .dispatch_mode(DispatchMode::Sequential)
+        .build_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    io.ns("/", move |socket: SocketRef| {
+        socket.on("panic", || -> () { panic!("handler panic") });
+        let tx = tx.clone();
+        socket.on("test", move |Data::<String>(data)| async move {
+            tx.send(data).unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"panic\"]".into())).await);
+    assert_ok!(stx.send(Message("2[\"test\",\"foo\"]".into())).await);
+    assert_eq!(recv_timeout(&mut rx).await, "foo");
+}
+
+#[tokio::test]
+pub async fn in_flight_limit_backpressure() {
+    let (svc, io) = SocketIo::builder().max_in_flight_handlers(1).build_svc();
+    spawn_server(3800, svc).await;
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let (tx, mut rx) = mpsc::unbounded_channel::<usize>();
+    io.ns("/", move |socket: SocketRef| {
+        let running = running.clone();
+        let tx = tx.clone();
+        socket.on("work", move || async move {
+            let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            tx.send(count).unwrap();
+        });
+    });
+
+    let mut ws = create_ws_connection(3800).await;
+    // The next events are not read from the transport until the running handler completes
+    for _ in 0..4 {
+        assert_ok!(ws.send(WsMessage::Text("42[\"work\"]".into())).await);
+    }
+    for _ in 0..4 {
+        assert_eq!(recv_timeout(&mut rx).await, 1);
+    }
+}
+
+#[tokio::test]
+pub async fn in_flight_limit_ack() {
+    let (svc, io) = SocketIo::builder().max_in_flight_handlers(1).build_svc();
+    spawn_server(3801, svc).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on("work", move |socket: SocketRef| async move {
+            // The transport is not paused while the acknowledgement is awaited
+            let ack = socket.emit_with_ack::<_, [String; 1]>("ask", ()).unwrap();
+            let [data] = assert_ok!(ack.await).data;
+            tx.send(data).unwrap();
+        });
+    });
+
+    let mut ws = create_ws_connection(3801).await;
+    assert_ok!(ws.send(WsMessage::Text("42[\"work\"]".into())).await);
+    loop {
+        let msg = assert_some!(ws.next().await);
+        if assert_ok!(msg)
+            .to_text()
+            .unwrap()
+            .starts_with("421[\"ask\"")
+        {
+            break;
+        }
+    }
+    assert_ok!(ws.send(WsMessage::Text("431[\"done\"]".into())).await);
+    assert_eq!(recv_timeout(&mut rx).await, "done");
+}
+
+#[tokio::test]
+pub async fn in_flight_limit_max_pause() {
+    let (svc, io) = SocketIo::builder()
+        .ping_interval(Duration::from_millis(50))
+        .ping_timeout(Duration::from_millis(50))
+        .max_read_pause(Duration::from_millis(200))
+        .max_in_flight_handlers(1)
+        .build_svc();
+    spawn_server(3802, svc).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<DisconnectReason>();
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        socket.on_disconnect(move |reason: DisconnectReason| tx.send(reason).unwrap());
+        // The handler never completes, reading stays paused
+        socket.on("work", std::future::pending::<()>);
+    });
+
+    let mut ws = create_ws_connection(3802).await;
+    let start = tokio::time::Instant::now();
+    assert_ok!(ws.send(WsMessage::Text("42[\"work\"]".into())).await);
+    // The pongs are not read while reading is paused
+    let pong = tokio::spawn(async move {
+        while let Some(Ok(msg)) = ws.next().await {
+            if msg == WsMessage::Text("2".into())
+                && ws.send(WsMessage::Text("3".into())).await.is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let reason = tokio::time::timeout(Duration::from_millis(1000), rx.recv()).await;
+    assert_eq!(
+        assert_some!(assert_ok!(reason)),
+        DisconnectReason::HeartbeatTimeout
+    );
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    pong.abort();
+}
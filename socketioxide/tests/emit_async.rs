@@ -0,0 +1,144 @@
+//! Tests for the async emit, waiting for space in the socket buffer
+mod utils;
+
+use std::time::Duration;
+
+use engineioxide::Packet::*;
+use socketioxide::{extract::SocketRef, BroadcastError, SendError, SocketError, SocketIo};
+use tokio::sync::mpsc;
+
+#[tokio::test]
+pub async fn emit_async_waits_for_capacity() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(1).build_svc();
+    io.ns("/", |socket: SocketRef| async move {
+        for i in 0..5 {
+            assert_ok!(socket.emit_async("tick", i, Duration::from_secs(1)).await);
+        }
+    });
+
+    let (_stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await);
+    for i in 0..5 {
+        // The client is slower than the producer
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let msg = tokio::time::timeout(Duration::from_millis(200), srx.recv()).await;
+        assert_eq!(
+            assert_some!(assert_ok!(msg)),
+            Message(format!("2[\"tick\",{i}]").into())
+        );
+    }
+}
+
+#[tokio::test]
+pub async fn emit_async_timeout() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(1).build_svc();
+    let (tx, mut rx) = mpsc::channel(1);
+    io.ns("/", move |socket: SocketRef| async move {
+        let res = loop {
+            if let Err(e) = socket
+                .emit_async("tick", (), Duration::from_millis(10))
+                .await
+            {
+                break (e, socket.buffer_capacity());
+            }
+        };
+        assert_ok!(tx.send(res).await);
+    });
+
+    // The client never reads the messages
+    let (_stx, _srx) = io.new_dummy_sock("/", ()).await;
+    let (err, capacity) = assert_some!(rx.recv().await);
+    assert!(matches!(
+        err,
+        SendError::Socket(SocketError::InternalChannelFull(()))
+    ));
+    assert_eq!(capacity, 0);
+}
+
+#[tokio::test]
+pub async fn reserve_async_permit() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(1).build_svc();
+    io.ns("/", |socket: SocketRef| async move {
+        for i in 0..3 {
+            let permit = assert_ok!(socket.reserve_async(Duration::from_secs(1)).await);
+            assert_ok!(permit.emit("tick", i));
+        }
+    });
+
+    let (_stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await);
+    for i in 0..3 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let msg = tokio::time::timeout(Duration::from_millis(200), srx.recv()).await;
+        assert_eq!(
+            assert_some!(assert_ok!(msg)),
+            Message(format!("2[\"tick\",{i}]").into())
+        );
+    }
+}
+
+#[tokio::test]
+pub async fn broadcast_emit_async() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(1).build_svc();
+    let (tx, mut rx) = mpsc::channel(2);
+    io.ns("/", move |socket: SocketRef| {
+        let tx = tx.clone();
+        async move {
+            socket.join("room1").ok();
+            assert_ok!(tx.send(()).await);
+        }
+    });
+
+    let (_stx1, mut srx1) = io.new_dummy_sock("/", ()).await;
+    let (_stx2, mut srx2) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx1.recv().await);
+    assert_some!(srx2.recv().await);
+    assert_some!(rx.recv().await);
+    assert_some!(rx.recv().await);
+
+    let io2 = io.clone();
+    tokio::spawn(async move {
+        for i in 0..3 {
+            assert_ok!(
+                io2.to("room1")
+                    .emit_async("tick", i, Duration::from_secs(1))
+                    .await
+            );
+        }
+    });
+    for i in 0..3 {
+        for srx in [&mut srx1, &mut srx2] {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let msg = tokio::time::timeout(Duration::from_millis(200), srx.recv()).await;
+            assert_eq!(
+                assert_some!(assert_ok!(msg)),
+                Message(format!("2[\"tick\",{i}]").into())
+            );
+        }
+    }
+}
+
+#[tokio::test]
+pub async fn broadcast_emit_async_timeout() {
+    let (_svc, io) = SocketIo::builder().max_buffer_size(1).build_svc();
+    io.ns("/", |socket: SocketRef| async move {
+        socket.join("room1").ok();
+    });
+
+    // The client never reads the messages
+    let (_stx, _srx) = io.new_dummy_sock("/", ()).await;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let res = loop {
+        if let Err(e) = io
+            .to("room1")
+            .emit_async("tick", (), Duration::from_millis(10))
+            .await
+        {
+            break e;
+        }
+    };
+    assert!(matches!(
+        res,
+        BroadcastError::Socket(errors) if matches!(errors[..], [SocketError::InternalChannelFull(())])
+    ));
+}
@@ -0,0 +1,149 @@
+//! Tests for the global and namespace error handlers
+mod utils;
+
+use engineioxide::Packet::*;
+use serde_json::json;
+use socketioxide::{
+    extract::{Data, SocketRef},
+    handler::error::{ErrorCause, ErrorReply, HandlerError},
+    socket::Sid,
+    SocketError, SocketIo,
+};
+use tokio::sync::mpsc;
+
+#[tokio::test]
+pub async fn extract_error_with_ack_reply() {
+    let (tx, mut rx) = mpsc::channel::<(Sid, String, Option<String>, Option<i64>)>(4);
+    let (_svc, io) = SocketIo::builder()
+        .on_error(move |err: &HandlerError| {
+            assert!(matches!(err.cause, ErrorCause::Extract(_)));
+            let data = (err.sid, err.ns.to_string(), err.event.clone(), err.ack_id);
+            tx.try_send(data).unwrap();
+            Some(ErrorReply::Ack(json!({ "error": "invalid data" })))
+        })
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("test", |_: Data<String>| -> () {
+            panic!("handler should not be called");
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("21[\"test\",1]".into())).await);
+    let (_, ns, event, ack_id) = assert_some!(rx.recv().await);
+    assert_eq!(ns, "/");
+    assert_eq!(event.as_deref(), Some("test"));
+    assert_eq!(ack_id, Some(1));
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("31[{\"error\":\"invalid data\"}]".into()));
+
+    // Without ack id, the ack reply is not sent
+    assert_ok!(stx.send(Message("2[\"test\",1]".into())).await);
+    let (_, _, _, ack_id) = assert_some!(rx.recv().await);
+    assert_eq!(ack_id, None);
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    assert_err!(srx.try_recv());
+}
+
+#[tokio::test]
+pub async fn ns_error_handler_overrides_global() {
+    let (_svc, io) = SocketIo::builder()
+        .on_error(|_: &HandlerError| -> Option<ErrorReply> {
+            panic!("global handler should not be called")
+        })
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("test", |_: Data<String>| {});
+    });
+    assert!(io.ns_on_error("/", |err: &HandlerError| {
+        let data = json!({ "event": err.event });
+        Some(ErrorReply::Emit("error".into(), data))
+    }));
+    assert!(!io.ns_on_error("/unknown", |_: &HandlerError| None));
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"test\",{}]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("2[\"error\",{\"event\":\"test\"}]".into()));
+}
+
+#[tokio::test]
+pub async fn ack_error_on_full_buffer() {
+    let (tx, mut rx) = mpsc::channel::<(Option<i64>, bool)>(32);
+    let (_svc, io) = SocketIo::builder()
+        .max_buffer_size(4)
+        .on_error(move |err: &HandlerError| {
+            let full = matches!(
+                err.cause,
+                ErrorCause::Send(SocketError::InternalChannelFull(()))
+            );
+            tx.try_send((err.ack_id, full)).unwrap();
+            None
+        })
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("flood", |socket: SocketRef| {
+            // The emit errors are returned to the handler, not reported
+            while socket.emit("flood", ()).is_ok() {}
+            // The returned acks are sent through the control lane until it is full as well,
+            // then they cannot be sent and they are reported
+            Ok::<_, String>("done")
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    for i in 1..=20 {
+        assert_ok!(stx.send(Message(format!("2{i}[\"flood\"]").into())).await);
+    }
+    let mut errors = Vec::new();
+    while let Ok(Some(err)) =
+        tokio::time::timeout(std::time::Duration::from_millis(50), rx.recv()).await
+    {
+        errors.push(err);
+    }
+    assert!(!errors.is_empty());
+    for (ack_id, full) in errors {
+        assert!(matches!(ack_id, Some(1..=20)));
+        assert!(full);
+    }
+}
+
+#[tokio::test]
+pub async fn error_handler_updates_handlers() {
+    let (tx, mut rx) = mpsc::channel::<ErrorCause>(4);
+    let (_svc, io) = SocketIo::new_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("test", |_: Data<String>| {});
+    });
+    let io2 = io.clone();
+    io.ns_on_error("/", move |err: &HandlerError| {
+        // The message handlers of the socket are not locked while the error handler is called
+        let socket = io2.get_socket(err.sid).unwrap();
+        socket.on("test", |socket: SocketRef, Data::<u32>(n)| {
+            socket.emit("test", n).ok();
+        });
+        None
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"test\",1]".into())).await);
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    assert_ok!(stx.send(Message("2[\"test\",2]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("2[\"test\",2]".into()));
+
+    // The errors can be forwarded to other tasks
+    tokio::spawn(async move { tx.send(ErrorCause::Service("error".into())).await })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_some!(rx.recv().await);
+}
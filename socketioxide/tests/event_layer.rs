@@ -0,0 +1,142 @@
+//! Tests for the tower layers wrapping the message handlers
+mod utils;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use engineioxide::Packet::*;
+use serde_json::json;
+use socketioxide::{
+    extract::{Data, SocketRef},
+    handler::{
+        error::{ErrorCause, ErrorReply, HandlerError},
+        EventRequest,
+    },
+    SocketIo,
+};
+use tokio::sync::mpsc;
+use tower::{limit::ConcurrencyLimitLayer, timeout::TimeoutLayer, Layer, Service};
+
+/// A layer sending the name of each event to a channel
+#[derive(Clone)]
+struct EventLogLayer(mpsc::UnboundedSender<String>);
+#[derive(Clone)]
+struct EventLog<S>(S, mpsc::UnboundedSender<String>);
+
+impl<S> Layer<S> for EventLogLayer {
+    type Service = EventLog<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        EventLog(inner, self.0.clone())
+    }
+}
+impl<S: Service<EventRequest>> Service<EventRequest> for EventLog<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+    fn call(&mut self, req: EventRequest) -> Self::Future {
+        self.1.send(req.event().to_string()).unwrap();
+        self.0.call(req)
+    }
+}
+
+#[tokio::test]
+pub async fn event_timeout_layer() {
+    let (_svc, io) = SocketIo::builder()
+        .on_error(|err: &HandlerError| {
+            assert!(matches!(err.cause, ErrorCause::Service(_)));
+            Some(ErrorReply::Ack(json!({ "error": err.cause.to_string() })))
+        })
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        let handler = |Data::<u64>(ms): Data<u64>| async move {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok::<_, std::convert::Infallible>(ms)
+        };
+        let timeout = TimeoutLayer::new(Duration::from_millis(50));
+        socket.on_with_layer("slow", handler, timeout);
+        socket.on("slow_no_layer", handler);
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("21[\"slow\",10]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("31[10]".into()));
+
+    assert_ok!(stx.send(Message("22[\"slow\",200]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(
+        msg,
+        Message("32[{\"error\":\"event service error: request timed out\"}]".into())
+    );
+
+    assert_ok!(stx.send(Message("23[\"slow_no_layer\",100]".into())).await);
+    let msg = assert_some!(srx.recv().await);
+    assert_eq!(msg, Message("33[100]".into()));
+}
+
+#[tokio::test]
+pub async fn socket_concurrency_limit_layer() {
+    let (_svc, io) = SocketIo::new_svc();
+    let running = Arc::new(AtomicUsize::new(0));
+    let (tx, mut rx) = mpsc::unbounded_channel::<usize>();
+    io.ns("/", move |socket: SocketRef| {
+        socket.event_layer(ConcurrencyLimitLayer::new(1));
+        let running = running.clone();
+        let tx = tx.clone();
+        socket.on("work", move || async move {
+            let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            tx.send(count).unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    for _ in 0..4 {
+        assert_ok!(stx.send(Message("2[\"work\"]".into())).await);
+    }
+    for _ in 0..4 {
+        let count = tokio::time::timeout(Duration::from_millis(500), rx.recv()).await;
+        assert_eq!(assert_some!(assert_ok!(count)), 1);
+    }
+}
+
+#[tokio::test]
+pub async fn global_event_layer() {
+    let (_svc, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let (etx, mut erx) = mpsc::unbounded_channel::<String>();
+    io.event_layer(EventLogLayer(tx));
+    io.ns("/", move |socket: SocketRef| {
+        let etx = etx.clone();
+        socket.on("test", move |Data::<String>(data)| {
+            etx.send(data).unwrap();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    assert_ok!(stx.send(Message("2[\"test\",\"foo\"]".into())).await);
+    assert_eq!(assert_some!(rx.recv().await), "test");
+    assert_eq!(assert_some!(erx.recv().await), "foo");
+
+    // Events without handler are not dispatched
+    assert_ok!(stx.send(Message("2[\"unknown\",\"bar\"]".into())).await);
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_err!(rx.try_recv());
+}
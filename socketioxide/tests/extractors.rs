@@ -2,11 +2,11 @@
 use std::time::Duration;
 
 use serde_json::json;
-use socketioxide::extract::{Data, SocketRef, State, TryData};
+use socketioxide::extract::{Arg, Data, DataArgs, SocketRef, State, TryData, TryDataArgs};
 use tokio::sync::mpsc;
 
 use engineioxide::Packet as EioPacket;
-use socketioxide::packet::Packet;
+use socketioxide::packet::{Args, Packet};
 use socketioxide::SocketIo;
 mod fixture;
 mod utils;
@@ -118,3 +118,185 @@ pub async fn try_data_extractor() {
     assert_ok!(stx.try_send(create_msg("/", "test", json!({ "test": 132 }))));
     assert_err!(timeout_rcv(&mut rx).await);
 }
+
+#[tokio::test]
+pub async fn data_args_extractor() {
+    let (_, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel::<(String, Vec<u8>)>(4);
+    let (try_tx, mut try_rx) = mpsc::channel::<Result<(u8,), serde_json::Error>>(4);
+    io.ns("/", move |s: SocketRef| {
+        s.on(
+            "test",
+            move |DataArgs(data): DataArgs<(String, Vec<u8>)>| {
+                assert_ok!(tx.try_send(data));
+            },
+        );
+        s.on("try", move |TryDataArgs(data): TryDataArgs<(u8,)>| {
+            assert_ok!(try_tx.try_send(data));
+        });
+    });
+    let (stx, _rtx) = io.new_dummy_sock("/", ()).await;
+
+    assert_ok!(stx.try_send(create_msg("/", "test", json!(["foo", [1, 2]]))));
+    assert_eq!(timeout_rcv(&mut rx).await, ("foo".into(), vec![1, 2]));
+
+    // A single argument is still deserialized as a sequence
+    assert_ok!(stx.try_send(create_msg("/", "try", 1)));
+    assert_eq!(assert_ok!(timeout_rcv(&mut try_rx).await), (1,));
+
+    assert_ok!(stx.try_send(create_msg("/", "try", json!([1, 2]))));
+    assert_err!(timeout_rcv(&mut try_rx).await);
+}
+
+#[tokio::test]
+pub async fn arg_extractor() {
+    let (_, io) = SocketIo::new_svc();
+    let (tx, mut rx) = mpsc::channel::<(i32, String)>(4);
+    io.ns("/", move |s: SocketRef| {
+        s.on(
+            "test",
+            move |Arg(x): Arg<0, i32>, Arg(meta): Arg<2, String>| {
+                assert_ok!(tx.try_send((x, meta)));
+            },
+        );
+        s.on(
+            "echo",
+            |s: SocketRef, DataArgs(data): DataArgs<Vec<Vec<i32>>>| {
+                assert_ok!(s.emit("echo", Args(data)));
+            },
+        );
+    });
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    srx.recv().await;
+
+    assert_ok!(stx.try_send(create_msg("/", "test", json!([1, 2, "meta"]))));
+    assert_eq!(timeout_rcv(&mut rx).await, (1, "meta".into()));
+
+    // Missing argument, the handler should not be called
+    assert_ok!(stx.try_send(create_msg("/", "test", json!([1, 2]))));
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(rx.try_recv().is_err());
+
+    // A single array argument is echoed as is
+    assert_ok!(stx.try_send(create_msg("/", "echo", json!([[1, 2]]))));
+    assert_eq!(
+        timeout_rcv(&mut srx).await,
+        EioPacket::Message(r#"2["echo",[1,2]]"#.into())
+    );
+}
+
+#[tokio::test]
+pub async fn bin_stream_extractor() {
+    use bytes::Bytes;
+    use futures_util::StreamExt;
+    use socketioxide::{extract::BinStream, BinStreamError};
+
+    let (_, io) = SocketIo::builder().max_bin_stream_size(5).build_svc();
+    let (tx, mut rx) = mpsc::channel::<Result<Bytes, BinStreamError>>(8);
+    let (name_tx, mut name_rx) = mpsc::channel::<String>(4);
+    io.ns("/", move |s: SocketRef| {
+        let tx = tx.clone();
+        let name_tx = name_tx.clone();
+        s.on(
+            "upload",
+            move |Data::<String>(name), mut stream: BinStream| async move {
+                assert_ok!(name_tx.try_send(name));
+                while let Some(chunk) = stream.next().await {
+                    assert_ok!(tx.send(chunk).await);
+                }
+            },
+        );
+    });
+    let (stx, _rtx) = io.new_dummy_sock("/", ()).await;
+    let placeholder = |num: usize| json!({ "_placeholder": true, "num": num });
+    let upload = |count: usize| {
+        let args: Vec<_> = std::iter::once(json!("upload"))
+            .chain(std::iter::once(json!("file")))
+            .chain((0..count).map(placeholder))
+            .collect();
+        EioPacket::Message(format!("5{count}-{}", json!(args)).into())
+    };
+
+    // The handler is called before the attachments are received
+    assert_ok!(stx.try_send(upload(2)));
+    assert_eq!(timeout_rcv(&mut name_rx).await, "file");
+    assert_ok!(stx.try_send(EioPacket::Binary(vec![1, 2].into())));
+    assert_eq!(timeout_rcv(&mut rx).await, Ok(Bytes::from_static(&[1, 2])));
+    assert_ok!(stx.try_send(EioPacket::Binary(vec![3].into())));
+    assert_eq!(timeout_rcv(&mut rx).await, Ok(Bytes::from_static(&[3])));
+
+    // The next attachments are dropped once the limit is exceeded
+    assert_ok!(stx.try_send(upload(3)));
+    assert_eq!(timeout_rcv(&mut name_rx).await, "file");
+    assert_ok!(stx.try_send(EioPacket::Binary(vec![1, 2, 3].into())));
+    assert_ok!(stx.try_send(EioPacket::Binary(vec![4, 5, 6].into())));
+    assert_ok!(stx.try_send(EioPacket::Binary(vec![7].into())));
+    assert_eq!(
+        timeout_rcv(&mut rx).await,
+        Ok(Bytes::from_static(&[1, 2, 3]))
+    );
+    assert_eq!(
+        timeout_rcv(&mut rx).await,
+        Err(BinStreamError::LimitExceeded)
+    );
+
+    // Regular messages are handled again after the last attachment
+    assert_ok!(stx.try_send(create_msg("/", "upload", "other")));
+    assert_eq!(timeout_rcv(&mut name_rx).await, "other");
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+pub async fn bin_stream_not_consumed() {
+    use futures_util::{SinkExt, StreamExt};
+    use socketioxide::{extract::BinStream, socket::DisconnectReason};
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    let (svc, io) = SocketIo::builder()
+        .ping_interval(Duration::from_millis(50))
+        .ping_timeout(Duration::from_millis(50))
+        .max_read_pause(Duration::from_millis(200))
+        .build_svc();
+    fixture::spawn_server(3803, svc).await;
+
+    let (tx, mut rx) = mpsc::channel::<DisconnectReason>(1);
+    let (stream_tx, mut stream_rx) = mpsc::channel::<BinStream>(1);
+    io.ns("/", move |s: SocketRef| {
+        let tx = tx.clone();
+        let stream_tx = stream_tx.clone();
+        s.on_disconnect(move |reason: DisconnectReason| assert_ok!(tx.try_send(reason)));
+        // The stream outlives the handler and it is never consumed
+        s.on("upload", move |stream: BinStream| {
+            assert_ok!(stream_tx.try_send(stream));
+        });
+    });
+
+    let mut ws = fixture::create_ws_connection(3803).await;
+    let placeholders: Vec<_> = (0..10)
+        .map(|num| json!({ "_placeholder": true, "num": num }))
+        .collect();
+    let packet = format!("4510-{}", json!(["upload", placeholders]));
+    assert_ok!(ws.send(WsMessage::Text(packet)).await);
+    let _stream = timeout_rcv(&mut stream_rx).await;
+    // Reading is paused once the stream buffer is full, the pongs are not read either
+    for i in 0..10u8 {
+        assert_ok!(ws.send(WsMessage::Binary(vec![i])).await);
+    }
+    let pong = tokio::spawn(async move {
+        while let Some(Ok(msg)) = ws.next().await {
+            if msg == WsMessage::Text("2".into())
+                && ws.send(WsMessage::Text("3".into())).await.is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let reason = tokio::time::timeout(Duration::from_millis(1000), rx.recv()).await;
+    assert_eq!(
+        assert_some!(assert_ok!(reason)),
+        DisconnectReason::HeartbeatTimeout
+    );
+    pong.abort();
+}
@@ -0,0 +1,33 @@
+//! Tests for the response header hooks and the sticky-session cookie
+use http::{header::SET_COOKIE, request::Parts, HeaderMap, HeaderValue};
+use socketioxide::{CookieConfig, SocketIo};
+
+mod fixture;
+
+use fixture::spawn_server;
+
+#[tokio::test]
+pub async fn header_hooks() {
+    let (svc, io) = SocketIo::builder()
+        .cookie(CookieConfig::new())
+        .on_initial_headers(|headers: &mut HeaderMap, _: &Parts| {
+            headers.insert("x-initial", HeaderValue::from_static("true"));
+        })
+        .on_headers(|headers: &mut HeaderMap, req: &Parts| {
+            headers.insert("x-method", req.method.as_str().parse().unwrap());
+        })
+        .build_svc();
+    io.ns("/", || {});
+    spawn_server(4100, svc).await;
+
+    let (_ws, res) = tokio_tungstenite::connect_async(
+        "ws://127.0.0.1:4100/socket.io/?EIO=4&transport=websocket",
+    )
+    .await
+    .unwrap();
+    let headers = res.headers();
+    assert_eq!(headers.get("x-initial").unwrap(), "true");
+    assert_eq!(headers.get("x-method").unwrap(), "GET");
+    let cookie = headers.get(SET_COOKIE).unwrap().to_str().unwrap();
+    assert!(cookie.starts_with("io="), "{cookie}");
+}
@@ -0,0 +1,178 @@
+//! Tests for the raw frame interceptor
+mod utils;
+
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use engineioxide::Packet::*;
+use socketioxide::{
+    extract::{Bin, Data, SocketRef},
+    interceptor::{Interceptor, Str},
+    socket::Sid,
+    SocketIo,
+};
+use tokio::sync::mpsc;
+
+/// Records every frame with its direction
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+impl Interceptor for Recorder {
+    fn on_message(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        self.0.lock().unwrap().push(format!("<- {msg}"));
+        Some(msg)
+    }
+    fn on_binary(&self, _sid: Sid, data: Bytes) -> Bytes {
+        self.0.lock().unwrap().push(format!("<- {data:?}"));
+        data
+    }
+    fn on_emit(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        self.0.lock().unwrap().push(format!("-> {msg}"));
+        Some(msg)
+    }
+    fn on_emit_binary(&self, _sid: Sid, data: Bytes) -> Bytes {
+        self.0.lock().unwrap().push(format!("-> {data:?}"));
+        data
+    }
+}
+
+/// Handles the frames prefixed with `custom:` and hides the `secret` events
+struct CustomFraming(mpsc::UnboundedSender<String>);
+impl Interceptor for CustomFraming {
+    fn on_message(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        match msg.strip_prefix("custom:") {
+            Some(frame) => {
+                self.0.send(frame.to_string()).unwrap();
+                None
+            }
+            None => Some(msg),
+        }
+    }
+    fn on_emit(&self, _sid: Sid, msg: Str) -> Option<Str> {
+        (!msg.contains("\"secret\"")).then_some(msg)
+    }
+}
+
+/// Redacts the attachments received from and emitted to the clients
+struct Redact;
+impl Interceptor for Redact {
+    fn on_binary(&self, _sid: Sid, _: Bytes) -> Bytes {
+        Bytes::from_static(b"in")
+    }
+    fn on_emit_binary(&self, _sid: Sid, data: Bytes) -> Bytes {
+        match &data[..] {
+            b"in" => Bytes::from_static(b"out"),
+            _ => data,
+        }
+    }
+}
+
+#[tokio::test]
+pub async fn interceptor_records_frames() {
+    let recorder = Recorder::default();
+    let (_svc, io) = SocketIo::builder()
+        .interceptor(recorder.clone())
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on(
+            "echo",
+            |socket: SocketRef, Data::<String>(data), Bin(bin)| {
+                socket.bin(bin).emit("echo", data).ok();
+            },
+        );
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    let connect = assert_some!(srx.recv().await);
+
+    assert_ok!(
+        stx.send(Message(
+            "51-[\"echo\",\"foo\",{\"_placeholder\":true,\"num\":0}]".into()
+        ))
+        .await
+    );
+    assert_ok!(stx.send(Binary(Bytes::from_static(&[1, 2]))).await);
+    assert_eq!(
+        assert_some!(srx.recv().await),
+        Message("51-[\"echo\",\"foo\",{\"_placeholder\":true,\"num\":0}]".into())
+    );
+    assert_eq!(
+        assert_some!(srx.recv().await),
+        Binary(Bytes::from_static(&[1, 2]))
+    );
+
+    let Message(connect) = connect else {
+        panic!("expected a connect packet");
+    };
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "<- 0null".to_string(),
+            format!("-> {connect}"),
+            "<- 51-[\"echo\",\"foo\",{\"_placeholder\":true,\"num\":0}]".to_string(),
+            "<- b\"\\x01\\x02\"".to_string(),
+            "-> 51-[\"echo\",\"foo\",{\"_placeholder\":true,\"num\":0}]".to_string(),
+            "-> b\"\\x01\\x02\"".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+pub async fn interceptor_custom_framing() {
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let (_svc, io) = SocketIo::builder()
+        .interceptor(CustomFraming(tx))
+        .build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("ping", |socket: SocketRef| {
+            socket.emit("secret", "hidden").ok();
+            socket.emit("pong", ()).ok();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    // Not a socket.io packet, the socket would be closed if it was decoded
+    assert_ok!(stx.send(Message("custom:hello".into())).await);
+    assert_eq!(assert_some!(rx.recv().await), "hello");
+
+    assert_ok!(stx.send(Message("2[\"ping\"]".into())).await);
+    assert_eq!(
+        assert_some!(srx.recv().await),
+        Message("2[\"pong\",null]".into())
+    );
+}
+
+#[tokio::test]
+pub async fn interceptor_keeps_attachments() {
+    let (_svc, io) = SocketIo::builder().interceptor(Redact).build_svc();
+    io.ns("/", |socket: SocketRef| {
+        socket.on("echo", |socket: SocketRef, Bin(bin)| {
+            socket.bin(bin).emit("echo", ()).ok();
+        });
+    });
+
+    let (stx, mut srx) = io.new_dummy_sock("/", ()).await;
+    assert_some!(srx.recv().await); // NS connect packet
+
+    let packet =
+        "52-[\"echo\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]";
+    assert_ok!(stx.send(Message(packet.into())).await);
+    assert_ok!(stx.send(Binary(Bytes::from_static(&[1]))).await);
+    assert_ok!(stx.send(Binary(Bytes::from_static(&[2]))).await);
+
+    // Every attachment is replaced, none is lost
+    assert_eq!(
+        assert_some!(srx.recv().await),
+        Message(
+            "52-[\"echo\",null,{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]"
+                .into()
+        )
+    );
+    for _ in 0..2 {
+        assert_eq!(
+            assert_some!(srx.recv().await),
+            Binary(Bytes::from_static(b"out"))
+        );
+    }
+}